@@ -0,0 +1,42 @@
+Feature: alias
+  The `[alias]` table of the cli.toml defines shortcuts for long
+  invocations, they are expanded before the arguments are parsed.
+
+Background:
+  Given a directory named ".delivery"
+  And a file named ".delivery/cli.toml" with:
+    """
+    server = "automate.mycompany.com"
+    enterprise = "Foobar"
+    organization = "Engineering"
+    project = "skunkworks"
+
+    [alias]
+    ou = "open users"
+    oup = "ou --print"
+    loop1 = "loop2"
+    loop2 = "loop1 --print"
+    """
+
+Scenario: expand a chain of aliases
+  When I successfully run `delivery oup`
+  Then the output should contain "https://automate.mycompany.com/e/Foobar/#/users"
+
+Scenario: extra arguments are appended to the alias
+  When I successfully run `delivery ou --print`
+  Then the output should contain "https://automate.mycompany.com/e/Foobar/#/users"
+
+Scenario: detect alias loops
+  When I run `delivery loop1`
+  Then the exit status should be 1
+  And the output should contain "Alias loop detected: loop1 -> loop2 -> loop1"
+
+Scenario: delivery help lists the aliases
+  When I run `delivery help`
+  Then the output should contain "ALIASES:"
+  And the output should match /oup\s+ou --print/
+
+Scenario: delivery without a subcommand lists the aliases
+  When I run `delivery`
+  Then the exit status should be 1
+  And the output should contain "ALIASES:"
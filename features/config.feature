@@ -0,0 +1,84 @@
+Feature: config
+  The `config show` command prints the effective configuration that
+  other commands will use, along with the source of every value. This
+  way we know if a value came from a cli.toml, a profile, an environment
+  variable, a command line option or the built-in defaults.
+
+Background:
+  Given a directory named ".delivery"
+  And a file named ".delivery/cli.toml" with:
+    """
+    server = "delivery.mycompany.com"
+    enterprise = "Foobar"
+
+    [profiles.staging]
+    server = "staging.mycompany.com"
+    """
+
+Scenario: config show with values from the cli.toml and the defaults
+  When I successfully run `delivery config show`
+  Then the output should match /server\s+= "delivery.mycompany.com"\s+# .*\.delivery\/cli.toml:1/
+  And the output should match /enterprise\s+= "Foobar"\s+# .*\.delivery\/cli.toml:2/
+  And the output should match /git_port\s+= "8989"\s+# default/
+  And the output should match /user\s+= \(not set\)/
+
+Scenario: config show with a profile, an environment variable and a flag
+  Given I set the environment variables to:
+    | variable        | value  |
+    | DELIVERY_USER   | alice  |
+  When I successfully run `delivery config show --profile staging --org Engineering`
+  Then the output should match /server\s+= "staging.mycompany.com"\s+# profile 'staging' \(.*\.delivery\/cli.toml:5\)/
+  And the output should match /user\s+= "alice"\s+# env DELIVERY_USER/
+  And the output should match /organization\s+= "Engineering"\s+# command line option/
+
+Scenario: config show in JSON format
+  When I successfully run `delivery config show --json`
+  Then the output should contain:
+    """
+      "git_port": {
+        "source": {
+          "type": "default"
+        },
+        "value": "8989"
+      },
+    """
+
+Scenario: config validate with a valid configuration
+  When I successfully run `delivery config validate`
+  Then the output should contain "Your configuration is valid"
+
+Scenario: config validate with unknown keys and invalid values
+  Given a file named ".delivery/cli.toml" with:
+    """
+    server = "delivery.mycompany.com"
+    enterprize = "Foobar"
+    api_protocol = "ftp"
+    git_port = 8989
+    """
+  When I run `delivery config validate`
+  Then the exit status should be 1
+  And the output should match /cli.toml:2: unknown key 'enterprize', did you mean 'enterprise'\?/
+  And the output should match /cli.toml:3: api_protocol must be one of http, https, found "ftp"/
+  And the output should match /cli.toml:4: git_port must be a quoted string/
+
+Scenario: config validate with inconsistent options
+  Given a file named ".delivery/cli.toml" with:
+    """
+    server = "delivery.mycompany.com"
+    fips = true
+    """
+  When I run `delivery config validate`
+  Then the exit status should be 1
+  And the output should contain "fips is enabled but fips_git_port is not set"
+
+Scenario: strict mode validates the configuration on every command
+  Given a file named ".delivery/cli.toml" with:
+    """
+    server = "delivery.mycompany.com"
+    strict = true
+    api_protocol = "ftp"
+    """
+  When I run `delivery token`
+  Then the exit status should be 1
+  And the output should contain "Your cli.toml configuration is invalid"
+  And the output should match /cli.toml:3: api_protocol must be one of http, https/
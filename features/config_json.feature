@@ -0,0 +1,136 @@
+Feature: config-json
+  The `config-json validate` command checks the .delivery/config.json
+  against the JSON Schema of its version and reports every error along
+  with its JSON path, line and column.
+
+Scenario: a valid config.json
+  Given a file named ".delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": {
+        "name": "build_cookbook",
+        "path": ".delivery/build_cookbook"
+      },
+      "skip_phases": ["smoke"],
+      "delivery-truck": { "lint": { "enable_cookstyle": true } }
+    }
+    """
+  When I successfully run `delivery config-json validate`
+  Then the output should contain "Your config.json is valid"
+
+Scenario: typos and conflicting build_cookbook sources
+  Given a file named ".delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": {
+        "name": "build_cookbook",
+        "path": ".delivery/build_cookbook",
+        "git": "https://github.com/example/build_cookbook.git"
+      },
+      "skip_phase": ["smoke"]
+    }
+    """
+  When I run `delivery config-json validate`
+  Then the exit status should be 1
+  And the output should match /config.json:3:3: \$.build_cookbook: the build_cookbook has more than one source \(path, git\)/
+  And the output should match /config.json:8:3: \$.skip_phase: unknown key 'skip_phase', did you mean 'skip_phases'\?/
+  And the output should contain "Found 2 error(s) in your config.json"
+
+Scenario: validate a custom config.json in JSON format
+  Given a file named "custom.json" with:
+    """
+    { "version": "1", "build_cookbook": 42 }
+    """
+  When I run `delivery config-json validate --config-json custom.json --json`
+  Then the exit status should be 1
+  And the output should contain:
+    """
+        "path": "$.build_cookbook",
+        "line": 1,
+        "column": 19,
+        "message": "expected string, found number"
+    """
+
+Scenario: migrate a v1 config.json with --dry-run
+  Given a file named ".delivery/config.json" with:
+    """
+    {
+      "version": "1",
+      "build_cookbook": "./.delivery/build_cookbook",
+      "delivery-truck": { "lint": { "enable_cookstyle": true } }
+    }
+    """
+  When I successfully run `delivery config-json migrate --dry-run`
+  Then the output should contain "-  \"build_cookbook\": \"./.delivery/build_cookbook\","
+  And the output should contain "+  \"version\": \"2\","
+  And the output should contain "Dry run, the config.json was not modified"
+  And the file ".delivery/config.json" should contain "\"version\": \"1\""
+
+Scenario: migrate a custom v1 config.json
+  Given a file named "custom.json" with:
+    """
+    {
+      "version": "1",
+      "build_cookbook": "./.delivery/build_cookbook",
+      "delivery-truck": { "lint": { "enable_cookstyle": true } }
+    }
+    """
+  When I successfully run `delivery config-json migrate -c custom.json`
+  Then the output should contain "Wrote the migrated config.json"
+  And the file "custom.json" should contain "\"version\": \"2\""
+  And the file "custom.json" should contain "\"enable_cookstyle\": true"
+  And the file "custom.json" should contain "\"path\": \"./.delivery/build_cookbook\""
+
+Scenario: migrate a config.json that is already version 2
+  Given a file named "custom.json" with:
+    """
+    { "version": "2", "build_cookbook": { "name": "b", "path": "b" } }
+    """
+  When I successfully run `delivery config-json migrate -c custom.json`
+  Then the output should contain "already version 2"
+
+Scenario: migrate a config.json without a version
+  Given a file named "custom.json" with:
+    """
+    { "build_cookbook": "./.delivery/build_cookbook" }
+    """
+  When I run `delivery config-json migrate -c custom.json`
+  Then the exit status should be 1
+  And the output should contain "version must be \"1\" or \"2\", found no version"
+
+Scenario: a config.json that extends a shared config
+  Given a file named "shared/cookbook.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": {
+        "name": "build_cookbook",
+        "path": ".delivery/build_cookbook"
+      },
+      "delivery-truck": { "lint": { "enable_cookstyle": true } }
+    }
+    """
+  And a file named ".delivery/config.json" with:
+    """
+    {
+      "extends": "../shared/cookbook.json",
+      "skip_phases": ["smoke"]
+    }
+    """
+  When I successfully run `delivery config-json validate`
+  Then the output should contain "Your config.json is valid"
+
+Scenario: a config.json that extends itself
+  Given a file named ".delivery/config.json" with:
+    """
+    { "extends": "base.json", "skip_phases": ["smoke"] }
+    """
+  And a file named ".delivery/base.json" with:
+    """
+    { "extends": "config.json", "version": "2" }
+    """
+  When I run `delivery config-json validate`
+  Then the exit status should be 1
+  And the output should contain "the config.json extends itself"
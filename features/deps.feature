@@ -0,0 +1,110 @@
+Feature: deps
+  The `deps` command reads the `dependencies` of the .delivery/config.json
+  of the projects checked out next to the current one and shows how they
+  depend on each other.
+
+Background:
+  Given a file named ".delivery/cli.toml" with:
+    """
+    organization = "chef"
+    """
+  And a file named "api/.delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": { "name": "build_cookbook", "path": ".delivery/build_cookbook" },
+      "dependencies": ["db"]
+    }
+    """
+  And a file named "db/.delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": { "name": "build_cookbook", "path": ".delivery/build_cookbook" }
+    }
+    """
+  And a file named "web/.delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": { "name": "build_cookbook", "path": ".delivery/build_cookbook" },
+      "dependencies": ["chef/api:release", "ui"]
+    }
+    """
+  And I cd to "web"
+  And I successfully run `git init`
+
+Scenario: showing the dependency tree of a project
+  When I successfully run `delivery deps`
+  Then the output should contain:
+    """
+    web
+    ├── api
+    │   └── db
+    └── ui (not found)
+    """
+
+Scenario: showing the projects that depend on a project
+  Given I cd to "../db"
+  And I successfully run `git init`
+  When I successfully run `delivery deps --reverse`
+  Then the output should contain "Projects that ship if db changes:"
+  And the output should contain "  api"
+  And the output should contain "  web"
+
+Scenario: printing the graph in the DOT format
+  When I successfully run `delivery deps --dot`
+  Then the output should contain:
+    """
+      "web" -> "api";
+    """
+  And the output should contain:
+    """
+      "api" -> "db";
+    """
+
+Scenario: a dependency cycle
+  Given a file named "../db/.delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": { "name": "build_cookbook", "path": ".delivery/build_cookbook" },
+      "dependencies": ["web"]
+    }
+    """
+  When I run `delivery deps`
+  Then the exit status should be 1
+  And the output should contain "Dependency cycle detected: api -> db -> web -> api"
+
+Scenario: projects of another organization are different projects
+  Given a file named "../db/.delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": { "name": "build_cookbook", "path": ".delivery/build_cookbook" },
+      "dependencies": ["ops/api"]
+    }
+    """
+  When I successfully run `delivery deps`
+  Then the output should contain:
+    """
+    web
+    ├── api
+    │   └── db
+    │       └── ops/api (not found)
+    └── ui (not found)
+    """
+
+Scenario: a checkout is named after the project of its delivery remote
+  Given I cd to "../db"
+  And I successfully run `git init`
+  And I successfully run `git remote add delivery ssh://cukes@skunkworks@delivery.mycompany.com:8989/skunkworks/chef/ui`
+  And I cd to "../web"
+  When I successfully run `delivery deps`
+  Then the output should contain:
+    """
+    web
+    ├── api
+    │   └── db (not found)
+    └── ui
+    """
@@ -0,0 +1,58 @@
+Feature: job-dispatch
+  The `job-dispatch explain` command evaluates the job_dispatch filters
+  of the .delivery/config.json against the attributes of a set of nodes
+  and reports the phases that no node is able to run.
+
+Background:
+  Given a file named "ubuntu.json" with:
+    """
+    { "name": "ubuntu-builder", "platform_family": "debian", "platform_version": "14.04" }
+    """
+  And a file named "centos.json" with:
+    """
+    { "automatic": { "platform_family": "rhel", "platform_version": "7.2" } }
+    """
+
+Scenario: every phase has an eligible builder
+  Given a file named ".delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": { "name": "build_cookbook", "path": ".delivery/build_cookbook" },
+      "job_dispatch": {
+        "version": "v2",
+        "filters": {
+          "unit": [
+            { "platform_family": ["debian"], "platform_version": ["14.04"] },
+            { "platform_family": ["rhel"] }
+          ]
+        }
+      }
+    }
+    """
+  When I successfully run `delivery job-dispatch explain ubuntu.json centos.json`
+  Then the output should contain "clause 1: platform_family in [debian] and platform_version in [14.04] => ubuntu-builder"
+  And the output should contain "clause 2: platform_family in [rhel] => centos"
+  And the output should contain "Phase lint: no filters, any node can run it"
+
+Scenario: phases without an eligible builder and malformed filters
+  Given a file named ".delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": { "name": "build_cookbook", "path": ".delivery/build_cookbook" },
+      "skip_phases": ["smoke"],
+      "job_dispatch": {
+        "version": "v2",
+        "filters": {
+          "syntax": [{ "platform_family": ["windows"] }],
+          "lint": [{ "platform_family": "debian" }]
+        }
+      }
+    }
+    """
+  When I run `delivery job-dispatch explain ubuntu.json centos.json`
+  Then the exit status should be 1
+  And the output should contain "Malformed filter $.job_dispatch.filters.lint[0].platform_family: expected a non-empty list of strings"
+  And the output should contain "No eligible builder for: lint, syntax"
+  And the output should not contain "Phase smoke"
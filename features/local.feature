@@ -196,3 +196,137 @@ Scenario: Executing stages that fails should stop on the failed
   And the output should match /Running.*Lint.*Phase/
   And the output should contain "Phase failed with exit code (2)"
   And the exit status should be 1
+
+Scenario: The phases in the skip_phases of the config.json are skipped
+  Given a file named ".delivery/project.toml" with:
+    """
+    [local_phases]
+    lint = "echo running-lint"
+    syntax = "echo running-syntax"
+    unit = "echo running-unit"
+    """
+  And a file named ".delivery/config.json" with:
+    """
+    {
+      "version": "2",
+      "build_cookbook": { "name": "build_cookbook", "path": ".delivery/build_cookbook" },
+      "skip_phases": ["syntax"]
+    }
+    """
+  When I successfully run `delivery local verify`
+  Then the output should contain "running-lint"
+  And the output should contain "running-unit"
+  And the output should not contain "running-syntax"
+  And the output should contain "Skipping the syntax phase"
+  When I successfully run `delivery local syntax --include-skipped`
+  Then the output should contain "running-syntax"
+
+Scenario: A config.json that can't be loaded doesn't skip any phase
+  Given a file named ".delivery/project.toml" with:
+    """
+    [local_phases]
+    lint = "echo running-lint"
+    syntax = "echo running-syntax"
+    unit = "echo running-unit"
+    """
+  And a file named ".delivery/config.json" with:
+    """
+    { "version": "2", "skip_phases": ["syntax"
+    """
+  When I successfully run `delivery local verify`
+  Then the output should contain "running-syntax"
+  And the output should contain "Ignoring the skip_phases of your .delivery/config.json"
+
+Scenario: Local phases with shell syntax, tables and steps
+  Given a file named ".delivery/project.toml" with:
+    """
+    [local_phases]
+    lint = "echo 'quoted  lint' | tr a-z A-Z && echo after-the-pipe"
+
+    [local_phases.syntax]
+    command = "sh"
+    args = ["-c", "echo syntax-in-$(basename $PWD) with $GREETING"]
+    env = { GREETING = "hello" }
+    cwd = ".delivery"
+
+    [local_phases.unit]
+    steps = [
+      { command = "echo", args = ["first-step"] },
+      { command = "sh", args = ["-c", "exit 4"] },
+      { command = "echo", args = ["never-runs"] },
+    ]
+    """
+  When I successfully run `delivery local lint`
+  Then the output should contain "QUOTED  LINT"
+  And the output should contain "after-the-pipe"
+  When I successfully run `delivery local syntax`
+  Then the output should contain "syntax-in-.delivery with hello"
+  When I run `delivery local unit`
+  Then the output should contain "first-step"
+  And the output should not contain "never-runs"
+  And the exit status should be 4
+
+Scenario: A local phase that runs a missing command
+  Given a file named ".delivery/project.toml" with:
+    """
+    [local_phases.unit]
+    command = "delivery-no-such-command"
+    """
+  When I run `delivery local unit`
+  Then the exit status should be 1
+  And the output should contain "Unable to run `delivery-no-such-command`"
+
+Scenario: Running the phases of a stage in parallel
+  Given a file named ".delivery/project.toml" with:
+    """
+    [local_phases]
+    lint = "echo running-lint"
+    syntax = "echo running-syntax && exit 3"
+    unit = "echo running-unit"
+    """
+  When I run `delivery local verify --jobs 3`
+  Then the output should contain "[lint  ] running-lint"
+  And the output should contain "[syntax] running-syntax"
+  And the output should contain "[unit  ] running-unit"
+  And the output should contain "lint passed"
+  And the output should contain "syntax failed with exit code 3"
+  And the output should contain "Phase failed with exit code (3)"
+  And the exit status should be 1
+
+Scenario: Custom phases and stages declared in the project.toml
+  Given a file named ".delivery/project.toml" with:
+    """
+    [local_phases]
+    lint = "echo running-lint"
+    security = "echo running-security"
+
+    [stages]
+    precommit = ["lint", "security"]
+    """
+  When I successfully run `delivery local security`
+  Then the output should match /Running.*security.*Phase/
+  And the output should contain "running-security"
+  When I successfully run `delivery local precommit`
+  Then the output should match /Running.*precommit.*Stage/
+  And the output should contain "running-lint"
+  And the output should contain "running-security"
+  When I run `delivery local docs`
+  Then the output should contain "error: 'docs' isn't a valid value for '<stage_phase>'"
+  And the exit status should be 1
+
+Scenario: Local phases with a timeout and retries
+  Given a file named ".delivery/project.toml" with:
+    """
+    [local_phases.provision]
+    steps = ["echo attempt >> attempts && test $(wc -l < attempts) -ge 2"]
+    retries = 1
+
+    [local_phases.functional]
+    steps = ["sleep 30"]
+    timeout = 1
+    """
+  When I successfully run `delivery local provision`
+  Then the output should contain "Phase failed with exit code (1) on attempt 1 of 2! Retrying in 0 seconds"
+  When I run `delivery local functional`
+  Then the output should contain "Phase timed out!"
+  And the exit status should be 1
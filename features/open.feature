@@ -0,0 +1,37 @@
+Feature: open
+  The `open` command launches the page of the Automate UI that matches
+  the current project, pipeline or change. With `--print` it only
+  displays the URL.
+
+Background:
+  Given a directory named ".delivery"
+  And a file named ".delivery/cli.toml" with:
+    """
+    server = "automate.mycompany.com"
+    enterprise = "Foobar"
+    organization = "Engineering"
+    project = "skunkworks"
+    """
+
+Scenario: print the url of the project
+  When I successfully run `delivery open --print`
+  Then the output should contain "https://automate.mycompany.com/e/Foobar/#/organizations/Engineering/projects/skunkworks"
+
+Scenario: print the url of a pipeline
+  When I successfully run `delivery open pipeline --for release --print`
+  Then the output should contain "https://automate.mycompany.com/e/Foobar/#/organizations/Engineering/projects/skunkworks/pipelines/release"
+
+Scenario: print the url of a change in a stage
+  When I successfully run `delivery open change 1234 --stage verify --print`
+  Then the output should contain "/projects/skunkworks/changes/1234/status/verify"
+
+Scenario: print the url of the users and the dashboard
+  When I successfully run `delivery open users --print`
+  Then the output should contain "https://automate.mycompany.com/e/Foobar/#/users"
+  When I successfully run `delivery open dashboard --print`
+  Then the output should contain "https://automate.mycompany.com/e/Foobar/#/dashboard"
+
+Scenario: a change requires its ID
+  When I run `delivery open change --print`
+  Then the exit status should be 1
+  And the output should contain "A change ID is required"
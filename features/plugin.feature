@@ -0,0 +1,30 @@
+Feature: plugin
+  Unknown subcommands are dispatched to a `delivery-<name>` executable
+  found on the PATH, it receives the resolved config as environment
+  variables and as a JSON file.
+
+Background:
+  Given a directory named ".delivery"
+  And a file named ".delivery/cli.toml" with:
+    """
+    server = "automate.mycompany.com"
+    enterprise = "Foobar"
+
+    [profiles.staging]
+    server = "staging.mycompany.com"
+    """
+
+Scenario: run a plugin with the resolved config
+  When I run `delivery --profile staging hello --loud world`
+  Then the exit status should be 3
+  And the output should contain "hello --loud world"
+  And the output should contain "server: staging.mycompany.com"
+  And the output should contain:
+    """
+      "enterprise": "Foobar",
+    """
+
+Scenario: an unknown subcommand without plugin
+  When I run `delivery goodbye`
+  Then the exit status should be 1
+  And the output should contain "'goodbye' is not a delivery command and there is no 'delivery-goodbye' executable in your PATH"
@@ -348,3 +348,60 @@ Scenario: For automation purposes I should be able to request a token
   And the ptty output should contain "MY_SUPER_DUPER_TOKEN"
   And the ptty output should not contain "Requesting Token"
   And the ptty output should not contain "saved API token to"
+
+Scenario: The password can come from the password_command of the cli.toml
+
+  Given the Delivery API server:
+    """
+      get('/api/v0/e/automation/saml/enabled') do
+        status 200
+        {
+            "enabled" => false
+        }
+      end
+      post('/api/v0/e/automation/users/token/get-token') do
+        status 200
+        {
+            "token" => "MY_VAULT_TOKEN"
+        }
+      end
+    """
+  And a file named ".delivery/cli.toml" with:
+    """
+    password_command = "echo something"
+    """
+  When I successfully run `delivery token --server 127.0.0.1:8080 --ent automation --user token --raw`
+  Then the output should contain "MY_VAULT_TOKEN"
+
+Scenario: The token can come from the token_command of the cli.toml
+
+  Given a file named ".delivery/cli.toml" with:
+    """
+    token_command = "echo TOKEN_FROM_THE_VAULT"
+    """
+  When I successfully run `delivery token --server 127.0.0.1:8080 --ent automation --user token --raw`
+  Then the output should contain "TOKEN_FROM_THE_VAULT"
+  And the file ".delivery/api-tokens" should not exist
+
+Scenario: A cli.toml of a project can't set a credential helper
+
+  Given a file named "project/.delivery/cli.toml" with:
+    """
+    token_command = "echo TOKEN_FROM_THE_PROJECT"
+    """
+  And I cd to "project"
+  When I run `delivery token --server 127.0.0.1:8080 --ent automation --user token --raw`
+  Then the exit status should be 1
+  And the output should contain "token_command is set in"
+  And the output should not contain "TOKEN_FROM_THE_PROJECT"
+
+Scenario: A credential helper that hangs is stopped after the credential_timeout
+
+  Given a file named ".delivery/cli.toml" with:
+    """
+    token_command = "sleep 10"
+    credential_timeout = "1"
+    """
+  When I run `delivery token --server 127.0.0.1:8080 --ent automation --user token`
+  Then the exit status should be 1
+  And the output should contain "token_command: 'sleep 10' timed out after 1 seconds"
@@ -0,0 +1,260 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Command Aliases
+//!
+//! The `[alias]` table of the cli.toml lets the end-user define
+//! shortcuts for long invocations, the same way git aliases work:
+//!
+//! ```toml
+//! [alias]
+//! rr = "review --no-open --auto-bump --for release-2"
+//! ```
+//!
+//! Aliases are expanded before clap parses the arguments, they can point
+//! to other aliases and they can never shadow a built-in subcommand.
+//!
+use errors::{DeliveryError, Kind};
+use std::collections::BTreeMap;
+use types::DeliveryResult;
+
+/// Expand the alias used as subcommand, if any, until we reach a
+/// built-in subcommand or a name that isn't an alias.
+pub fn expand(
+    args: Vec<String>,
+    aliases: &BTreeMap<String, String>,
+    builtins: &[&str],
+) -> DeliveryResult<Vec<String>> {
+    let mut args = args;
+    let mut chain: Vec<String> = Vec::new();
+    loop {
+        let index = match subcommand_index(&args) {
+            Some(i) => i,
+            None => return Ok(args),
+        };
+        let name = args[index].clone();
+        if builtins.contains(&name.as_str()) {
+            return Ok(args);
+        }
+        let command = match aliases.get(&name) {
+            Some(c) => c,
+            None => return Ok(args),
+        };
+        if chain.contains(&name) {
+            chain.push(name);
+            return Err(DeliveryError {
+                kind: Kind::InvalidAlias,
+                detail: Some(format!("Alias loop detected: {}", chain.join(" -> "))),
+            });
+        }
+        let expansion = try!(split_command(command));
+        if expansion.is_empty() {
+            return Err(DeliveryError {
+                kind: Kind::InvalidAlias,
+                detail: Some(format!("The alias '{}' is empty", name)),
+            });
+        }
+        debug!("Expanding alias {} to {:?}", name, expansion);
+        chain.push(name);
+        let rest = args.split_off(index + 1);
+        args.pop();
+        args.extend(expansion);
+        args.extend(rest);
+    }
+}
+
+/// The list of aliases to display in `delivery help`
+pub fn help_text(aliases: &BTreeMap<String, String>, builtins: &[&str]) -> Option<String> {
+    if aliases.is_empty() {
+        return None;
+    }
+    let width = aliases.keys().map(|k| k.len()).max().unwrap_or(0);
+    let mut text = String::from("ALIASES:");
+    for (name, command) in aliases.iter() {
+        text.push_str(&format!("\n    {:width$}    {}", name, command, width = width));
+        if builtins.contains(&name.as_str()) {
+            text.push_str(" (ignored, shadowed by a built-in subcommand)");
+        }
+    }
+    Some(text)
+}
+
+/// The `--profile` provided in the arguments, we need it to know which
+/// aliases to use before clap parses them. Only the options before the
+/// subcommand count, the ones after it belong to the subcommand or to an
+/// alias.
+pub fn profile_of_args(args: &[String]) -> Option<String> {
+    let end = subcommand_index(args).unwrap_or(args.len());
+    let mut iter = args[..end].iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--profile" {
+            return iter.next().cloned();
+        }
+        if arg.starts_with("--profile=") {
+            return Some(arg["--profile=".len()..].to_string());
+        }
+    }
+    None
+}
+
+// Index of the first argument that is not an option of the top-level
+// command, that is where clap would expect the subcommand to be.
+pub fn subcommand_index(args: &[String]) -> Option<usize> {
+    let mut index = 1;
+    while index < args.len() {
+        let arg = &args[index];
+        if arg == "--profile" {
+            index += 2;
+            continue;
+        }
+        if !arg.starts_with('-') {
+            return Some(index);
+        }
+        index += 1;
+    }
+    None
+}
+
+// Split an alias into arguments on whitespace, single or double
+// quotes can be used to keep an argument with spaces together.
+fn split_command(command: &str) -> DeliveryResult<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(current.clone());
+                    current.clear();
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(DeliveryError {
+            kind: Kind::InvalidAlias,
+            detail: Some(format!("Unterminated quote in the alias '{}'", command)),
+        });
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUILTINS: &'static [&'static str] = &["review", "job", "help"];
+
+    fn args(s: &[&str]) -> Vec<String> {
+        s.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn aliases(a: &[(&str, &str)]) -> BTreeMap<String, String> {
+        a.iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn expand_an_alias_keeping_the_rest_of_the_args() {
+        let a = aliases(&[("rr", "review --no-open --for release-2")]);
+        assert_eq!(
+            args(&["delivery", "--no-color", "review", "--no-open", "--for", "release-2", "-a"]),
+            expand(args(&["delivery", "--no-color", "rr", "-a"]), &a, BUILTINS).unwrap()
+        );
+    }
+
+    #[test]
+    fn expand_a_chain_of_aliases() {
+        let a = aliases(&[("rr", "r --for release"), ("r", "review --no-open")]);
+        assert_eq!(
+            args(&["delivery", "review", "--no-open", "--for", "release"]),
+            expand(args(&["delivery", "rr"]), &a, BUILTINS).unwrap()
+        );
+    }
+
+    #[test]
+    fn builtins_and_unknown_names_are_not_expanded() {
+        let a = aliases(&[("review", "job verify unit")]);
+        let input = args(&["delivery", "--profile", "rr", "review"]);
+        assert_eq!(input.clone(), expand(input, &a, BUILTINS).unwrap());
+        let input = args(&["delivery", "unknown"]);
+        assert_eq!(input.clone(), expand(input, &a, BUILTINS).unwrap());
+    }
+
+    #[test]
+    fn detect_alias_loops() {
+        let a = aliases(&[("a", "b --x"), ("b", "a")]);
+        let err = expand(args(&["delivery", "a"]), &a, BUILTINS).unwrap_err();
+        assert_eq!(
+            Some("Alias loop detected: a -> b -> a".to_string()),
+            err.detail
+        );
+    }
+
+    #[test]
+    fn profile_from_the_args() {
+        assert_eq!(
+            Some("staging".to_string()),
+            profile_of_args(&args(&["delivery", "--profile", "staging", "rr"]))
+        );
+        assert_eq!(
+            None,
+            profile_of_args(&args(&["delivery", "rr", "--profile", "staging"]))
+        );
+        assert_eq!(
+            Some("lab".to_string()),
+            profile_of_args(&args(&["delivery", "--profile=lab", "rr"]))
+        );
+        assert_eq!(None, profile_of_args(&args(&["delivery", "rr"])));
+    }
+
+    #[test]
+    fn split_command_with_quotes() {
+        assert_eq!(
+            args(&["job", "verify", "unit lint", "--git-url", ""]),
+            split_command("job  verify 'unit lint' --git-url \"\"").unwrap()
+        );
+        assert!(split_command("job \"verify").is_err());
+    }
+
+    #[test]
+    fn help_text_lists_the_aliases() {
+        assert_eq!(None, help_text(&BTreeMap::new(), BUILTINS));
+        let a = aliases(&[("rr", "review --no-open"), ("review", "job")]);
+        assert_eq!(
+            "ALIASES:\n    review    job (ignored, shadowed by a built-in subcommand)\
+             \n    rr        review --no-open",
+            help_text(&a, BUILTINS).unwrap()
+        );
+    }
+}
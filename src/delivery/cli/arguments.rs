@@ -128,6 +128,12 @@ fn_arg!(
     "--non-interactive 'Disable command line interactions'"
 );
 
+fn_arg!(
+    profile_arg,
+    "--profile=[profile] 'Named profile from your cli.toml to use \
+     (default: $DELIVERY_PROFILE)'"
+);
+
 #[cfg(test)]
 mod tests {
     use super::value_of;
@@ -0,0 +1,114 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use cli::arguments::{
+    a2_mode_arg, api_port_arg, pipeline_arg, project_arg, u_e_s_o_args, value_of,
+};
+use cli::Options;
+use config::Config;
+use types::DeliveryResult;
+
+pub const SUBCOMMAND_NAME: &'static str = "config";
+
+#[derive(Debug)]
+pub struct ConfigClapOptions<'n> {
+    pub action: &'n str,
+    pub json: bool,
+    pub user: &'n str,
+    pub server: &'n str,
+    pub api_port: &'n str,
+    pub ent: &'n str,
+    pub org: &'n str,
+    pub pipeline: &'n str,
+    pub project: &'n str,
+    pub a2_mode: Option<bool>,
+}
+
+impl<'n> Default for ConfigClapOptions<'n> {
+    fn default() -> Self {
+        ConfigClapOptions {
+            action: "show",
+            json: false,
+            user: "",
+            server: "",
+            api_port: "",
+            ent: "",
+            org: "",
+            pipeline: "",
+            project: "",
+            a2_mode: None,
+        }
+    }
+}
+
+impl<'n> ConfigClapOptions<'n> {
+    pub fn new(matches: &'n ArgMatches<'n>) -> Self {
+        ConfigClapOptions {
+            action: value_of(&matches, "action"),
+            json: matches.is_present("json"),
+            user: value_of(&matches, "user"),
+            server: value_of(&matches, "server"),
+            api_port: value_of(&matches, "api-port"),
+            ent: value_of(&matches, "ent"),
+            org: value_of(&matches, "org"),
+            pipeline: value_of(&matches, "pipeline"),
+            project: value_of(&matches, "project"),
+            a2_mode: if matches.is_present("a2-mode") {
+                Some(true)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl<'n> Options for ConfigClapOptions<'n> {
+    fn merge_options_and_config(&self, config: Config) -> DeliveryResult<Config> {
+        let new_config = config
+            .set_user(&self.user)
+            .set_server(&self.server)
+            .set_api_port(&self.api_port)
+            .set_enterprise(&self.ent)
+            .set_organization(&self.org)
+            .set_pipeline(&self.pipeline)
+            .set_project(&self.project)
+            .set_a2_mode_if_def(self.a2_mode);
+        Ok(new_config)
+    }
+}
+
+pub fn clap_subcommand<'c>() -> App<'c, 'c> {
+    SubCommand::with_name(SUBCOMMAND_NAME)
+        .about("Inspect the configuration loaded from your cli.toml files")
+        .arg(
+            Arg::from_usage("<action> 'Action to perform on the config'")
+                .takes_value(false)
+                .possible_values(&["show", "validate"])
+                .help(
+                    "show: Print the effective config and where each value came from\n\
+                     validate: Report unknown keys, invalid values and inconsistent \
+                     options in your cli.toml files\n",
+                ),
+        )
+        .args_from_usage("--json 'Output the result in JSON format'")
+        .arg(api_port_arg())
+        .args(&vec![project_arg()])
+        .args(&pipeline_arg())
+        .args(&u_e_s_o_args())
+        .args(&vec![a2_mode_arg()])
+}
@@ -0,0 +1,80 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use clap::{App, Arg, ArgMatches, SubCommand};
+use cli::arguments::{config_project_arg, value_of};
+use cli::Options;
+use config::Config;
+use types::DeliveryResult;
+
+pub const SUBCOMMAND_NAME: &'static str = "config-json";
+
+#[derive(Debug)]
+pub struct ConfigJsonClapOptions<'n> {
+    pub action: &'n str,
+    pub config_json: &'n str,
+    pub json: bool,
+    pub dry_run: bool,
+}
+
+impl<'n> Default for ConfigJsonClapOptions<'n> {
+    fn default() -> Self {
+        ConfigJsonClapOptions {
+            action: "validate",
+            config_json: "",
+            json: false,
+            dry_run: false,
+        }
+    }
+}
+
+impl<'n> ConfigJsonClapOptions<'n> {
+    pub fn new(matches: &'n ArgMatches<'n>) -> Self {
+        ConfigJsonClapOptions {
+            action: value_of(&matches, "action"),
+            config_json: value_of(&matches, "config-json"),
+            json: matches.is_present("json"),
+            dry_run: matches.is_present("dry-run"),
+        }
+    }
+}
+
+impl<'n> Options for ConfigJsonClapOptions<'n> {
+    fn merge_options_and_config(&self, config: Config) -> DeliveryResult<Config> {
+        Ok(config)
+    }
+}
+
+pub fn clap_subcommand<'c>() -> App<'c, 'c> {
+    SubCommand::with_name(SUBCOMMAND_NAME)
+        .about("Manage the .delivery/config.json of the project")
+        .arg(
+            Arg::from_usage("<action> 'Action to perform on the config.json'")
+                .takes_value(false)
+                .possible_values(&["validate", "migrate"])
+                .help(
+                    "validate: Check the config.json against the JSON Schema of its \
+                     version and report every error with its location\n\
+                     migrate: Rewrite a version 1 config.json as version 2 and \
+                     commit the change\n",
+                ),
+        )
+        .args(&vec![config_project_arg()])
+        .args_from_usage(
+            "--json 'Output the errors in JSON format'
+            --dry-run 'Show the changes of the migration without writing them'",
+        )
+}
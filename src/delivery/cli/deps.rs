@@ -0,0 +1,100 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use clap::{App, ArgMatches, SubCommand};
+use cli::arguments::{pipeline_arg, project_arg, u_e_s_o_args, value_of};
+use cli::Options;
+use config::Config;
+use project;
+use types::DeliveryResult;
+
+pub const SUBCOMMAND_NAME: &'static str = "deps";
+
+#[derive(Debug)]
+pub struct DepsClapOptions<'n> {
+    pub dot: bool,
+    pub reverse: bool,
+    pub fetch: bool,
+    pub user: &'n str,
+    pub server: &'n str,
+    pub ent: &'n str,
+    pub org: &'n str,
+    pub pipeline: &'n str,
+    pub project: &'n str,
+}
+
+impl<'n> Default for DepsClapOptions<'n> {
+    fn default() -> Self {
+        DepsClapOptions {
+            dot: false,
+            reverse: false,
+            fetch: false,
+            user: "",
+            server: "",
+            ent: "",
+            org: "",
+            pipeline: "master",
+            project: "",
+        }
+    }
+}
+
+impl<'n> DepsClapOptions<'n> {
+    pub fn new(matches: &'n ArgMatches<'n>) -> Self {
+        DepsClapOptions {
+            dot: matches.is_present("dot"),
+            reverse: matches.is_present("reverse"),
+            fetch: matches.is_present("fetch"),
+            user: value_of(&matches, "user"),
+            server: value_of(&matches, "server"),
+            ent: value_of(&matches, "ent"),
+            org: value_of(&matches, "org"),
+            pipeline: value_of(&matches, "pipeline"),
+            project: value_of(&matches, "project"),
+        }
+    }
+}
+
+impl<'n> Options for DepsClapOptions<'n> {
+    fn merge_options_and_config(&self, config: Config) -> DeliveryResult<Config> {
+        let mut new_config = config
+            .set_user(&self.user)
+            .set_server(&self.server)
+            .set_enterprise(&self.ent)
+            .set_organization(&self.org)
+            .set_pipeline(&self.pipeline)
+            .set_project(&self.project);
+
+        if new_config.project.is_none() {
+            new_config.project = project::project_from_cwd().ok();
+        }
+        Ok(new_config)
+    }
+}
+
+pub fn clap_subcommand<'c>() -> App<'c, 'c> {
+    SubCommand::with_name(SUBCOMMAND_NAME)
+        .about("Show the dependencies between projects from their config.json")
+        .args_from_usage(
+            "--dot 'Print the whole graph in the DOT format of Graphviz'
+            --reverse 'Show the projects that depend on this project'
+            --fetch 'Fetch the config.json of the dependencies that are not checked \
+                     out next to this project from the Automate server'",
+        )
+        .args(&vec![project_arg()])
+        .args(&pipeline_arg())
+        .args(&u_e_s_o_args())
+}
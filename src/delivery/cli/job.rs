@@ -47,6 +47,8 @@ pub struct JobClapOptions<'n> {
     pub branch: &'n str,
     pub skip_default: bool,
     pub local: bool,
+    pub no_cache: bool,
+    pub include_skipped: bool,
     pub docker_image: &'n str,
     pub fips: bool,
     pub fips_git_port: &'n str,
@@ -74,6 +76,8 @@ impl<'n> Default for JobClapOptions<'n> {
             branch: "",
             skip_default: false,
             local: false,
+            no_cache: false,
+            include_skipped: false,
             docker_image: "",
             fips: false,
             fips_git_port: "",
@@ -103,6 +107,8 @@ impl<'n> JobClapOptions<'n> {
             branch: value_of(&matches, "branch"),
             skip_default: matches.is_present("skip-default"),
             local: matches.is_present("local"),
+            no_cache: matches.is_present("no-cache"),
+            include_skipped: matches.is_present("include-skipped"),
             docker_image: value_of(&matches, "docker"),
             fips: matches.is_present("fips"),
             fips_git_port: value_of(&matches, "fips-git-port"),
@@ -163,6 +169,9 @@ pub fn clap_subcommand<'c>() -> App<'c, 'c> {
             "-S --shasum=[gitsha] 'Git SHA of change'",
             "--change-id=[id] 'The change ID'",
             "--skip-default 'skip default'",
+            "--no-cache 'Fetch and vendor the build_cookbook without using the cache'",
+            "--include-skipped 'Run the phases skipped by the skip_phases or the paths of the \
+             config.json'",
             "--docker=[image] 'Docker image'"
         ])
         .args_from_usage(
@@ -0,0 +1,85 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use clap::{App, Arg, ArgMatches, SubCommand};
+use cli::arguments::{config_project_arg, value_of};
+use cli::Options;
+use config::Config;
+use types::DeliveryResult;
+
+pub const SUBCOMMAND_NAME: &'static str = "job-dispatch";
+
+#[derive(Debug)]
+pub struct JobDispatchClapOptions<'n> {
+    pub action: &'n str,
+    pub nodes: Vec<&'n str>,
+    pub config_json: &'n str,
+    pub json: bool,
+}
+
+impl<'n> Default for JobDispatchClapOptions<'n> {
+    fn default() -> Self {
+        JobDispatchClapOptions {
+            action: "explain",
+            nodes: Vec::new(),
+            config_json: "",
+            json: false,
+        }
+    }
+}
+
+impl<'n> JobDispatchClapOptions<'n> {
+    pub fn new(matches: &'n ArgMatches<'n>) -> Self {
+        JobDispatchClapOptions {
+            action: value_of(&matches, "action"),
+            nodes: matches
+                .values_of("nodes")
+                .map(|v| v.collect())
+                .unwrap_or_default(),
+            config_json: value_of(&matches, "config-json"),
+            json: matches.is_present("json"),
+        }
+    }
+}
+
+impl<'n> Options for JobDispatchClapOptions<'n> {
+    fn merge_options_and_config(&self, config: Config) -> DeliveryResult<Config> {
+        Ok(config)
+    }
+}
+
+pub fn clap_subcommand<'c>() -> App<'c, 'c> {
+    SubCommand::with_name(SUBCOMMAND_NAME)
+        .about("Check the job_dispatch filters of the config.json against your nodes")
+        .arg(
+            Arg::from_usage("<action> 'Action to perform on the job_dispatch filters'")
+                .takes_value(false)
+                .possible_values(&["explain"])
+                .help(
+                    "explain: Report which filter clause of each phase matches which \
+                     node and the phases that no node is able to run\n",
+                ),
+        )
+        .arg(
+            Arg::from_usage("<nodes>... 'JSON files with the attributes of the nodes'")
+                .help(
+                    "JSON files with the attributes of the nodes, like the output of \
+                     ohai or a node saved with `knife node show -l -F json`",
+                ),
+        )
+        .args(&vec![config_project_arg()])
+        .args_from_usage("--json 'Output the report in JSON format'")
+}
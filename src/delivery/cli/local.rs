@@ -15,89 +15,122 @@
 // limitations under the License.
 //
 use clap::{App, Arg, ArgMatches, SubCommand};
+use cli::alias;
 use cli::arguments::value_of;
-use delivery_config::project::{Phase, Stage};
+use delivery_config::project::{ProjectToml, STANDARD_PHASES, STANDARD_STAGES};
 
 pub const SUBCOMMAND_NAME: &'static str = "local";
 
 #[derive(Debug)]
 pub struct LocalClapOptions<'n> {
-    pub phase: Option<Phase>,
-    pub stage: Option<Stage>,
+    // The phase or the stage to run, it is resolved with the project.toml
+    pub stage_phase: &'n str,
     pub remote_toml: Option<&'n str>,
+    pub include_skipped: bool,
+    pub jobs: usize,
+    pub watch: bool,
 }
 
 impl<'n> Default for LocalClapOptions<'n> {
     fn default() -> Self {
         LocalClapOptions {
-            phase: None,
-            stage: None,
+            stage_phase: "",
             remote_toml: None,
+            include_skipped: false,
+            jobs: 1,
+            watch: false,
         }
     }
 }
 
 impl<'n> LocalClapOptions<'n> {
     pub fn new(matches: &'n ArgMatches<'n>) -> Self {
-        let mut phase: Option<Phase> = None;
-        let mut stage: Option<Stage> = None;
-        match value_of(matches, "stage_phase") {
-            "unit" => phase = Some(Phase::Unit),
-            "lint" => phase = Some(Phase::Lint),
-            "syntax" => phase = Some(Phase::Syntax),
-            "provision" => phase = Some(Phase::Provision),
-            "deploy" => phase = Some(Phase::Deploy),
-            "smoke" => phase = Some(Phase::Smoke),
-            "functional" => phase = Some(Phase::Functional),
-            "cleanup" => phase = Some(Phase::Cleanup),
-            "verify" => stage = Some(Stage::Verify),
-            "acceptance" => stage = Some(Stage::Acceptance),
-            "all" => stage = Some(Stage::All),
-            _ => {}
-        };
-
         let url = match value_of(&matches, "remote-project-toml") {
             "" => None,
             u => Some(u),
         };
 
         LocalClapOptions {
-            phase: phase,
-            stage: stage,
+            stage_phase: value_of(&matches, "stage_phase"),
             remote_toml: url,
+            include_skipped: matches.is_present("include-skipped"),
+            jobs: value_of(&matches, "jobs").parse().unwrap_or(1),
+            watch: matches.is_present("watch"),
         }
     }
 }
 
-pub fn clap_subcommand<'c>() -> App<'c, 'c> {
+/// The phases and stages that the `.delivery/project.toml` of the project
+/// declares besides the standard ones, so that clap accepts them. They are
+/// only known after loading a remote project.toml, or the project.toml is
+/// broken, then it is `None` and the name is checked by the command.
+pub fn declared_stage_phases(args: &[String]) -> Option<Vec<String>> {
+    let index = match alias::subcommand_index(args) {
+        Some(i) if args[i] == SUBCOMMAND_NAME => i,
+        _ => return Some(Vec::new()),
+    };
+    if args[index + 1..]
+        .iter()
+        .any(|a| a == "-r" || a.starts_with("--remote-project-toml"))
+    {
+        return None;
+    }
+    match ProjectToml::load_local_toml() {
+        Ok(ref project_toml) if project_toml.remote_file.is_none() => Some(
+            project_toml
+                .names()
+                .into_iter()
+                .filter(|n| !STANDARD_PHASES.contains(&n.as_str()))
+                .filter(|n| !STANDARD_STAGES.contains(&n.as_str()))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+pub fn clap_subcommand<'c>(declared: Option<&'c [String]>) -> App<'c, 'c> {
+    let mut stage_phase = Arg::with_name("stage_phase")
+        .takes_value(false)
+        .required(true)
+        .help(
+            "Automate phase or stage to execute locally.\n\nAvailable phases: [unit, \
+             lint, syntax, provision, deploy, smoke, functional, cleanup]\n\nStages \
+             will execute a series of phases in the following order:\nverify: [unit, \
+             lint, syntax]\nacceptance: [provision, deploy, smoke, functional, \
+             cleanup]\nall: [unit, lint, syntax, provision, deploy, smoke, functional, \
+             cleanup]\n\nOther phases and stages can be declared in the [local_phases] \
+             and [stages] of the .delivery/project.toml\n\n",
+        );
+    if let Some(declared) = declared {
+        let names: Vec<&str> = STANDARD_PHASES
+            .iter()
+            .chain(STANDARD_STAGES.iter())
+            .cloned()
+            .chain(declared.iter().map(|n| n.as_str()))
+            .collect();
+        stage_phase = stage_phase.possible_values(&names);
+    }
     SubCommand::with_name(SUBCOMMAND_NAME)
         .about("Run Delivery phases on your local workstation.")
+        .arg(stage_phase)
+        .args_from_usage(
+            "-r --remote-project-toml=[remote-url] 'URL for remote project.toml'
+            --include-skipped 'Run the phases listed in the skip_phases of the config.json'
+            -w --watch 'Run again when the project files change'",
+        )
         .arg(
-            Arg::with_name("stage_phase")
-                .takes_value(false)
-                .required(true)
-                .possible_values(&[
-                    "unit",
-                    "lint",
-                    "syntax",
-                    "provision",
-                    "deploy",
-                    "smoke",
-                    "functional",
-                    "cleanup",
-                    // Stages
-                    "verify",
-                    "acceptance",
-                    "all",
-                ])
+            Arg::with_name("jobs")
+                .short("j")
+                .long("jobs")
+                .takes_value(true)
+                .value_name("N")
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(format!("expected a number of jobs greater than 0, got {}", v)),
+                })
                 .help(
-                    "Automate phase or stage to execute locally.\n\nAvailable phases: [unit, \
-                     lint, syntax, provision, deploy, smoke, functional, cleanup]\n\nStages \
-                     will execute a series of phases in the following order:\nverify: [unit, \
-                     lint, syntax]\nacceptance: [provision, deploy, smoke, functional, \
-                     cleanup]\nall: [unit, lint, syntax, provision, deploy, smoke, functional, \
-                     cleanup]\n\n",
+                    "Run up to N independent phases of a stage at the same time, the output \
+                     of every phase is prefixed with its name (default: 1)",
                 ),
         )
-        .args_from_usage("-r --remote-project-toml=[remote-url] 'URL for remote project.toml'")
 }
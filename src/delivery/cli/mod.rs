@@ -16,6 +16,7 @@
 //
 
 use clap::{App, AppSettings, ArgMatches};
+use config::sources::{Source, Sources};
 use config::Config;
 use delivery_config::project::ProjectToml;
 use errors::DeliveryError;
@@ -35,17 +36,23 @@ use utils::say::{self, print_error, sayln};
 // the ClapAlias trait for arguments that we might deprecate in the future
 #[macro_use]
 pub mod arguments;
-use cli::arguments::{no_color_arg, no_spinner_arg, non_interactive_arg};
+use cli::arguments::{no_color_arg, no_spinner_arg, non_interactive_arg, profile_arg, value_of};
 
 // Modules for setting up clap subcommand including their options and defaults,
 // as well as advanced subcommand match parsing (see local for an example).
+mod alias;
 pub mod api;
 pub mod checkout;
 pub mod clone;
+pub mod config;
+pub mod config_json;
+pub mod deps;
 pub mod diff;
 pub mod init;
 pub mod job;
+pub mod job_dispatch;
 pub mod local;
+pub mod open;
 pub mod pull;
 pub mod review;
 pub mod setup;
@@ -59,10 +66,16 @@ use command::Command;
 use command::api::ApiCommand;
 use command::checkout::CheckoutCommand;
 use command::clone::CloneCommand;
+use command::config::ConfigCommand;
+use command::config_json::ConfigJsonCommand;
+use command::deps::DepsCommand;
 use command::diff::DiffCommand;
 use command::init::InitCommand;
 use command::job::{run_docker_job, JobCommand};
+use command::job_dispatch::JobDispatchCommand;
 use command::local::LocalCommand;
+use command::open::OpenCommand;
+use command::plugin::PluginCommand;
 use command::pull::PullCommand;
 use command::review::ReviewCommand;
 use command::setup::SetupCommand;
@@ -76,10 +89,33 @@ pub trait Options {
 pub fn run() {
     let build_version = format!("{} {}", version(), build_git_sha());
 
-    let app = make_app(&build_version);
-    let app_matches = app.get_matches();
+    // Expand the aliases from the cli.toml before clap parses the arguments,
+    // a broken config is reported later on by the command that loads it.
+    let args: Vec<String> = env::args().collect();
+    let profile = alias::profile_of_args(&args);
+    let aliases = Config::load_config_with_sources(&cwd(), profile.as_ref().map(|p| p.as_str()))
+        .ok()
+        .and_then(|(config, _)| config.alias)
+        .unwrap_or_default();
+    let builtins = subcommand_names();
+    let args = match alias::expand(args, &aliases, &builtins) {
+        Ok(a) => a,
+        Err(e) => {
+            exit_with(e, 1);
+            return;
+        }
+    };
+    let aliases_help = alias::help_text(&aliases, &builtins);
+    let declared_stage_phases = local::declared_stage_phases(&args);
+
+    let app = make_app_with_aliases(
+        &build_version,
+        declared_stage_phases.as_ref().map(|n| n.as_slice()),
+        aliases_help.as_ref().map(|h| h.as_str()),
+    );
+    let app_matches = app.clone().get_matches_from(args);
 
-    match match_command_and_start(&app_matches, &build_version) {
+    match match_command_and_start(&app_matches, app) {
         // You can exit with any integer, can also be used to bypass default
         // error handling if you handled an error and returned non-zero.
         Ok(exit_status) => process::exit(exit_status),
@@ -121,14 +157,11 @@ fn execute_command<C: Command>(matches: &ArgMatches, command: C) -> DeliveryResu
     command_result
 }
 
-fn match_command_and_start(
-    app_matches: &ArgMatches,
-    build_version: &str,
-) -> DeliveryResult<ExitCode> {
+fn match_command_and_start(app_matches: &ArgMatches, mut app: App) -> DeliveryResult<ExitCode> {
     let cmd_result = match app_matches.subcommand() {
         (api::SUBCOMMAND_NAME, Some(matches)) => {
             let options = api::ApiClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = ApiCommand {
                 options: &options,
                 config: &config,
@@ -137,7 +170,7 @@ fn match_command_and_start(
         }
         (checkout::SUBCOMMAND_NAME, Some(matches)) => {
             let options = checkout::CheckoutClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = CheckoutCommand {
                 options: &options,
                 config: &config,
@@ -146,16 +179,45 @@ fn match_command_and_start(
         }
         (clone::SUBCOMMAND_NAME, Some(matches)) => {
             let options = clone::CloneClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = CloneCommand {
                 options: &options,
                 config: &config,
             };
             execute_command(&matches, command)
         }
+        (config::SUBCOMMAND_NAME, Some(matches)) => {
+            let options = config::ConfigClapOptions::new(&matches);
+            let (config, sources) =
+                try!(load_config_and_sources_with_options(&matches, &options));
+            let command = ConfigCommand {
+                options: &options,
+                config: &config,
+                sources: &sources,
+            };
+            execute_command(&matches, command)
+        }
+        (config_json::SUBCOMMAND_NAME, Some(matches)) => {
+            let options = config_json::ConfigJsonClapOptions::new(&matches);
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
+            let command = ConfigJsonCommand {
+                options: &options,
+                config: &config,
+            };
+            execute_command(&matches, command)
+        }
+        (deps::SUBCOMMAND_NAME, Some(matches)) => {
+            let options = deps::DepsClapOptions::new(&matches);
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
+            let command = DepsCommand {
+                options: &options,
+                config: &config,
+            };
+            execute_command(&matches, command)
+        }
         (diff::SUBCOMMAND_NAME, Some(matches)) => {
             let options = diff::DiffClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = DiffCommand {
                 options: &options,
                 config: &config,
@@ -164,7 +226,7 @@ fn match_command_and_start(
         }
         (init::SUBCOMMAND_NAME, Some(matches)) => {
             let options = init::InitClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = InitCommand {
                 options: &options,
                 config: &config,
@@ -173,7 +235,7 @@ fn match_command_and_start(
         }
         (job::SUBCOMMAND_NAME, Some(matches)) => {
             let options = job::JobClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = JobCommand {
                 options: &options,
                 config: &config,
@@ -184,6 +246,15 @@ fn match_command_and_start(
                 execute_command(&matches, command)
             }
         }
+        (job_dispatch::SUBCOMMAND_NAME, Some(matches)) => {
+            let options = job_dispatch::JobDispatchClapOptions::new(&matches);
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
+            let command = JobDispatchCommand {
+                options: &options,
+                config: &config,
+            };
+            execute_command(&matches, command)
+        }
         (local::SUBCOMMAND_NAME, Some(matches)) => {
             let options = local::LocalClapOptions::new(&matches);
             let config = try!(ProjectToml::load_toml(options.remote_toml));
@@ -193,9 +264,18 @@ fn match_command_and_start(
             };
             execute_command(&matches, command)
         }
+        (open::SUBCOMMAND_NAME, Some(matches)) => {
+            let options = open::OpenClapOptions::new(&matches);
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
+            let command = OpenCommand {
+                options: &options,
+                config: &config,
+            };
+            execute_command(&matches, command)
+        }
         (review::SUBCOMMAND_NAME, Some(matches)) => {
             let options = review::ReviewClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = ReviewCommand {
                 options: &options,
                 config: &config,
@@ -209,17 +289,30 @@ fn match_command_and_start(
             } else {
                 PathBuf::from(options.path)
             };
-            let config = try!(load_config_and_merge_with_options(&options));
+            let profile = profile_of(&matches);
+            // We only start from the keys that the target cli.toml, or the
+            // named profile, already sets and not from the whole merged config.
+            let config = match profile {
+                Some(name) => {
+                    let base = try!(Config::read_profile(&config_path, name));
+                    try!(options.merge_options_and_config(base))
+                }
+                None => {
+                    let base = try!(Config::load_file_config(&config_path));
+                    try!(options.merge_options_and_config(base))
+                }
+            };
             let command = SetupCommand {
                 options: &options,
                 config: &config,
                 config_path: &config_path,
+                profile: profile,
             };
             execute_command(&matches, command)
         }
         (token::SUBCOMMAND_NAME, Some(matches)) => {
             let options = token::TokenClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = TokenCommand {
                 options: &options,
                 config: &config,
@@ -228,7 +321,7 @@ fn match_command_and_start(
         }
         (status::SUBCOMMAND_NAME, Some(matches)) => {
             let options = status::StatusClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = StatusCommand {
                 options: &options,
                 config: &config,
@@ -237,7 +330,7 @@ fn match_command_and_start(
         }
         (pull::SUBCOMMAND_NAME, Some(matches)) => {
             let options = pull::PullClapOptions::new(&matches);
-            let config = try!(load_config_and_merge_with_options(&options));
+            let config = try!(load_config_and_merge_with_options(&matches, &options));
             let command = PullCommand {
                 options: &options,
                 config: &config,
@@ -254,24 +347,84 @@ fn match_command_and_start(
             handle_global_flags(&matches);
             Ok(0)
         }
+        (name, Some(matches)) => {
+            // Unknown subcommands are dispatched to a `delivery-<name>` plugin
+            let config = try!(Config::load_config_for_profile(
+                &cwd(),
+                profile_of(&app_matches)
+            ));
+            try!(config.validate_merged());
+            let command = PluginCommand {
+                name: name,
+                args: matches.values_of("").map(|v| v.collect()).unwrap_or_default(),
+                config: &config,
+            };
+            execute_command(&matches, command)
+        }
         _ => {
-            // ownership issue with use of above defined app
-            // so for now...
-            let mut a = make_app(&build_version);
-            a.print_help().ok().expect("failed to write help to stdout");
+            app.print_help().ok().expect("failed to write help to stdout");
             Ok(1)
         }
     };
     cmd_result
 }
 
+// The names of every built-in subcommand, aliases can't shadow them.
+fn subcommand_names() -> Vec<&'static str> {
+    vec![
+        api::SUBCOMMAND_NAME,
+        checkout::SUBCOMMAND_NAME,
+        clone::SUBCOMMAND_NAME,
+        config::SUBCOMMAND_NAME,
+        config_json::SUBCOMMAND_NAME,
+        deps::SUBCOMMAND_NAME,
+        diff::SUBCOMMAND_NAME,
+        init::SUBCOMMAND_NAME,
+        job::SUBCOMMAND_NAME,
+        job_dispatch::SUBCOMMAND_NAME,
+        local::SUBCOMMAND_NAME,
+        open::SUBCOMMAND_NAME,
+        pull::SUBCOMMAND_NAME,
+        review::SUBCOMMAND_NAME,
+        setup::SUBCOMMAND_NAME,
+        spin::SUBCOMMAND_NAME,
+        status::SUBCOMMAND_NAME,
+        token::SUBCOMMAND_NAME,
+        "help",
+    ]
+}
+
+// The app with only the standard phases and stages of `delivery local`
+#[cfg(test)]
 fn make_app<'a>(version: &'a str) -> App<'a, 'a> {
+    make_app_for_project(version, Some(&[]))
+}
+
+// The app that parses the arguments, its help lists the aliases of the
+// cli.toml after the subcommands.
+fn make_app_with_aliases<'a>(
+    version: &'a str,
+    stage_phases: Option<&'a [String]>,
+    aliases_help: Option<&'a str>,
+) -> App<'a, 'a> {
+    let app = make_app_for_project(version, stage_phases);
+    match aliases_help {
+        Some(help) => app.after_help(help),
+        None => app,
+    }
+}
+
+// The phases and stages that `delivery local` accepts depend on the
+// project.toml of the project, see `local::declared_stage_phases`.
+fn make_app_for_project<'a>(version: &'a str, stage_phases: Option<&'a [String]>) -> App<'a, 'a> {
     App::new("delivery")
         .version(version)
         .setting(AppSettings::GlobalVersion)
+        .setting(AppSettings::AllowExternalSubcommands)
         .arg(no_spinner_arg().global(true))
         .arg(no_color_arg().global(true))
         .arg(non_interactive_arg().global(true))
+        .arg(profile_arg().global(true))
         .subcommand(review::clap_subcommand())
         .subcommand(clone::clap_subcommand())
         .subcommand(checkout::clap_subcommand())
@@ -282,9 +435,14 @@ fn make_app<'a>(version: &'a str) -> App<'a, 'a> {
         .subcommand(api::clap_subcommand())
         .subcommand(token::clap_subcommand())
         .subcommand(spin::clap_subcommand())
-        .subcommand(local::clap_subcommand())
+        .subcommand(local::clap_subcommand(stage_phases))
         .subcommand(status::clap_subcommand())
         .subcommand(pull::clap_subcommand())
+        .subcommand(config::clap_subcommand())
+        .subcommand(open::clap_subcommand())
+        .subcommand(config_json::clap_subcommand())
+        .subcommand(job_dispatch::clap_subcommand())
+        .subcommand(deps::clap_subcommand())
 }
 
 fn handle_global_flags(matches: &ArgMatches) {
@@ -305,14 +463,60 @@ fn exit_with(e: DeliveryError, i: ExitCode) {
     process::exit(i)
 }
 
-fn load_config_and_merge_with_options<T: Options>(opts: &T) -> DeliveryResult<Config> {
-    let mut config = try!(Config::load_config(&utils::cwd()));
+// Returns the profile provided through the global `--profile` flag
+fn profile_of<'a>(matches: &'a ArgMatches) -> Option<&'a str> {
+    match value_of(matches, "profile") {
+        "" => None,
+        p => Some(p),
+    }
+}
+
+// Load the config for a command
+//
+// The values are resolved in the following order, where each source only
+// overrides the keys it actually sets:
+//
+//   1. The built-in defaults (see `Config::default`)
+//   2. The user-global `~/.delivery/cli.toml`
+//   3. Every `.delivery/cli.toml` from the root of the filesystem down to cwd
+//   4. The `[profiles.<name>]` selected with `--profile` or `DELIVERY_PROFILE`
+//   5. The `DELIVERY_<FIELD>` environment variables (i.e. `DELIVERY_SERVER`)
+//   6. The command line options
+fn load_config_and_merge_with_options<T: Options>(
+    matches: &ArgMatches,
+    opts: &T,
+) -> DeliveryResult<Config> {
+    let mut config = try!(Config::load_config_for_profile(
+        &utils::cwd(),
+        profile_of(matches)
+    ));
     debug!("Initial config: {:?}", config);
     config = try!(opts.merge_options_and_config(config));
     debug!("Merged config: {:?}", config);
+    try!(config.validate_merged());
     Ok(config)
 }
 
+// Same as `load_config_and_merge_with_options` but it also returns the
+// source of every value, the command line options are recorded as flags.
+//
+// The cli.toml files are not validated even if `strict = true` since this
+// is used by the commands that inspect the config.
+fn load_config_and_sources_with_options<T: Options>(
+    matches: &ArgMatches,
+    opts: &T,
+) -> DeliveryResult<(Config, Sources)> {
+    let (config, mut sources) = try!(Config::load_config_with_sources(
+        &utils::cwd(),
+        profile_of(matches)
+    ));
+    debug!("Initial config: {:?}", config);
+    let merged = try!(opts.merge_options_and_config(config.clone()));
+    debug!("Merged config: {:?}", merged);
+    sources.record_changes(&config, &merged, |_| Source::Flag);
+    Ok((merged, sources))
+}
+
 fn version() -> String {
     let build_version = option_env!("DELIV_CLI_VERSION").unwrap_or("0.0.0");
     format!("{}", build_version)
@@ -326,7 +530,10 @@ fn build_git_sha() -> String {
 #[cfg(test)]
 mod tests {
     use cli;
-    use cli::{api, checkout, clone, diff, init, job, review, setup, spin, token};
+    use cli::{
+        api, checkout, clone, config, config_json, deps, diff, init, job, job_dispatch, local,
+        open, review, setup, spin, token,
+    };
 
     #[test]
     fn test_clap_api_options() {
@@ -549,6 +756,8 @@ mod tests {
             "-b",
             "evil",
             "--skip-default",
+            "--no-cache",
+            "--include-skipped",
             "-l",
             "--docker",
             "uzumaki",
@@ -575,6 +784,47 @@ mod tests {
         assert_eq!(job_opts.docker_image, "uzumaki");
         assert_eq!(job_opts.local, true);
         assert_eq!(job_opts.skip_default, true);
+        assert_eq!(job_opts.no_cache, true);
+        assert_eq!(job_opts.include_skipped, true);
+    }
+
+    #[test]
+    fn test_clap_local_options() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec!["delivery", "local", "verify", "--jobs", "3", "--watch"]);
+        assert_eq!(Some("local"), matches.subcommand_name());
+        let local_matches = matches.subcommand_matches(local::SUBCOMMAND_NAME).unwrap();
+        let local_opts = local::LocalClapOptions::new(&local_matches);
+        assert_eq!(local_opts.stage_phase, "verify");
+        assert_eq!(local_opts.jobs, 3);
+        assert_eq!(local_opts.watch, true);
+
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from_safe(vec!["delivery", "local", "verify", "-j", "0"]);
+        assert!(matches.is_err());
+
+        let app = cli::make_app(&build_version);
+        assert!(app
+            .get_matches_from_safe(vec!["delivery", "local", "security"])
+            .is_err());
+        let declared = vec![String::from("security")];
+        let app = cli::make_app_for_project(&build_version, Some(&declared));
+        let matches = app.get_matches_from(vec!["delivery", "local", "security"]);
+        let local_matches = matches.subcommand_matches(local::SUBCOMMAND_NAME).unwrap();
+        assert_eq!(
+            local::LocalClapOptions::new(&local_matches).stage_phase,
+            "security"
+        );
+    }
+
+    #[test]
+    fn test_declared_stage_phases_only_for_the_local_subcommand() {
+        let args: Vec<String> = vec!["delivery", "review", "--for", "local"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(Some(Vec::new()), local::declared_stage_phases(&args));
     }
 
     #[test]
@@ -646,5 +896,194 @@ mod tests {
         assert_eq!(setup_opts.user, "u");
         assert_eq!(setup_opts.pipeline, "p");
         assert_eq!(setup_opts.path, "/my/config/cli.toml");
+        assert_eq!(setup_opts.non_interactive, false);
+    }
+
+    #[test]
+    fn test_clap_open_options() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec![
+            "delivery", "open", "change", "1234", "--stage", "build", "--print",
+        ]);
+        assert_eq!(Some("open"), matches.subcommand_name());
+        let open_matches = matches.subcommand_matches(open::SUBCOMMAND_NAME).unwrap();
+        let open_opts = open::OpenClapOptions::new(&open_matches);
+        assert_eq!(open_opts.item, "change");
+        assert_eq!(open_opts.change, "1234");
+        assert_eq!(open_opts.stage, "build");
+        assert_eq!(open_opts.print, true);
+
+        let app = cli::make_app(&build_version);
+        assert!(app
+            .get_matches_from_safe(vec!["delivery", "open", "change", "1234", "--stage", "qa"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_clap_external_subcommand() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches =
+            app.get_matches_from(vec!["delivery", "--profile", "lab", "hello", "--loud", "world"]);
+        match matches.subcommand() {
+            ("hello", Some(ext_matches)) => {
+                let args: Vec<&str> = ext_matches.values_of("").unwrap().collect();
+                assert_eq!(vec!["--loud", "world"], args);
+            }
+            _ => panic!("the external subcommand was not matched"),
+        }
+        assert_eq!(Some("lab"), cli::profile_of(&matches));
+    }
+
+    #[test]
+    fn test_clap_config_json_options() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec![
+            "delivery",
+            "config-json",
+            "validate",
+            "-c",
+            "/tmp/config.json",
+            "--json",
+        ]);
+        assert_eq!(Some("config-json"), matches.subcommand_name());
+        let cj_matches = matches
+            .subcommand_matches(config_json::SUBCOMMAND_NAME)
+            .unwrap();
+        let cj_opts = config_json::ConfigJsonClapOptions::new(&cj_matches);
+        assert_eq!(cj_opts.action, "validate");
+        assert_eq!(cj_opts.config_json, "/tmp/config.json");
+        assert_eq!(cj_opts.json, true);
+        assert_eq!(cj_opts.dry_run, false);
+    }
+
+    #[test]
+    fn test_clap_deps_options() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec![
+            "delivery", "deps", "--reverse", "--fetch", "-p", "api", "-f", "release",
+        ]);
+        assert_eq!(Some("deps"), matches.subcommand_name());
+        let deps_matches = matches.subcommand_matches(deps::SUBCOMMAND_NAME).unwrap();
+        let deps_opts = deps::DepsClapOptions::new(&deps_matches);
+        assert_eq!(deps_opts.reverse, true);
+        assert_eq!(deps_opts.fetch, true);
+        assert_eq!(deps_opts.dot, false);
+        assert_eq!(deps_opts.project, "api");
+        assert_eq!(deps_opts.pipeline, "release");
+    }
+
+    #[test]
+    fn test_clap_job_dispatch_options() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec![
+            "delivery",
+            "job-dispatch",
+            "explain",
+            "ubuntu.json",
+            "centos.json",
+            "--json",
+        ]);
+        assert_eq!(Some("job-dispatch"), matches.subcommand_name());
+        let jd_matches = matches
+            .subcommand_matches(job_dispatch::SUBCOMMAND_NAME)
+            .unwrap();
+        let jd_opts = job_dispatch::JobDispatchClapOptions::new(&jd_matches);
+        assert_eq!(jd_opts.action, "explain");
+        assert_eq!(jd_opts.nodes, vec!["ubuntu.json", "centos.json"]);
+        assert_eq!(jd_opts.config_json, "");
+        assert_eq!(jd_opts.json, true);
+    }
+
+    #[test]
+    fn test_clap_config_json_migrate_dry_run() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches =
+            app.get_matches_from(vec!["delivery", "config-json", "migrate", "--dry-run"]);
+        let cj_matches = matches
+            .subcommand_matches(config_json::SUBCOMMAND_NAME)
+            .unwrap();
+        let cj_opts = config_json::ConfigJsonClapOptions::new(&cj_matches);
+        assert_eq!(cj_opts.action, "migrate");
+        assert_eq!(cj_opts.config_json, "");
+        assert_eq!(cj_opts.dry_run, true);
+    }
+
+    #[test]
+    fn test_clap_setup_non_interactive() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches =
+            app.get_matches_from(vec!["delivery", "setup", "--non-interactive", "-s", "s"]);
+        let setup_matches = matches.subcommand_matches(setup::SUBCOMMAND_NAME).unwrap();
+        let setup_opts = setup::SetupClapOptions::new(&setup_matches);
+        assert_eq!(setup_opts.non_interactive, true);
+        assert_eq!(setup_opts.missing_required(), true);
+    }
+
+    #[test]
+    fn test_clap_setup_with_required_flags() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec![
+            "delivery", "setup", "-s", "s", "-e", "e", "-o", "o", "-u", "u",
+        ]);
+        let setup_matches = matches.subcommand_matches(setup::SUBCOMMAND_NAME).unwrap();
+        let setup_opts = setup::SetupClapOptions::new(&setup_matches);
+        assert_eq!(setup_opts.missing_required(), false);
+    }
+
+    #[test]
+    fn test_clap_global_profile_option() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec![
+            "delivery",
+            "--profile",
+            "staging",
+            "setup",
+            "-s",
+            "s",
+        ]);
+        let setup_matches = matches.subcommand_matches(setup::SUBCOMMAND_NAME).unwrap();
+        assert_eq!(Some("staging"), cli::profile_of(&setup_matches));
+
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec!["delivery", "setup", "--profile", "lab"]);
+        let setup_matches = matches.subcommand_matches(setup::SUBCOMMAND_NAME).unwrap();
+        assert_eq!(Some("lab"), cli::profile_of(&setup_matches));
+
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec!["delivery", "setup"]);
+        let setup_matches = matches.subcommand_matches(setup::SUBCOMMAND_NAME).unwrap();
+        assert_eq!(None, cli::profile_of(&setup_matches));
+    }
+
+    #[test]
+    fn test_clap_config_options() {
+        let build_version = format!("{} {}", cli::version(), cli::build_git_sha());
+        let app = cli::make_app(&build_version);
+        let matches = app.get_matches_from(vec![
+            "delivery",
+            "config",
+            "show",
+            "--json",
+            "-s",
+            "automate.example.com",
+            "-o",
+            "builders",
+        ]);
+        assert_eq!(Some("config"), matches.subcommand_name());
+        let config_matches = matches.subcommand_matches(config::SUBCOMMAND_NAME).unwrap();
+        let config_opts = config::ConfigClapOptions::new(&config_matches);
+        assert_eq!(config_opts.action, "show");
+        assert_eq!(config_opts.json, true);
+        assert_eq!(config_opts.server, "automate.example.com");
+        assert_eq!(config_opts.org, "builders");
     }
 }
@@ -0,0 +1,123 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use clap::{App, Arg, ArgMatches, SubCommand};
+use cli::arguments::{pipeline_arg, project_arg, u_e_s_o_args, value_of};
+use cli::Options;
+use config::Config;
+use project;
+use types::DeliveryResult;
+
+pub const SUBCOMMAND_NAME: &'static str = "open";
+
+#[derive(Debug)]
+pub struct OpenClapOptions<'n> {
+    pub item: &'n str,
+    pub change: &'n str,
+    pub stage: &'n str,
+    pub print: bool,
+    pub user: &'n str,
+    pub server: &'n str,
+    pub ent: &'n str,
+    pub org: &'n str,
+    pub pipeline: &'n str,
+    pub project: &'n str,
+}
+
+impl<'n> Default for OpenClapOptions<'n> {
+    fn default() -> Self {
+        OpenClapOptions {
+            item: "project",
+            change: "",
+            stage: "",
+            print: false,
+            user: "",
+            server: "",
+            ent: "",
+            org: "",
+            pipeline: "master",
+            project: "",
+        }
+    }
+}
+
+impl<'n> OpenClapOptions<'n> {
+    pub fn new(matches: &'n ArgMatches<'n>) -> Self {
+        OpenClapOptions {
+            item: matches.value_of("item").unwrap_or("project"),
+            change: value_of(&matches, "change"),
+            stage: value_of(&matches, "stage"),
+            print: matches.is_present("print"),
+            user: value_of(&matches, "user"),
+            server: value_of(&matches, "server"),
+            ent: value_of(&matches, "ent"),
+            org: value_of(&matches, "org"),
+            pipeline: value_of(&matches, "pipeline"),
+            project: value_of(&matches, "project"),
+        }
+    }
+}
+
+impl<'n> Options for OpenClapOptions<'n> {
+    fn merge_options_and_config(&self, config: Config) -> DeliveryResult<Config> {
+        let mut new_config = config
+            .set_user(&self.user)
+            .set_server(&self.server)
+            .set_enterprise(&self.ent)
+            .set_organization(&self.org)
+            .set_pipeline(&self.pipeline)
+            .set_project(&self.project);
+
+        if new_config.project.is_none() {
+            new_config.project = project::project_from_cwd().ok();
+        }
+        Ok(new_config)
+    }
+}
+
+pub fn clap_subcommand<'c>() -> App<'c, 'c> {
+    SubCommand::with_name(SUBCOMMAND_NAME)
+        .about("Open a page of the Automate UI in your browser")
+        .arg(
+            Arg::from_usage("[item] 'Page to open (default: project)'")
+                .possible_values(&["project", "pipeline", "change", "users", "dashboard"])
+                .help(
+                    "project: The changes of the current project\n\
+                     pipeline: The changes of the project targeting a pipeline\n\
+                     change: The review page of a change (requires <change>)\n\
+                     users: The users of the enterprise\n\
+                     dashboard: The dashboard of the enterprise\n",
+                ),
+        )
+        .args_from_usage(
+            "[change] 'The ID of the change to open'
+            --print 'Print the URL instead of opening it in a browser'",
+        )
+        .arg(
+            Arg::from_usage("--stage=[stage] 'Open the status of the change in a stage'")
+                .possible_values(&[
+                    "verify",
+                    "build",
+                    "acceptance",
+                    "union",
+                    "rehearsal",
+                    "delivered",
+                ]),
+        )
+        .args(&vec![project_arg()])
+        .args(&pipeline_arg())
+        .args(&u_e_s_o_args())
+}
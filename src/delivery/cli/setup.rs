@@ -34,6 +34,7 @@ pub struct SetupClapOptions<'n> {
     pub pipeline: &'n str,
     pub project: &'n str,
     pub a2_mode: Option<bool>,
+    pub non_interactive: bool,
 }
 
 impl<'n> Default for SetupClapOptions<'n> {
@@ -47,6 +48,7 @@ impl<'n> Default for SetupClapOptions<'n> {
             pipeline: "master",
             project: "",
             a2_mode: None,
+            non_interactive: false,
         }
     }
 }
@@ -66,8 +68,17 @@ impl<'n> SetupClapOptions<'n> {
             } else {
                 None
             },
+            non_interactive: matches.is_present("non-interactive"),
         }
     }
+
+    /// Whether any of the server, enterprise, organization or user
+    /// was left out of the command line.
+    pub fn missing_required(&self) -> bool {
+        [self.server, self.ent, self.org, self.user]
+            .iter()
+            .any(|v| v.is_empty())
+    }
 }
 
 impl<'n> Options for SetupClapOptions<'n> {
@@ -90,7 +101,12 @@ impl<'n> Options for SetupClapOptions<'n> {
 
 pub fn clap_subcommand<'c>() -> App<'c, 'c> {
     SubCommand::with_name(SUBCOMMAND_NAME)
-        .about("Write a config file capturing specified options")
+        .about(
+            "Write a config file capturing specified options. When run from a \
+             terminal without --server, --ent, --org or --user it walks you \
+             through them and verifies them against the Automate server \
+             (use --non-interactive to skip it)",
+        )
         .args(&vec![config_path_arg(), project_arg()])
         .args(&pipeline_arg())
         .args(&u_e_s_o_args())
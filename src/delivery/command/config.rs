@@ -0,0 +1,147 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use cli::config::ConfigClapOptions;
+use command::Command;
+use config::sources::{Source, Sources};
+use config::Config;
+use serde_json;
+use serde_json::Value as SerdeJson;
+use types::{DeliveryResult, ExitCode};
+use utils::cwd;
+use utils::say::{say, sayln};
+
+pub struct ConfigCommand<'n> {
+    pub options: &'n ConfigClapOptions<'n>,
+    pub config: &'n Config,
+    pub sources: &'n Sources,
+}
+
+impl<'n> Command for ConfigCommand<'n> {
+    fn run(&self) -> DeliveryResult<ExitCode> {
+        match self.options.action {
+            "validate" => self.validate(),
+            _ => self.show(),
+        }
+    }
+}
+
+impl<'n> ConfigCommand<'n> {
+    fn validate(&self) -> DeliveryResult<ExitCode> {
+        let issues = self.config.validate(&cwd());
+        let exit_code = if issues.is_empty() { 0 } else { 1 };
+        if self.options.json {
+            sayln("white", &serde_json::to_string_pretty(&issues)?);
+            return Ok(exit_code);
+        }
+
+        for path in Config::cli_toml_paths(&cwd()) {
+            say("white", "Validating ");
+            sayln("yellow", &format!("{}", path.display()));
+        }
+        for issue in issues.iter() {
+            sayln("red", &format!("{}", issue));
+        }
+        if issues.is_empty() {
+            sayln("green", "Your configuration is valid");
+        } else {
+            sayln("red", &format!("Found {} issue(s) in your configuration", issues.len()));
+        }
+        Ok(exit_code)
+    }
+
+    fn show(&self) -> DeliveryResult<ExitCode> {
+        let entries = config_entries(self.config, self.sources)?;
+        if self.options.json {
+            let mut map = serde_json::Map::new();
+            for (key, value, source) in entries.into_iter() {
+                let source = match source {
+                    Some(s) => serde_json::to_value(s)?,
+                    None => SerdeJson::Null,
+                };
+                map.insert(key, json!({ "value": value, "source": source }));
+            }
+            sayln("white", &serde_json::to_string_pretty(&map)?);
+            return Ok(0);
+        }
+
+        let width = entries.iter().map(|e| e.0.len()).max().unwrap_or(0);
+        for (key, value, source) in entries.into_iter() {
+            say("white", &format!("{:width$} = ", key, width = width));
+            match source {
+                Some(s) => {
+                    say("yellow", &format!("{}", value));
+                    sayln("magenta", &format!("  # {}", s));
+                }
+                None => sayln("red", "(not set)"),
+            }
+        }
+        Ok(0)
+    }
+}
+
+// Returns every field of the config in order, along with its value
+// and the source where the value came from, if any.
+fn config_entries<'a>(
+    config: &Config,
+    sources: &'a Sources,
+) -> DeliveryResult<Vec<(String, SerdeJson, Option<&'a Source>)>> {
+    let mut entries = Vec::new();
+    if let SerdeJson::Object(fields) = serde_json::to_value(config)? {
+        for (key, value) in fields.into_iter() {
+            if key == "profiles" {
+                continue;
+            }
+            let source = match value {
+                SerdeJson::Null => None,
+                _ => sources.get(&key),
+            };
+            entries.push((key, value, source));
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::config_entries;
+    use config::sources::{Source, Sources};
+    use config::Config;
+    use serde_json::Value as SerdeJson;
+
+    #[test]
+    fn config_entries_with_sources() {
+        let config = Config::default().set_server("automate.example.com");
+        let mut sources = Sources::new();
+        sources.record(&Config::default(), |_| Source::Default);
+        sources.record_changes(&Config::default(), &config, |_| Source::Flag);
+
+        let entries = config_entries(&config, &sources).unwrap();
+        assert!(entries.iter().all(|e| e.0 != "profiles"));
+
+        let server = entries.iter().find(|e| e.0 == "server").unwrap();
+        assert_eq!(SerdeJson::String("automate.example.com".to_string()), server.1);
+        assert_eq!(Some(&Source::Flag), server.2);
+
+        let git_port = entries.iter().find(|e| e.0 == "git_port").unwrap();
+        assert_eq!(Some(&Source::Default), git_port.2);
+
+        let user = entries.iter().find(|e| e.0 == "user").unwrap();
+        assert_eq!(SerdeJson::Null, user.1);
+        assert_eq!(None, user.2);
+    }
+}
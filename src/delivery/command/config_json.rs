@@ -0,0 +1,286 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use cli::config_json::ConfigJsonClapOptions;
+use command::Command;
+use config::Config;
+use delivery_config::schema;
+use delivery_config::DeliveryConfig;
+use errors::{DeliveryError, Kind};
+use project;
+use serde_json;
+use serde_json::Value as SerdeJson;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::PathBuf;
+use types::{DeliveryResult, ExitCode};
+use utils::say::{say, sayln};
+use utils::{cwd, read_file};
+
+pub struct ConfigJsonCommand<'n> {
+    pub options: &'n ConfigJsonClapOptions<'n>,
+    pub config: &'n Config,
+}
+
+impl<'n> Command for ConfigJsonCommand<'n> {
+    fn run(&self) -> DeliveryResult<ExitCode> {
+        match self.options.action {
+            "migrate" => self.migrate(),
+            _ => self.validate(),
+        }
+    }
+}
+
+impl<'n> ConfigJsonCommand<'n> {
+    // The config.json provided with --config-json or the one of the project
+    fn config_json_path(&self) -> DeliveryResult<PathBuf> {
+        if self.options.config_json.is_empty() {
+            DeliveryConfig::find_config_file(&cwd())
+        } else {
+            Ok(PathBuf::from(self.options.config_json))
+        }
+    }
+
+    fn validate(&self) -> DeliveryResult<ExitCode> {
+        let path = self.config_json_path()?;
+        let errors = DeliveryConfig::schema_errors(&path)?;
+        let exit_code = if errors.is_empty() { 0 } else { 1 };
+        if self.options.json {
+            sayln("white", &serde_json::to_string_pretty(&errors)?);
+            return Ok(exit_code);
+        }
+
+        say("white", "Validating ");
+        sayln("yellow", &format!("{}", path.display()));
+        for error in errors.iter() {
+            sayln("red", &format!("{}:{}", path.display(), error));
+        }
+        if errors.is_empty() {
+            sayln("green", "Your config.json is valid");
+        } else {
+            sayln("red", &format!("Found {} error(s) in your config.json", errors.len()));
+        }
+        Ok(exit_code)
+    }
+
+    fn migrate(&self) -> DeliveryResult<ExitCode> {
+        let path = self.config_json_path()?;
+        let content = read_file(&path)?;
+        let raw: SerdeJson = serde_json::from_str(&content)?;
+        match raw.get("version") {
+            Some(&SerdeJson::String(ref v)) if v == "1" => {}
+            Some(&SerdeJson::String(ref v)) if v == "2" => {
+                sayln("green", "Your config.json is already version 2, nothing to migrate");
+                return Ok(0);
+            }
+            version => {
+                let found = version.map_or(String::from("no version"), |v| v.to_string());
+                return Err(DeliveryError {
+                    kind: Kind::InvalidDeliveryConfig,
+                    detail: Some(format!(
+                        "  {}: version must be \"1\" or \"2\", found {}",
+                        path.display(),
+                        found
+                    )),
+                });
+            }
+        }
+        let errors = schema::validate(&content);
+        if !errors.is_empty() {
+            let lines: Vec<String> = errors
+                .iter()
+                .map(|e| format!("  {}:{}", path.display(), e))
+                .collect();
+            return Err(DeliveryError {
+                kind: Kind::InvalidDeliveryConfig,
+                detail: Some(format!(
+                    "{}\nFix the errors before migrating the config.json",
+                    lines.join("\n")
+                )),
+            });
+        }
+
+        let migrated = DeliveryConfig::migrate_v1_to_v2(&raw)?;
+        let new_content = format!("{}\n", to_string_in_order(&migrated, &content));
+
+        say("white", "Migrating ");
+        say("yellow", &format!("{}", path.display()));
+        sayln("white", " to version 2");
+        for line in line_diff(&content, &new_content) {
+            let color = if line.starts_with('+') {
+                "green"
+            } else if line.starts_with('-') {
+                "red"
+            } else {
+                "white"
+            };
+            sayln(color, &line);
+        }
+        if self.options.dry_run {
+            sayln("yellow", "Dry run, the config.json was not modified");
+            return Ok(0);
+        }
+
+        let mut file = File::create(&path)?;
+        file.write_all(new_content.as_bytes())?;
+
+        // Only the config.json of the project is committed, not a custom one
+        if self.options.config_json.is_empty() {
+            let project_root = project::root_dir(&cwd())?;
+            if DeliveryConfig::git_add_commit_config_with_message(
+                &project_root,
+                "Migrates the Delivery config to version 2",
+            )? {
+                sayln("green", "Committed the migrated config.json");
+            }
+        } else {
+            sayln("green", "Wrote the migrated config.json");
+        }
+        Ok(0)
+    }
+}
+
+// Pretty print the migrated config with the keys of every object in the
+// order they have in the original `content`, the new ones go last. The
+// maps of serde_json are sorted, so the diff would show every moved key.
+fn to_string_in_order(json: &SerdeJson, content: &str) -> String {
+    let positions = schema::locate(content);
+    let mut out = String::new();
+    write_in_order(json, "$", &positions, 0, &mut out);
+    out
+}
+
+fn write_in_order(
+    json: &SerdeJson,
+    path: &str,
+    positions: &HashMap<String, (usize, usize)>,
+    depth: usize,
+    out: &mut String,
+) {
+    let indent = "  ".repeat(depth + 1);
+    match *json {
+        SerdeJson::Object(ref map) if !map.is_empty() => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by_key(|k| {
+                positions
+                    .get(&schema::child_path(path, k))
+                    .cloned()
+                    .unwrap_or((usize::max_value(), 0))
+            });
+            out.push_str("{\n");
+            for (i, key) in keys.iter().enumerate() {
+                out.push_str(&format!("{}{}: ", indent, SerdeJson::String(key.to_string())));
+                let child = schema::child_path(path, key);
+                write_in_order(&map[key.as_str()], &child, positions, depth + 1, out);
+                out.push_str(if i + 1 < keys.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&format!("{}}}", "  ".repeat(depth)));
+        }
+        SerdeJson::Array(ref items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&indent);
+                let child = format!("{}[{}]", path, i);
+                write_in_order(item, &child, positions, depth + 1, out);
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&format!("{}]", "  ".repeat(depth)));
+        }
+        ref value => out.push_str(&value.to_string()),
+    }
+}
+
+// A line by line diff of two texts, every line is prefixed with '-' if
+// it was removed, '+' if it was added or ' ' if it didn't change.
+fn line_diff(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                ::std::cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(format!(" {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(format!("-{}", old[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|l| format!("-{}", l)));
+    diff.extend(new[j..].iter().map(|l| format!("+{}", l)));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{line_diff, to_string_in_order};
+    use serde_json;
+    use serde_json::Value as SerdeJson;
+
+    #[test]
+    fn keep_the_order_of_the_keys() {
+        let content = r#"{
+          "version": "1",
+          "zeta": { "b": [1, {"d": 2, "c": 3}], "a": {} },
+          "build_cookbook": "./.delivery/build_cookbook",
+          "alpha": []
+        }"#;
+        let mut json: SerdeJson = serde_json::from_str(content).unwrap();
+        json["build_cookbook"] = json!({"path": "./.delivery/build_cookbook", "name": "b"});
+        assert_eq!(
+            "{\n  \"version\": \"1\",\n  \"zeta\": {\n    \"b\": [\n      1,\n      {\n        \
+             \"d\": 2,\n        \"c\": 3\n      }\n    ],\n    \"a\": {}\n  },\n  \
+             \"build_cookbook\": {\n    \"name\": \"b\",\n    \"path\": \
+             \"./.delivery/build_cookbook\"\n  },\n  \"alpha\": []\n}",
+            to_string_in_order(&json, content)
+        );
+    }
+
+    #[test]
+    fn line_diff_of_two_texts() {
+        let old = "{\n  \"a\": 1,\n  \"b\": 2\n}\n";
+        let new = "{\n  \"a\": 1,\n  \"b\": 3,\n  \"c\": 4\n}\n";
+        assert_eq!(
+            vec![
+                " {",
+                "   \"a\": 1,",
+                "-  \"b\": 2",
+                "+  \"b\": 3,",
+                "+  \"c\": 4",
+                " }",
+            ],
+            line_diff(old, new)
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use cli::deps::DepsClapOptions;
+use command::Command;
+use config::Config;
+use delivery_config::dependencies::{Dependency, DependencyGraph};
+use delivery_config::DeliveryConfig;
+use errors::{DeliveryError, Kind};
+use git;
+use project;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use tempdir::TempDir;
+use types::{DeliveryResult, ExitCode};
+use utils::cwd;
+use utils::path_to_string;
+use utils::say::{say, sayln};
+
+pub struct DepsCommand<'n> {
+    pub options: &'n DepsClapOptions<'n>,
+    pub config: &'n Config,
+}
+
+impl<'n> Command for DepsCommand<'n> {
+    fn run(&self) -> DeliveryResult<ExitCode> {
+        let project_root = project::root_dir(&cwd())?;
+        let project_name = self.config.project()?;
+        let mut graph = DependencyGraph::new();
+        let mut sources = BTreeMap::new();
+
+        // Every project checked out next to this one is part of the graph,
+        // that way we know about the projects that depend on this one.
+        if let Some(parent) = project_root.parent() {
+            for entry in fs::read_dir(parent)? {
+                let path = entry?.path();
+                if path == project_root || !DeliveryConfig::config_file_path(&path).exists() {
+                    continue;
+                }
+                if let Some(name) = self.checkout_name(&path) {
+                    self.add_project(&mut graph, &mut sources, &name, &path);
+                }
+            }
+        }
+        self.add_project(&mut graph, &mut sources, &project_name, &project_root);
+
+        if self.options.fetch {
+            self.fetch_missing_projects(&mut graph, &mut sources, &project_name)?;
+        }
+
+        if self.options.dot {
+            say("white", &graph.to_dot());
+        } else if self.options.reverse {
+            let dependents = graph.reverse_dependencies(&project_name);
+            if dependents.is_empty() {
+                sayln("white", &format!("No project depends on {}", project_name));
+            } else {
+                sayln(
+                    "white",
+                    &format!("Projects that ship if {} changes:", project_name),
+                );
+                for p in dependents.iter() {
+                    sayln("yellow", &format!("  {}", p));
+                }
+            }
+        } else {
+            for line in graph.tree(&project_name) {
+                sayln("white", &line);
+            }
+        }
+
+        let cycles = graph.cycles();
+        for cycle in cycles.iter() {
+            sayln(
+                "red",
+                &format!("Dependency cycle detected: {}", cycle.join(" -> ")),
+            );
+        }
+        Ok(if cycles.is_empty() { 0 } else { 1 })
+    }
+}
+
+impl<'n> DepsCommand<'n> {
+    // The name of a project checked out next to this one, taken from its
+    // delivery remote (`.../<ent>/<org>/<project>`) since the directory
+    // can have any name. Without a delivery remote we fall back to the
+    // name of the directory.
+    fn checkout_name(&self, path: &Path) -> Option<String> {
+        let remote = git::delivery_remote_from_repo(path).unwrap_or_default();
+        let mut segments = remote.trim_end_matches('/').rsplit('/');
+        match (segments.next(), segments.next()) {
+            (Some(project), Some(org)) if !project.is_empty() && !org.is_empty() => {
+                let dep = Dependency {
+                    organization: Some(org.to_string()),
+                    project: project.to_string(),
+                    pipeline: None,
+                };
+                Some(dep.name(self.organization().as_ref().map(|o| o.as_str())))
+            }
+            _ => path.file_name().map(|n| n.to_string_lossy().into_owned()),
+        }
+    }
+
+    fn organization(&self) -> Option<String> {
+        self.config.organization().ok()
+    }
+
+    // Add a project and its dependencies to the graph, a project with an
+    // invalid config.json is reported and left out. Where to fetch every
+    // dependency from is kept in `sources`.
+    fn add_project(
+        &self,
+        graph: &mut DependencyGraph,
+        sources: &mut BTreeMap<String, Dependency>,
+        name: &str,
+        path: &Path,
+    ) {
+        let org = self.organization();
+        match dependencies_of(path) {
+            Ok(deps) => {
+                let names: Vec<String> = deps
+                    .iter()
+                    .map(|d| d.name(org.as_ref().map(|o| o.as_str())))
+                    .collect();
+                graph.add(name, &names);
+                for (name, dep) in names.into_iter().zip(deps.into_iter()) {
+                    sources.entry(name).or_insert(dep);
+                }
+            }
+            Err(e) => sayln(
+                "yellow",
+                &format!("Ignoring the config.json of {}: {}", name, e),
+            ),
+        }
+    }
+
+    // Clone the dependencies we don't know about from the Automate server
+    // until every project reachable from `root` is in the graph. Every
+    // dependency is cloned from its own organization and pipeline, falling
+    // back to the ones of this project, and one that can't be cloned is
+    // reported and shown as not found.
+    fn fetch_missing_projects(
+        &self,
+        graph: &mut DependencyGraph,
+        sources: &mut BTreeMap<String, Dependency>,
+        root: &str,
+    ) -> DeliveryResult<()> {
+        let tempdir = TempDir::new("delivery-deps")?;
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![root.to_string()];
+        while let Some(current) = pending.pop() {
+            let deps: Vec<String> = graph.dependencies(&current).into_iter().cloned().collect();
+            for dep in deps {
+                if !visited.insert(dep.clone()) {
+                    continue;
+                }
+                if graph.contains(&dep) {
+                    pending.push(dep);
+                    continue;
+                }
+                let source = match sources.get(&dep) {
+                    Some(source) => source.clone(),
+                    None => continue,
+                };
+                // Two organizations can have a project with the same name
+                let path = tempdir.path().join(dep.replace('/', "-"));
+                say("white", "Fetching the config.json of ");
+                sayln("yellow", &dep);
+                if let Err(e) = self.clone_dependency(&source, &path, tempdir.path()) {
+                    sayln("yellow", &format!("Could not fetch {}: {}", dep, e));
+                    continue;
+                }
+                self.add_project(graph, sources, &dep, &path);
+                // Mark it as known even if its config.json is invalid
+                graph.add(&dep, &[]);
+                pending.push(dep);
+            }
+        }
+        Ok(())
+    }
+
+    fn clone_dependency(&self, dep: &Dependency, path: &Path, dir: &Path) -> DeliveryResult<()> {
+        let config = self
+            .config
+            .clone()
+            .set_organization(dep.organization.as_ref().map_or("", |o| o.as_str()))
+            .set_pipeline(dep.pipeline.as_ref().map_or("", |p| p.as_str()))
+            .set_project(&dep.project);
+        git::git_command(
+            &[
+                "clone",
+                "--depth",
+                "1",
+                "--branch",
+                &config.pipeline()?,
+                &config.delivery_git_ssh_url()?,
+                &path_to_string(path),
+            ],
+            dir,
+        )?;
+        Ok(())
+    }
+}
+
+// The projects listed in the `dependencies` of the config.json of a project
+fn dependencies_of(path: &Path) -> DeliveryResult<Vec<Dependency>> {
+    let config = DeliveryConfig::load_config(path)?;
+    config
+        .dependencies
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| match Dependency::parse(&entry) {
+            Some(dep) => Ok(dep),
+            None => Err(DeliveryError {
+                kind: Kind::DeliveryConfigParse,
+                detail: Some(format!(
+                    "invalid dependency '{}' in {}, expected [organization/]project[:pipeline]",
+                    entry,
+                    path_to_string(DeliveryConfig::config_file_path(path))
+                )),
+            }),
+        })
+        .collect()
+}
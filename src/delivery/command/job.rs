@@ -18,6 +18,7 @@
 use cli::job::JobClapOptions;
 use command::Command;
 use config::Config;
+use delivery_config::DeliveryConfig;
 use errors::{DeliveryError, Kind};
 use fips;
 use git;
@@ -137,7 +138,22 @@ impl<'n> Command for JobCommand<'n> {
         } else {
             String::from(self.options.git_url)
         };
-        try!(ws.setup_repo_for_change(&clone_url, &c, &pi, self.options.shasum));
+        let changed_files = try!(ws.setup_repo_for_change(&clone_url, &c, &pi, self.options.shasum));
+        let config_json = try!(DeliveryConfig::load_config(&ws.repo));
+        let paths_decision = match config_json.paths {
+            Some(ref paths) => Some(try!(paths.decide(&phases, changed_files.as_ref()))),
+            None => None,
+        };
+        let phases = if self.options.include_skipped {
+            phases
+        } else {
+            let phases = super::without_skipped_phases(&config_json, &phases);
+            super::without_untouched_phases(paths_decision.as_ref(), &phases)
+        };
+        if phases.is_empty() {
+            sayln("yellow", "Every phase of this job is skipped, nothing to run");
+            return Ok(0);
+        }
         sayln("white", "Configuring the job");
         // This can be optimized out, almost certainly
         try!(utils::remove_recursive(&ws.chef.join("build_cookbook")));
@@ -154,7 +170,13 @@ impl<'n> Command for JobCommand<'n> {
             change_id: self.options.change_id.to_string(),
             patchset_number: patch.to_string(),
         };
-        try!(ws.setup_chef_for_job(&self.config, change, &ws_path));
+        try!(ws.setup_chef_for_job(
+            &self.config,
+            change,
+            &ws_path,
+            self.options.no_cache,
+            paths_decision
+        ));
         sayln("white", "Running the job");
 
         let privilege_drop = if privileged_process() {
@@ -173,7 +195,7 @@ impl<'n> Command for JobCommand<'n> {
             "magenta",
             &format!("Running {} {}", phase_msg, phases.join(", ")),
         );
-        try!(ws.run_job(self.options.phases, &privilege_drop, &local_change));
+        try!(ws.run_job(&phases.join(" "), &privilege_drop, &local_change));
         Ok(0)
     }
 }
@@ -221,6 +243,8 @@ pub fn run_docker_job(opts: &JobClapOptions) -> DeliveryResult<ExitCode> {
     let flags = vec![
         ("--skip-default", &opts.skip_default),
         ("--local", &opts.local),
+        ("--no-cache", &opts.no_cache),
+        ("--include-skipped", &opts.include_skipped),
     ];
 
     for (flag, value) in flags {
@@ -0,0 +1,150 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use cli::job_dispatch::JobDispatchClapOptions;
+use command::Command;
+use config::Config;
+use delivery_config::extends::{self, BaseCache};
+use delivery_config::job_dispatch::{explain, Explanation, Node, PHASES};
+use delivery_config::{DeliveryConfig, JobDispatch};
+use errors::{DeliveryError, Kind};
+use serde_json;
+use serde_json::Value as SerdeJson;
+use std::path::{Path, PathBuf};
+use types::{DeliveryResult, ExitCode};
+use utils::say::{say, sayln};
+use utils::{cwd, read_file};
+
+pub struct JobDispatchCommand<'n> {
+    pub options: &'n JobDispatchClapOptions<'n>,
+    pub config: &'n Config,
+}
+
+impl<'n> Command for JobDispatchCommand<'n> {
+    fn run(&self) -> DeliveryResult<ExitCode> {
+        let config_json = self.load_config_json()?;
+        let job_dispatch = match config_json.get("job_dispatch") {
+            Some(jd) => Some(serde_json::from_value::<JobDispatch>(jd.clone()).map_err(|e| {
+                DeliveryError {
+                    kind: Kind::DeliveryConfigParse,
+                    detail: Some(format!("  $.job_dispatch: {}", e)),
+                }
+            })?),
+            None => None,
+        };
+        let skip_phases: Vec<String> = config_json
+            .get("skip_phases")
+            .and_then(|s| serde_json::from_value(s.clone()).ok())
+            .unwrap_or_default();
+        let phases: Vec<String> = PHASES
+            .iter()
+            .filter(|p| !skip_phases.iter().any(|s| s.as_str() == **p))
+            .map(|p| p.to_string())
+            .collect();
+        let nodes = self
+            .options
+            .nodes
+            .iter()
+            .map(|n| load_node(Path::new(n)))
+            .collect::<DeliveryResult<Vec<Node>>>()?;
+
+        let explanation = explain(job_dispatch.as_ref(), &phases, &nodes);
+        let failed =
+            !explanation.errors.is_empty() || !explanation.unassignable_phases().is_empty();
+        if self.options.json {
+            sayln("white", &serde_json::to_string_pretty(&explanation)?);
+        } else {
+            print_explanation(&explanation);
+        }
+        Ok(if failed { 1 } else { 0 })
+    }
+}
+
+impl<'n> JobDispatchCommand<'n> {
+    // The config.json provided with --config-json or the one of the project
+    fn load_config_json(&self) -> DeliveryResult<SerdeJson> {
+        let path = if self.options.config_json.is_empty() {
+            DeliveryConfig::find_config_file(&cwd())?
+        } else {
+            PathBuf::from(self.options.config_json)
+        };
+        extends::resolve(&path, &BaseCache::new()?)
+    }
+}
+
+// The name of a node is the `name` (or `fqdn`) attribute of its JSON,
+// falling back to the name of the file.
+fn load_node(path: &Path) -> DeliveryResult<Node> {
+    let attributes: SerdeJson = serde_json::from_str(&read_file(path)?).map_err(|e| {
+        DeliveryError {
+            kind: Kind::JsonParseError,
+            detail: Some(format!("{}: {}", path.display(), e)),
+        }
+    })?;
+    let name = ["name", "fqdn"]
+        .iter()
+        .filter_map(|k| attributes.get(*k).and_then(|n| n.as_str()))
+        .next()
+        .map(String::from)
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string())
+        });
+    Ok(Node {
+        name: name,
+        attributes: attributes,
+    })
+}
+
+fn print_explanation(explanation: &Explanation) {
+    for phase in explanation.phases.iter() {
+        say("white", "Phase ");
+        say("magenta", &phase.phase);
+        match phase.clauses {
+            None => sayln("white", ": no filters, any node can run it"),
+            Some(ref clauses) => {
+                sayln("white", "");
+                for (i, clause) in clauses.iter().enumerate() {
+                    let attributes: Vec<String> = clause
+                        .clause
+                        .iter()
+                        .map(|(k, v)| format!("{} in [{}]", k, v.join(", ")))
+                        .collect();
+                    say(
+                        "white",
+                        &format!("  clause {}: {} => ", i + 1, attributes.join(" and ")),
+                    );
+                    if clause.nodes.is_empty() {
+                        sayln("yellow", "no node");
+                    } else {
+                        sayln("green", &clause.nodes.join(", "));
+                    }
+                }
+            }
+        }
+    }
+    for e in explanation.errors.iter() {
+        sayln("red", &format!("Malformed filter {}", e));
+    }
+    let unassignable = explanation.unassignable_phases();
+    if !unassignable.is_empty() {
+        sayln(
+            "red",
+            &format!("No eligible builder for: {}", unassignable.join(", ")),
+        );
+    }
+}
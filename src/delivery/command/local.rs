@@ -17,13 +17,29 @@
 
 use cli::local::LocalClapOptions;
 use command::Command;
-use delivery_config::project::{Phase, ProjectToml};
-use errors::{DeliveryError, Kind};
+use delivery_config::project::{LocalPhase, Phase, PhaseCommand, ProjectToml};
+use delivery_config::DeliveryConfig;
+use errors::{DeliveryError, Kind, PhaseFailure};
 use project;
-use std::process::Stdio;
+use project::watch::Watcher;
+use std::cmp;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use types::{DeliveryResult, ExitCode};
 use utils;
 use utils::say::{say, sayln};
+use utils::{InterruptGuard, ProcessGroup};
+
+// How often the files of the project are looked at, and how long they
+// must stay the same before the phase runs again.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
 
 pub struct LocalCommand<'n> {
     pub options: &'n LocalClapOptions<'n>,
@@ -33,18 +49,47 @@ pub struct LocalCommand<'n> {
 impl<'n> Command for LocalCommand<'n> {
     fn run(&self) -> DeliveryResult<ExitCode> {
         sayln("green", "Chef Delivery");
+        if self.options.watch {
+            return self.watch();
+        }
+        self.run_once(&RunContext::default())
+    }
+}
 
+impl<'n> LocalCommand<'n> {
+    fn run_once(&self, ctx: &RunContext) -> DeliveryResult<ExitCode> {
         // If a Stage was provided, trigger their phases in order
-        if let Some(stage) = self.options.stage.clone() {
+        let name = self.options.stage_phase;
+        let config_json = self.skip_config();
+        let is_skipped = |p: &Phase| is_skipped(config_json.as_ref(), p);
+        if let Some(stage) = try!(self.config.stage(name)) {
             say("white", "Running ");
             say("yellow", &format!("{}", stage));
             sayln("white", " Stage");
-            for phase in stage.phases().into_iter() {
-                match try!(exec_phase(&self.config.clone(), Some(phase))) {
+            if self.options.jobs > 1 {
+                for group in stage.phase_groups() {
+                    let phases: Vec<Phase> =
+                        group.into_iter().filter(|p| !is_skipped(p)).collect();
+                    try!(exec_phases_in_parallel(
+                        self.config,
+                        phases,
+                        self.options.jobs,
+                        ctx
+                    ));
+                }
+                return Ok(0);
+            }
+            let phases: Vec<Phase> = stage
+                .phases()
+                .into_iter()
+                .filter(|p| !is_skipped(p))
+                .collect();
+            for phase in phases.into_iter() {
+                match try!(exec_phase(&self.config.clone(), Some(phase), ctx)) {
                     0 => continue,
                     exit_code => {
                         return Err(DeliveryError {
-                            kind: Kind::PhaseFailed(exit_code),
+                            kind: Kind::PhaseFailed(PhaseFailure::new(exit_code)),
                             detail: None,
                         })
                     }
@@ -52,18 +97,230 @@ impl<'n> Command for LocalCommand<'n> {
             }
             Ok(0)
         } else {
-            exec_phase(self.config, self.options.phase.clone())
+            let phase = try!(self.config.phase(name).ok_or_else(|| DeliveryError {
+                kind: Kind::PhaseNotFound,
+                detail: Some(format!(
+                    "'{}' is neither a phase nor a stage of your .delivery/project.toml, \
+                     the available ones are: {}",
+                    name,
+                    self.config.names().join(", ")
+                )),
+            }));
+            if is_skipped(&phase) {
+                Ok(0)
+            } else {
+                exec_phase(self.config, Some(phase), ctx)
+            }
+        }
+    }
+
+    // Run the phase or the stage every time the files of the project
+    // change, a change cancels the run that is in progress. Ctrl-C stops
+    // the run in progress, if any, and then stops watching.
+    fn watch(&self) -> DeliveryResult<ExitCode> {
+        let _guard = InterruptGuard::new();
+        let mut watcher = try!(Watcher::new(&try!(project::project_path())));
+        let ctx = RunContext::default();
+        let cancel = ctx.cancel.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || loop {
+            let changes = watcher.wait_for_changes(WATCH_INTERVAL, WATCH_DEBOUNCE);
+            let failed = changes.is_err();
+            if !failed {
+                cancel.store(true, Ordering::SeqCst);
+            }
+            if tx.send(changes).is_err() || failed {
+                break;
+            }
+        });
+        loop {
+            ctx.cancel.store(false, Ordering::SeqCst);
+            match self.run_once(&ctx) {
+                Ok(0) => sayln("green", "Passed"),
+                Ok(exit_code) => sayln("red", &format!("Failed with exit code {}", exit_code)),
+                Err(DeliveryError {
+                    kind: Kind::PhaseCancelled,
+                    ..
+                }) => sayln("yellow", "Cancelled"),
+                Err(DeliveryError {
+                    kind: Kind::Interrupted,
+                    ..
+                }) => {}
+                Err(e) => sayln("red", &format!("Failed: {}", e)),
+            }
+            if !ctx.cancel.load(Ordering::SeqCst) && !utils::interrupted() {
+                sayln("white", "Waiting for changes (press Ctrl-C to stop)");
+            }
+            let mut changed = loop {
+                if utils::interrupted() {
+                    sayln("white", "Stopped watching");
+                    return Ok(0);
+                }
+                match rx.recv_timeout(Duration::from_millis(50)) {
+                    Ok(changes) => break try!(changes),
+                    Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(0),
+                }
+            };
+            while let Ok(changes) = rx.try_recv() {
+                changed.extend(try!(changes));
+            }
+            sayln(
+                "yellow",
+                &format!("Changed: {}", describe_changes(&changed)),
+            );
+        }
+    }
+
+    // Load the `.delivery/config.json` that lists the phases to skip, a
+    // project without a config.json doesn't skip any phase and one that
+    // can't be loaded is reported and ignored.
+    fn skip_config(&self) -> Option<DeliveryConfig> {
+        if self.options.include_skipped {
+            return None;
+        }
+        let loaded = project::project_path().and_then(|p| {
+            if DeliveryConfig::config_file_path(&p).exists() {
+                DeliveryConfig::load_config(p).map(Some)
+            } else {
+                Ok(None)
+            }
+        });
+        loaded.unwrap_or_else(|e| {
+            sayln(
+                "yellow",
+                &format!("Ignoring the skip_phases of your .delivery/config.json: {}", e),
+            );
+            None
+        })
+    }
+}
+
+// Verify if the config.json, if any, skips the phase
+fn is_skipped(config_json: Option<&DeliveryConfig>, phase: &Phase) -> bool {
+    match config_json {
+        Some(c) => {
+            let name = phase.to_string();
+            super::without_skipped_phases(c, &[&name]).is_empty()
+        }
+        None => false,
+    }
+}
+
+// List the first changed files
+fn describe_changes(changed: &[PathBuf]) -> String {
+    let mut files: Vec<String> = changed.iter().take(3).map(utils::path_to_string).collect();
+    if changed.len() > files.len() {
+        files.push(format!("and {} more", changed.len() - files.len()));
+    }
+    files.join(", ")
+}
+
+// Where the output of a phase goes, the terminal or, when phases run in
+// parallel, the terminal with every line prefixed with the phase name.
+#[derive(Clone)]
+enum PhaseOutput {
+    Inherit,
+    Prefixed(String),
+}
+
+impl PhaseOutput {
+    fn stdio(&self) -> Stdio {
+        match *self {
+            PhaseOutput::Inherit => Stdio::inherit(),
+            PhaseOutput::Prefixed(_) => Stdio::piped(),
         }
     }
+
+    // Print a message about the phase, not one of its own lines
+    fn say(&self, color: &str, msg: &str) {
+        match *self {
+            PhaseOutput::Inherit => sayln(color, msg),
+            PhaseOutput::Prefixed(ref prefix) => println!("{} {}", prefix, msg),
+        }
+    }
+
+    // Copy the output of a process line by line, a whole line is printed
+    // at once so the lines of the phases running in parallel don't mix.
+    fn forward<R>(&self, reader: Option<R>, stderr: bool) -> Option<thread::JoinHandle<()>>
+    where
+        R: Read + Send + 'static,
+    {
+        let prefix = match *self {
+            PhaseOutput::Prefixed(ref p) => p.clone(),
+            PhaseOutput::Inherit => return None,
+        };
+        reader.map(|r| {
+            thread::spawn(move || {
+                for line in BufReader::new(r).lines() {
+                    match line {
+                        Ok(l) if stderr => eprintln!("{} {}", prefix, l),
+                        Ok(l) => println!("{} {}", prefix, l),
+                        Err(_) => break,
+                    }
+                }
+            })
+        })
+    }
 }
 
-fn exec_phase(project_toml: &ProjectToml, phase: Option<Phase>) -> DeliveryResult<ExitCode> {
-    if let Some(phase_cmd) = try!(project_toml.local_phase(phase.clone())) {
-        say("white", "Running ");
-        say("magenta", &format!("{:?}", phase.unwrap()));
-        sayln("white", " Phase");
-        debug!("Executing command: {}", phase_cmd);
-        exec_command(&phase_cmd)
+// How a phase runs, where its output goes, a flag that cancels it, when
+// it times out and the shell of the phases that are a string (the one of
+// the user if `None`).
+#[derive(Clone)]
+struct RunContext {
+    output: PhaseOutput,
+    cancel: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    shell: Option<String>,
+}
+
+impl Default for RunContext {
+    fn default() -> Self {
+        RunContext {
+            output: PhaseOutput::Inherit,
+            cancel: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+            shell: None,
+        }
+    }
+}
+
+impl RunContext {
+    fn timed_out(&self) -> bool {
+        self.deadline.map_or(false, |d| Instant::now() >= d)
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+fn exec_phase(
+    project_toml: &ProjectToml,
+    phase: Option<Phase>,
+    ctx: &RunContext,
+) -> DeliveryResult<ExitCode> {
+    if let Some(local_phase) = try!(project_toml.local_phase(phase.clone())) {
+        match ctx.output {
+            PhaseOutput::Inherit => {
+                say("white", "Running ");
+                let name = match phase.unwrap() {
+                    Phase::Custom(name) => name,
+                    p => format!("{:?}", p),
+                };
+                say("magenta", &name);
+                sayln("white", " Phase");
+            }
+            PhaseOutput::Prefixed(ref prefix) => println!("{} Running {}", prefix, local_phase),
+        }
+        debug!("Executing command: {}", local_phase);
+        exec_local_phase(
+            &local_phase,
+            &try!(project::project_path()),
+            &BTreeMap::new(),
+            ctx,
+        )
     } else {
         let p = phase.unwrap();
         sayln(
@@ -78,25 +335,451 @@ fn exec_phase(project_toml: &ProjectToml, phase: Option<Phase>) -> DeliveryResul
         Ok(1)
     }
 }
-fn exec_command(cmd: &str) -> DeliveryResult<ExitCode> {
-    // TODO: I just copy paste the old code and modified a little bit
-    // so it works but we have to work on UW-75 to make it right!
-    // We should maybe create a tempfile to stick the command coming from
-    // the config instead of running `chef exec` as the command.
-    let mut split_cmd = cmd.split_whitespace();
-    let c = split_cmd.next().unwrap();
-    let args_vec = split_cmd.collect::<Vec<&str>>();
-    let output = utils::make_command(c)
-        .args(&args_vec)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .current_dir(try!(project::project_path()))
-        .output()
-        .unwrap_or_else(|e| panic!("Unexpected error: Failed to execute process: {}", e));
-
-    let return_code = match output.status.code() {
-        Some(code) => code,
-        _ => 1,
+
+// Run independent phases, at most `jobs` at a time. Every phase runs even
+// if another one fails, then the phases that failed are reported and the
+// first failure is returned.
+fn exec_phases_in_parallel(
+    project_toml: &ProjectToml,
+    phases: Vec<Phase>,
+    jobs: usize,
+    ctx: &RunContext,
+) -> DeliveryResult<()> {
+    if phases.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<String> = phases.iter().map(|p| p.to_string()).collect();
+    sayln(
+        "white",
+        &format!("Running {} ({} at a time)", names.join(", "), jobs),
+    );
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    let queue = Arc::new(Mutex::new(
+        phases.iter().cloned().collect::<VecDeque<Phase>>(),
+    ));
+    let (tx, rx) = mpsc::channel();
+    let workers: Vec<thread::JoinHandle<()>> = (0..cmp::min(jobs, phases.len()))
+        .map(|_| {
+            let queue = queue.clone();
+            let tx = tx.clone();
+            let project_toml = project_toml.clone();
+            let cancel = ctx.cancel.clone();
+            let shell = ctx.shell.clone();
+            thread::spawn(move || loop {
+                if utils::interrupted() {
+                    break;
+                }
+                let phase = match queue.lock().unwrap().pop_front() {
+                    Some(p) => p,
+                    None => break,
+                };
+                let prefix = format!("[{:width$}]", phase.to_string(), width = width);
+                let ctx = RunContext {
+                    output: PhaseOutput::Prefixed(prefix),
+                    cancel: cancel.clone(),
+                    deadline: None,
+                    shell: shell.clone(),
+                };
+                let result = exec_phase(&project_toml, Some(phase.clone()), &ctx);
+                if tx.send((phase, result)).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+    let mut results: Vec<(Phase, DeliveryResult<ExitCode>)> = rx.iter().collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    // Report in the order of the stage, not in the order they finished
+    results.sort_by_key(|&(ref phase, _)| phases.iter().position(|p| p == phase));
+    let mut failure = None;
+    for (phase, result) in results {
+        match result {
+            Ok(0) => sayln("green", &format!("{} passed", phase)),
+            Ok(exit_code) => {
+                sayln(
+                    "red",
+                    &format!("{} failed with exit code {}", phase, exit_code),
+                );
+                if failure.is_none() {
+                    failure = Some(DeliveryError {
+                        kind: Kind::PhaseFailed(PhaseFailure::new(exit_code)),
+                        detail: None,
+                    });
+                }
+            }
+            Err(e) => {
+                sayln("red", &format!("{} failed: {}", phase, e));
+                if failure.is_none() {
+                    failure = Some(e);
+                }
+            }
+        }
+    }
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+// Run a local phase in `dir`. A table that fails runs again up to its
+// `retries`, and every attempt is killed after its `timeout`; a failure
+// that used them is a `PhaseFailed` error that tells which attempt failed.
+fn exec_local_phase(
+    local_phase: &LocalPhase,
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+    ctx: &RunContext,
+) -> DeliveryResult<ExitCode> {
+    let table = match *local_phase {
+        LocalPhase::Shell(ref cmd) => {
+            let command = match ctx.shell {
+                Some(ref shell) => utils::shell_command_with(shell, cmd),
+                None => utils::shell_command(cmd),
+            };
+            return exec_command(command, cmd, dir, env, ctx);
+        }
+        LocalPhase::Command(ref table) => table,
     };
-    Ok(return_code)
+    let attempts = table.retries.unwrap_or(0) + 1;
+    let mut attempt = 1;
+    loop {
+        let mut attempt_ctx = ctx.clone();
+        if let Some(timeout) = table.timeout {
+            let deadline = Instant::now() + Duration::from_secs(timeout);
+            attempt_ctx.deadline = Some(ctx.deadline.map_or(deadline, |d| cmp::min(d, deadline)));
+        }
+        let failure = match exec_table(local_phase, table, dir, env, &attempt_ctx) {
+            Ok(0) => return Ok(0),
+            Ok(exit_code) if attempts == 1 => return Ok(exit_code),
+            Ok(exit_code) => PhaseFailure::new(exit_code),
+            Err(DeliveryError {
+                kind: Kind::PhaseFailed(failure),
+                ..
+            }) => failure,
+            Err(e) => return Err(e),
+        };
+        let failure = if attempts > 1 {
+            PhaseFailure {
+                attempt: attempt,
+                attempts: attempts,
+                ..failure
+            }
+        } else {
+            failure
+        };
+        if attempt == attempts || ctx.timed_out() {
+            return Err(DeliveryError {
+                kind: Kind::PhaseFailed(failure),
+                detail: None,
+            });
+        }
+        let delay = table.retry_delay.unwrap_or(0);
+        ctx.output.say(
+            "yellow",
+            &format!("{} Retrying in {} seconds", failure, delay),
+        );
+        try!(wait_to_retry(Duration::from_secs(delay), ctx));
+        attempt += 1;
+    }
+}
+
+// The steps of a table run in order until one of them fails
+fn exec_table(
+    local_phase: &LocalPhase,
+    table: &PhaseCommand,
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+    ctx: &RunContext,
+) -> DeliveryResult<ExitCode> {
+    let dir = match table.cwd {
+        Some(ref cwd) => dir.join(cwd),
+        None => dir.to_path_buf(),
+    };
+    let mut env = env.clone();
+    env.extend(table.env.clone());
+    if let Some(ref command) = table.command {
+        let mut cmd = utils::make_command(command);
+        cmd.args(&table.args);
+        return exec_command(cmd, &local_phase.to_string(), &dir, &env, ctx);
+    }
+    for step in table.steps.iter().flat_map(|s| s.iter()) {
+        match exec_local_phase(step, &dir, &env, ctx)? {
+            0 => continue,
+            exit_code => return Ok(exit_code),
+        }
+    }
+    Ok(0)
+}
+
+// Wait before running a phase again, unless it is cancelled meanwhile
+fn wait_to_retry(delay: Duration, ctx: &RunContext) -> DeliveryResult<()> {
+    let until = Instant::now() + delay;
+    loop {
+        if utils::interrupted() {
+            return Err(DeliveryError {
+                kind: Kind::Interrupted,
+                detail: None,
+            });
+        }
+        if ctx.cancelled() {
+            return Err(DeliveryError {
+                kind: Kind::PhaseCancelled,
+                detail: None,
+            });
+        }
+        let now = Instant::now();
+        if now >= until {
+            return Ok(());
+        }
+        thread::sleep(cmp::min(until - now, Duration::from_millis(50)));
+    }
+}
+
+fn exec_command(
+    mut cmd: process::Command,
+    description: &str,
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+    ctx: &RunContext,
+) -> DeliveryResult<ExitCode> {
+    cmd.envs(env)
+        .current_dir(dir)
+        .stdout(ctx.output.stdio())
+        .stderr(ctx.output.stdio());
+    // The phases that run in parallel can't share the terminal, only a
+    // phase that runs on its own gets to read from it.
+    let foreground = match ctx.output {
+        PhaseOutput::Inherit => true,
+        PhaseOutput::Prefixed(_) => false,
+    };
+    let mut group = ProcessGroup::spawn(&mut cmd, foreground).map_err(|e| DeliveryError {
+        kind: Kind::FailedToExecute,
+        detail: Some(format!(
+            "Unable to run `{}` in {}: {}",
+            description,
+            dir.display(),
+            e
+        )),
+    })?;
+    let forwarders = vec![
+        ctx.output.forward(group.child.stdout.take(), false),
+        ctx.output.forward(group.child.stderr.take(), true),
+    ];
+    // The whole process group is killed, so that nothing the phase
+    // started keeps running.
+    let status = loop {
+        if let Some(status) = group.try_wait()? {
+            if utils::interrupted() {
+                break Err(Kind::Interrupted);
+            }
+            break Ok(status);
+        }
+        if utils::interrupted() {
+            group.kill()?;
+            break Err(Kind::Interrupted);
+        }
+        if ctx.cancelled() {
+            group.kill()?;
+            break Err(Kind::PhaseCancelled);
+        }
+        if ctx.timed_out() {
+            group.kill()?;
+            break Err(Kind::PhaseFailed(PhaseFailure {
+                timed_out: true,
+                ..PhaseFailure::new(1)
+            }));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    for forwarder in forwarders.into_iter().flatten() {
+        let _ = forwarder.join();
+    }
+    match status {
+        // A process killed by a signal has no exit code
+        Ok(status) => Ok(status.code().unwrap_or(1)),
+        Err(kind) => Err(DeliveryError {
+            kind: kind,
+            detail: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    // Run the phases that are a string with /bin/sh, whatever $SHELL is
+    fn sh_context() -> RunContext {
+        RunContext {
+            shell: Some(String::from("/bin/sh")),
+            ..RunContext::default()
+        }
+    }
+
+    fn table(command: &str, args: &[&str]) -> PhaseCommand {
+        PhaseCommand {
+            command: Some(command.to_string()),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            ..PhaseCommand::default()
+        }
+    }
+
+    #[test]
+    fn run_a_phase_through_the_shell() {
+        let tempdir = TempDir::new("local_phase").unwrap();
+        let phase = LocalPhase::Shell(String::from(
+            "echo 'a b' | grep -q \"a b\" && FOO=bar sh -c 'exit 3'",
+        ));
+        assert_eq!(
+            3,
+            exec_local_phase(
+                &phase,
+                tempdir.path(),
+                &BTreeMap::new(),
+                &sh_context()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn run_the_steps_of_a_table() {
+        let tempdir = TempDir::new("local_phase").unwrap();
+        utils::mkdir_recursive(&tempdir.path().join("sub")).unwrap();
+        let mut check_env = table("sh", &["-c", "test \"$FOO\" = bar && test -f marker"]);
+        check_env.cwd = Some(String::from("sub"));
+        let mut phase = PhaseCommand::default();
+        phase.env.insert(String::from("FOO"), String::from("bar"));
+        phase.steps = Some(vec![
+            LocalPhase::Shell(String::from("touch sub/marker")),
+            LocalPhase::Command(check_env),
+            LocalPhase::Shell(String::from("exit 2")),
+            LocalPhase::Shell(String::from("touch never")),
+        ]);
+        let phase = LocalPhase::Command(phase);
+        assert_eq!(
+            2,
+            exec_local_phase(
+                &phase,
+                tempdir.path(),
+                &BTreeMap::new(),
+                &sh_context()
+            )
+            .unwrap()
+        );
+        assert!(!tempdir.path().join("never").exists());
+    }
+
+    #[test]
+    fn cancel_a_phase_and_every_process_it_started() {
+        let tempdir = TempDir::new("local_phase").unwrap();
+        let phase = LocalPhase::Shell(String::from("sleep 1 && touch marker"));
+        let ctx = sh_context();
+        let cancel = ctx.cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            cancel.store(true, Ordering::SeqCst);
+        });
+        let err = exec_local_phase(&phase, tempdir.path(), &BTreeMap::new(), &ctx).unwrap_err();
+        match err.kind {
+            Kind::PhaseCancelled => {}
+            _ => panic!("expected a PhaseCancelled error, got {:?}", err.kind),
+        }
+        thread::sleep(Duration::from_millis(1500));
+        assert!(!tempdir.path().join("marker").exists());
+    }
+
+    fn steps(steps: &[&str]) -> PhaseCommand {
+        PhaseCommand {
+            steps: Some(
+                steps
+                    .iter()
+                    .map(|s| LocalPhase::Shell(s.to_string()))
+                    .collect(),
+            ),
+            ..PhaseCommand::default()
+        }
+    }
+
+    #[test]
+    fn retry_a_failing_phase() {
+        let tempdir = TempDir::new("local_phase").unwrap();
+        let mut flaky = steps(&["echo attempt >> attempts && test $(wc -l < attempts) -ge 3"]);
+        flaky.retries = Some(2);
+        let phase = LocalPhase::Command(flaky.clone());
+        let exit_code = exec_local_phase(
+            &phase,
+            tempdir.path(),
+            &BTreeMap::new(),
+            &sh_context(),
+        );
+        assert_eq!(0, exit_code.unwrap());
+
+        utils::remove_recursive(&tempdir.path().join("attempts")).unwrap();
+        flaky.retries = Some(1);
+        let phase = LocalPhase::Command(flaky);
+        let err = exec_local_phase(
+            &phase,
+            tempdir.path(),
+            &BTreeMap::new(),
+            &sh_context(),
+        )
+        .unwrap_err();
+        match err.kind {
+            Kind::PhaseFailed(ref failure) => assert_eq!(
+                PhaseFailure {
+                    exit_code: 1,
+                    attempt: 2,
+                    attempts: 2,
+                    timed_out: false,
+                },
+                *failure
+            ),
+            _ => panic!("expected a PhaseFailed error, got {:?}", err.kind),
+        }
+    }
+
+    #[test]
+    fn kill_a_phase_that_times_out() {
+        let tempdir = TempDir::new("local_phase").unwrap();
+        let mut slow = steps(&["sleep 2 && touch marker"]);
+        slow.timeout = Some(1);
+        let phase = LocalPhase::Command(slow);
+        let err = exec_local_phase(
+            &phase,
+            tempdir.path(),
+            &BTreeMap::new(),
+            &sh_context(),
+        )
+        .unwrap_err();
+        match err.kind {
+            Kind::PhaseFailed(ref failure) if failure.timed_out => {}
+            _ => panic!("expected a timed out phase, got {:?}", err.kind),
+        }
+        thread::sleep(Duration::from_millis(1500));
+        assert!(!tempdir.path().join("marker").exists());
+    }
+
+    #[test]
+    fn a_missing_command_is_an_error() {
+        let tempdir = TempDir::new("local_phase").unwrap();
+        let phase = LocalPhase::Command(table("delivery-no-such-command", &[]));
+        let err = exec_local_phase(
+            &phase,
+            tempdir.path(),
+            &BTreeMap::new(),
+            &sh_context(),
+        )
+        .unwrap_err();
+        match err.kind {
+            Kind::FailedToExecute => {}
+            _ => panic!("expected a FailedToExecute error, got {:?}", err.kind),
+        }
+        assert!(err.detail().unwrap().contains("delivery-no-such-command"));
+    }
 }
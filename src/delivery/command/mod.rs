@@ -16,6 +16,8 @@
 //
 
 use config::Config;
+use delivery_config::paths::PathsDecision;
+use delivery_config::DeliveryConfig;
 use git;
 use project;
 use std;
@@ -27,10 +29,16 @@ use utils::say::sayln;
 pub mod api;
 pub mod checkout;
 pub mod clone;
+pub mod config;
+pub mod config_json;
+pub mod deps;
 pub mod diff;
 pub mod init;
 pub mod job;
+pub mod job_dispatch;
 pub mod local;
+pub mod open;
+pub mod plugin;
 pub mod pull;
 pub mod review;
 pub mod setup;
@@ -62,12 +70,12 @@ pub trait Command: Sized {
 pub fn verify_and_repair_git_remote(config: &Config) -> DeliveryResult<()> {
     if !project::git_remote_up_to_date(config)? {
         let p_path = project::project_path()?;
-        let c_path = Config::dot_delivery_cli_path(&cwd()).expect("Unable to find cli.toml");
+        let c_paths = Config::cli_toml_paths(&cwd());
         let git_ssh_url = config.delivery_git_ssh_url()?;
         let msg = &format!(
             "Updating 'delivery' remote with the default configuration \
              loaded from {:?}.\n\tcurrent: {}\n\tupdate:  {}",
-            c_path,
+            c_paths,
             &git::delivery_remote_from_repo(&p_path)?,
             &git_ssh_url
         );
@@ -76,3 +84,50 @@ pub fn verify_and_repair_git_remote(config: &Config) -> DeliveryResult<()> {
     }
     Ok(())
 }
+
+// Remove the phases that the project skips with the `skip_phases` of its
+// `.delivery/config.json`, letting the user know why they don't run.
+pub fn without_skipped_phases<'a>(config: &DeliveryConfig, phases: &[&'a str]) -> Vec<&'a str> {
+    let (run, skipped) = config.partition_phases(phases);
+    for phase in skipped {
+        sayln(
+            "yellow",
+            &format!(
+                "Skipping the {} phase: it is listed in the skip_phases of your \
+                 .delivery/config.json (use --include-skipped to run it)",
+                phase
+            ),
+        );
+    }
+    run
+}
+
+// Remove the phases that none of the changed files trigger, according to
+// the `paths` of the config.json
+pub fn without_untouched_phases<'a>(
+    decision: Option<&PathsDecision>,
+    phases: &[&'a str],
+) -> Vec<&'a str> {
+    let decision = match decision {
+        Some(d) => d,
+        None => return phases.to_vec(),
+    };
+    phases
+        .iter()
+        .cloned()
+        .filter(|phase| {
+            if decision.runs(phase) {
+                return true;
+            }
+            sayln(
+                "yellow",
+                &format!(
+                    "Skipping the {} phase: none of the changed files match its paths in \
+                     your .delivery/config.json (use --include-skipped to run it)",
+                    phase
+                ),
+            );
+            false
+        })
+        .collect()
+}
@@ -0,0 +1,113 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+use cli::open::OpenClapOptions;
+use command::Command;
+use config::Config;
+use errors::{DeliveryError, Kind};
+use types::{DeliveryResult, ExitCode};
+use utils;
+use utils::say::sayln;
+
+pub struct OpenCommand<'n> {
+    pub options: &'n OpenClapOptions<'n>,
+    pub config: &'n Config,
+}
+
+impl<'n> Command for OpenCommand<'n> {
+    fn run(&self) -> DeliveryResult<ExitCode> {
+        let url = try!(item_url(self.config, self.options));
+
+        if self.options.print {
+            sayln("white", &url);
+            return Ok(0);
+        }
+
+        if utils::open::item(&url).is_err() {
+            sayln("yellow", "We could not open the page in the browser for you.");
+            sayln(
+                "yellow",
+                "Make sure there is a program that can open HTML files in your path \
+                 or pass --print to only display the URL.",
+            );
+        }
+        sayln("magenta", &url);
+        Ok(0)
+    }
+}
+
+// Build the url of the page that the end-user asked for
+fn item_url(config: &Config, options: &OpenClapOptions) -> DeliveryResult<String> {
+    match options.item {
+        "change" => {
+            if options.change.is_empty() {
+                return Err(DeliveryError {
+                    kind: Kind::MissingConfig,
+                    detail: Some(String::from(
+                        "A change ID is required, i.e. 'delivery open change <change-id>'",
+                    )),
+                });
+            }
+            if options.stage.is_empty() {
+                config.change_url(options.change)
+            } else {
+                config.change_stage_url(options.change, options.stage)
+            }
+        }
+        "pipeline" => config.pipeline_url(),
+        "users" => config.users_url(),
+        "dashboard" => config.dashboard_url(),
+        _ => config.project_url(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::item_url;
+    use cli::open::OpenClapOptions;
+    use config::Config;
+
+    fn config() -> Config {
+        Config::default()
+            .set_server("automate.example.com")
+            .set_enterprise("ent")
+            .set_organization("org")
+            .set_project("proj")
+    }
+
+    #[test]
+    fn item_url_for_every_item() {
+        let base = "https://automate.example.com/e/ent/#";
+        let mut options = OpenClapOptions::default();
+        assert_eq!(
+            format!("{}/organizations/org/projects/proj", base),
+            item_url(&config(), &options).unwrap()
+        );
+
+        options.item = "users";
+        assert_eq!(format!("{}/users", base), item_url(&config(), &options).unwrap());
+
+        options.item = "change";
+        assert!(item_url(&config(), &options).is_err());
+
+        options.change = "1234";
+        options.stage = "verify";
+        assert_eq!(
+            format!("{}/organizations/org/projects/proj/changes/1234/status/verify", base),
+            item_url(&config(), &options).unwrap()
+        );
+    }
+}
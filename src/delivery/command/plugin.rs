@@ -0,0 +1,128 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! External Subcommands
+//!
+//! Any subcommand that we don't know is dispatched to an executable
+//! named `delivery-<name>` found on the PATH, like git does. The plugin
+//! receives the resolved config so it doesn't have to discover the
+//! cli.toml files on its own:
+//!
+//! * Every plain value as a `DELIVERY_<FIELD>` environment variable, the
+//!   same ones that `delivery` reads, so calling `delivery` again from the
+//!   plugin uses the exact same config.
+//! * The whole config as a JSON file whose path is in `DELIVERY_CONFIG_FILE`.
+//!
+use command::Command;
+use config::Config;
+use errors::{DeliveryError, Kind};
+use serde_json;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::process;
+use tempdir::TempDir;
+use types::{DeliveryResult, ExitCode};
+use utils;
+
+pub const CONFIG_FILE_ENV: &'static str = "DELIVERY_CONFIG_FILE";
+
+pub struct PluginCommand<'n> {
+    pub name: &'n str,
+    pub args: Vec<&'n str>,
+    pub config: &'n Config,
+}
+
+impl<'n> Command for PluginCommand<'n> {
+    fn run(&self) -> DeliveryResult<ExitCode> {
+        let executable = try!(find_plugin(self.name));
+        debug!("Dispatching '{}' to {}", self.name, executable.display());
+
+        // The tempdir has to outlive the plugin process
+        let tempdir = try!(TempDir::new("delivery-plugin"));
+        let config_file = tempdir.path().join("config.json");
+        try!(write_config_json(self.config, &config_file));
+
+        let status = try!(
+            process::Command::new(&executable)
+                .args(&self.args)
+                .envs(self.config.to_env_vars())
+                .env(CONFIG_FILE_ENV, &config_file)
+                .status()
+        );
+        Ok(status.code().unwrap_or(1))
+    }
+}
+
+/// Name of the executable that implements the external subcommand
+pub fn plugin_executable(name: &str) -> String {
+    format!("delivery-{}", name)
+}
+
+fn find_plugin(name: &str) -> DeliveryResult<PathBuf> {
+    utils::find_command(&plugin_executable(name)).ok_or_else(|| DeliveryError {
+        kind: Kind::NoMatchingCommand,
+        detail: Some(format!(
+            "'{}' is not a delivery command and there is no '{}' executable in your PATH.\n\
+             Run 'delivery help' to list the available commands.",
+            name,
+            plugin_executable(name)
+        )),
+    })
+}
+
+// The profiles are already applied, the plugin only needs the result
+fn write_config_json(config: &Config, path: &PathBuf) -> DeliveryResult<()> {
+    let mut resolved = config.clone();
+    resolved.profiles = None;
+    let mut file = try!(File::create(path));
+    try!(file.write_all(serde_json::to_string_pretty(&resolved)?.as_bytes()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use utils::read_file;
+
+    #[test]
+    fn unknown_plugin_is_no_matching_command() {
+        let result = find_plugin("this-plugin-does-not-exist");
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_enum!(err.kind, Kind::NoMatchingCommand);
+        assert!(err
+            .detail
+            .unwrap()
+            .contains("'delivery-this-plugin-does-not-exist'"));
+    }
+
+    #[test]
+    fn config_json_without_profiles() {
+        let tempdir = TempDir::new("plugin").unwrap();
+        let path = tempdir.path().join("config.json");
+        let mut config = Config::default().set_server("automate.example.com");
+        let mut profiles = BTreeMap::new();
+        profiles.insert(String::from("staging"), Config::default());
+        config.profiles = Some(profiles);
+
+        write_config_json(&config, &path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&read_file(&path).unwrap()).unwrap();
+        assert_eq!("automate.example.com", json["server"]);
+        assert_eq!(serde_json::Value::Null, json["profiles"]);
+    }
+}
@@ -18,8 +18,12 @@
 use cli::setup::SetupClapOptions;
 use command::Command;
 use config::Config;
+use http;
+use http::APIClient;
 use std::path::PathBuf;
+use token::TokenStore;
 use types::{DeliveryResult, ExitCode};
+use utils;
 use utils::path_join_many::PathJoinMany;
 use utils::say::{say, sayln};
 
@@ -27,17 +31,41 @@ pub struct SetupCommand<'n> {
     pub options: &'n SetupClapOptions<'n>,
     pub config: &'n Config,
     pub config_path: &'n PathBuf,
+    pub profile: Option<&'n str>,
 }
 
 impl<'n> Command for SetupCommand<'n> {
     fn run(&self) -> DeliveryResult<ExitCode> {
         sayln("green", "Chef Delivery");
 
+        // The wizard only fills in what the command line left out
+        let interactive = !self.options.non_interactive
+            && self.options.missing_required()
+            && utils::stdin_is_terminal();
+        let config = if interactive {
+            match try!(self.wizard()) {
+                Some(c) => c,
+                None => {
+                    sayln("red", "Setup aborted, no configuration was written");
+                    return Ok(1);
+                }
+            }
+        } else {
+            self.config.clone()
+        };
+
         let toml_path = self.config_path.join_many(&[".delivery", "cli.toml"]);
         say("white", "Writing configuration to ");
         sayln("yellow", &format!("{}", toml_path.display()));
 
-        let pretty_toml = self.config.write_file(self.config_path)?;
+        let pretty_toml = match self.profile {
+            Some(name) => {
+                say("white", "Using profile ");
+                sayln("yellow", name);
+                config.write_profile_file(self.config_path, name)?
+            }
+            None => config.write_file(self.config_path)?,
+        };
         sayln("magenta", "New configuration");
         sayln("magenta", "-----------------");
         say("white", &pretty_toml);
@@ -45,3 +73,140 @@ impl<'n> Command for SetupCommand<'n> {
         Ok(0)
     }
 }
+
+impl<'n> SetupCommand<'n> {
+    // Walk the end-user through the options, probing the Automate server
+    // along the way. The options provided through the command line or the
+    // existing cli.toml are offered as defaults.
+    //
+    // Returns `None` if the end-user decided to abort the setup.
+    fn wizard(&self) -> DeliveryResult<Option<Config>> {
+        let mut config = self.config.clone();
+
+        let server = try!(ask("Automate server", config.server.as_ref()));
+        config = config.set_server(&server);
+
+        say("white", "Checking the server status: ");
+        match http::status::get(&config) {
+            Ok(status) => {
+                let color = if status.status == "pong" { "green" } else { "red" };
+                sayln(color, &status.status.replace("pong", "up"));
+                if status.fips_mode.unwrap_or(false) {
+                    sayln("yellow", "The Automate server runs in FIPS mode");
+                    config.fips = Some(true);
+                    let default_port = config
+                        .fips_git_port
+                        .clone()
+                        .unwrap_or(String::from("36534"));
+                    let port = try!(ask(
+                        "Local port to proxy git traffic through Stunnel",
+                        Some(&default_port)
+                    ));
+                    config = config.set_fips_git_port(&port);
+                }
+            }
+            Err(e) => {
+                sayln("red", "unreachable");
+                sayln("red", &format!("{}", e));
+                if let Some(detail) = e.detail() {
+                    sayln("red", &detail);
+                }
+                if !try!(confirm("Continue anyway?", false)) {
+                    return Ok(None);
+                }
+            }
+        }
+
+        let ent = try!(ask("Enterprise", config.enterprise.as_ref()));
+        let org = try!(ask("Organization", config.organization.as_ref()));
+        let user = try!(ask("User", config.user.as_ref()));
+        config = config
+            .set_enterprise(&ent)
+            .set_organization(&org)
+            .set_user(&user);
+
+        if config.saml.is_none() {
+            say("white", "Checking SAML authentication: ");
+            match http::saml::is_enabled(&config) {
+                Ok(saml) => {
+                    sayln("green", if saml { "enabled" } else { "disabled" });
+                    config.saml = Some(saml);
+                }
+                Err(e) => sayln("red", &format!("unable to verify ({})", e)),
+            }
+        }
+
+        if try!(confirm("Fetch an API token now?", true)) {
+            try!(TokenStore::request_token(&config));
+            say("white", "Verifying the enterprise and user: ");
+            let client = try!(APIClient::from_config(&config));
+            if client.user_exists(&user) {
+                sayln("green", "found");
+            } else {
+                sayln(
+                    "red",
+                    &format!(
+                        "the user '{}' was not found in the enterprise '{}'",
+                        user, ent
+                    ),
+                );
+                if !try!(confirm("Write the configuration anyway?", false)) {
+                    return Ok(None);
+                }
+            }
+        } else {
+            sayln(
+                "yellow",
+                "Skipping the verification of the enterprise and user, it requires an API token",
+            );
+        }
+
+        Ok(Some(config))
+    }
+}
+
+// Ask a question to the end-user, an empty answer means the default
+fn ask(question: &str, default: Option<&String>) -> DeliveryResult<String> {
+    match default {
+        Some(d) => say("white", &format!("{} [{}]: ", question, d)),
+        None => say("white", &format!("{}: ", question)),
+    }
+    let answer = try!(utils::read_from_terminal());
+    if answer.is_empty() {
+        Ok(default.cloned().unwrap_or_default())
+    } else {
+        Ok(answer)
+    }
+}
+
+// Ask a yes/no question to the end-user
+fn confirm(question: &str, default: bool) -> DeliveryResult<bool> {
+    let options = if default { "[Y/n]" } else { "[y/N]" };
+    say("white", &format!("{} {} ", question, options));
+    let answer = try!(utils::read_from_terminal());
+    Ok(parse_confirm(&answer, default))
+}
+
+fn parse_confirm(answer: &str, default: bool) -> bool {
+    match answer.trim().to_lowercase().as_ref() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_confirm;
+
+    #[test]
+    fn parse_confirm_answers() {
+        assert!(parse_confirm("y", false));
+        assert!(parse_confirm("YES", false));
+        assert!(!parse_confirm("n", true));
+        assert!(!parse_confirm("No", true));
+        assert!(parse_confirm("", true));
+        assert!(!parse_confirm("", false));
+        assert!(!parse_confirm("maybe", false));
+    }
+}
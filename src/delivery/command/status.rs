@@ -20,7 +20,6 @@ use command::Command;
 use config::Config;
 use http;
 use json::server_status::*;
-use serde_json;
 use std::time::Instant;
 use types::{DeliveryResult, ExitCode};
 use utils::say::{say, sayln};
@@ -42,13 +41,10 @@ impl<'n> Command for StatusCommand<'n> {
         let elapsed_milli =
             (elapsed.as_secs() * 1_000) + (elapsed.subsec_nanos() / 1_000_000) as u64;
 
-        let mut json_string = try!(http::APIClient::extract_pretty_json(&mut result));
-        // Backwards compat: A few versions of the server shipped with a
-        // key with a space in it.
-        json_string = json_string.replace("configuration mode", "configuration_mode");
+        let json_string = try!(http::APIClient::extract_pretty_json(&mut result));
 
         if self.options.json {
-            sayln("white", json_string.as_ref());
+            sayln("white", http::status::normalize_status(&json_string).as_ref());
             return Ok(0);
         }
 
@@ -69,9 +65,7 @@ impl<'n> Command for StatusCommand<'n> {
         };
 
         // Replace "pong" with "up" because it is more human friendly word.
-        json_string = json_string.replace("pong", "up");
-
-        let s: ServerStatus = serde_json::from_str(&json_string)?;
+        let s = try!(http::status::parse_status(&json_string.replace("pong", "up")));
 
         sayln(
             "white",
@@ -18,6 +18,7 @@
 pub use errors;
 use errors::{DeliveryError, Kind};
 use std::clone::Clone;
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::fs::File;
 use std::io::prelude::*;
@@ -26,7 +27,7 @@ use toml;
 use types::DeliveryResult;
 use utils::path_ext::{is_dir, is_file};
 use utils::path_join_many::PathJoinMany;
-use utils::{mkdir_recursive, read_file};
+use utils::{env_variable, home_dir, mkdir_recursive, read_file};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Config {
@@ -49,9 +50,63 @@ pub struct Config {
     pub fips_git_port: Option<String>,
     pub fips_custom_cert_filename: Option<String>,
     pub a2_mode: Option<bool>,
+    // Validate the cli.toml files every time we load them and fail on
+    // any issue instead of silently ignoring it.
+    pub strict: Option<bool>,
+    // Credential helpers, shell commands that print the password or the
+    // API token on stdout, i.e. `vault read -field=password secret/automate`
+    pub password_command: Option<String>,
+    pub token_command: Option<String>,
+    // Seconds to wait for the credential helpers (default: 30)
+    pub credential_timeout: Option<String>,
+    // User-defined command aliases declared in the `[alias]` table,
+    // i.e. `rr = "review --no-open --for release"`
+    pub alias: Option<BTreeMap<String, String>>,
+    // Named profiles declared as `[profiles.<name>]` tables in the cli.toml,
+    // this must be the last field so that the tables are serialized after
+    // all the plain values.
+    pub profiles: Option<BTreeMap<String, Config>>,
 }
 
+// Every plain value of the Config along with the type of its value, the
+// single list of fields behind the `DELIVERY_<FIELD>` environment variables,
+// `override_with` and the validation of the cli.toml files. It hands the
+// list to the `$callback` macro as `field: Type,` pairs.
+macro_rules! with_config_fields {
+    ($callback:ident) => {
+        $callback! {
+            server: Text,
+            api_port: Port,
+            api_protocol: Protocol,
+            user: Text,
+            enterprise: Text,
+            organization: Text,
+            project: Text,
+            git_port: Port,
+            pipeline: Text,
+            token_file: Text,
+            generator: Text,
+            non_interactive: Bool,
+            auto_bump: Bool,
+            config_json: Text,
+            saml: Bool,
+            fips: Bool,
+            fips_git_port: Port,
+            fips_custom_cert_filename: Text,
+            a2_mode: Bool,
+            strict: Bool,
+            password_command: Text,
+            token_command: Text,
+            credential_timeout: Seconds,
+        }
+    };
+}
+
+pub mod sources;
 pub mod url_format;
+pub mod validate;
+
+use config::sources::{Source, Sources};
 
 impl Default for Config {
     fn default() -> Config {
@@ -75,6 +130,12 @@ impl Default for Config {
             fips_git_port: None,
             fips_custom_cert_filename: None,
             a2_mode: None,
+            strict: None,
+            password_command: None,
+            token_command: None,
+            credential_timeout: None,
+            alias: None,
+            profiles: None,
         }
     }
 }
@@ -272,20 +333,348 @@ impl Config {
     }
 
     pub fn load_config(cwd: &PathBuf) -> DeliveryResult<Self> {
-        let have_config = Config::dot_delivery_cli_path(cwd);
-        match have_config.as_ref() {
-            Some(path) => {
-                let toml = read_file(path)?;
-                match Config::parse_config(&toml) {
-                    Ok(c) => return Ok(c),
-                    Err(_) => return Ok(Default::default()),
+        Config::load_config_for_profile(cwd, None)
+    }
+
+    /// Load the cli.toml config and layer the provided profile on top of it.
+    ///
+    /// When no profile is provided we fallback to the `DELIVERY_PROFILE`
+    /// environment variable, if that is not set either we just return
+    /// the base configuration.
+    pub fn load_config_for_profile(
+        cwd: &PathBuf,
+        profile: Option<&str>,
+    ) -> DeliveryResult<Self> {
+        let (config, _) = Config::load_config_with_sources(cwd, profile)?;
+        if config.strict.unwrap_or(false) {
+            Config::fail_on_issues(&Config::validate_files(cwd))?;
+        }
+        Ok(config)
+    }
+
+    /// Return every issue found in the cli.toml files that make up the
+    /// config of the provided path plus the ones of the effective config.
+    pub fn validate(&self, cwd: &PathBuf) -> Vec<validate::Issue> {
+        let mut issues = Config::validate_files(cwd);
+        issues.extend(validate::validate_config(self));
+        issues
+    }
+
+    /// With `strict = true`, fail if the values of the config don't go
+    /// together. It is called once the command line options are merged
+    /// since they can fix, or break, the combination of the cli.toml.
+    pub fn validate_merged(&self) -> DeliveryResult<()> {
+        if self.strict.unwrap_or(false) {
+            Config::fail_on_issues(&validate::validate_config(self))
+        } else {
+            Ok(())
+        }
+    }
+
+    // The keys, types and ranges of every cli.toml of the provided path
+    fn validate_files(cwd: &PathBuf) -> Vec<validate::Issue> {
+        let mut issues = Vec::new();
+        for path in Config::cli_toml_paths(cwd) {
+            issues.extend(validate::validate_file(&path));
+        }
+        issues
+    }
+
+    // Fail if there is any issue with the config, used when `strict = true`
+    fn fail_on_issues(issues: &[validate::Issue]) -> DeliveryResult<()> {
+        if issues.is_empty() {
+            return Ok(());
+        }
+        let detail = issues
+            .iter()
+            .map(|i| format!("  {}", i))
+            .collect::<Vec<String>>()
+            .join("\n");
+        Err(DeliveryError {
+            kind: Kind::InvalidConfig,
+            detail: Some(format!(
+                "{}\nRun 'delivery config validate' for more details.",
+                detail
+            )),
+        })
+    }
+
+    /// Load the config like `load_config_for_profile` and keep track of
+    /// where each of the values came from.
+    pub fn load_config_with_sources(
+        cwd: &PathBuf,
+        profile: Option<&str>,
+    ) -> DeliveryResult<(Self, Sources)> {
+        let mut config: Config = Default::default();
+        let mut sources = Sources::new();
+        sources.record(&config, |_| Source::Default);
+
+        // The file, and its content, where each profile was last defined
+        let mut profile_files: BTreeMap<String, (PathBuf, String)> = BTreeMap::new();
+        let user_global = home_dir(&[".delivery", "cli.toml"]).ok();
+        for path in Config::layered_cli_toml_paths(cwd, user_global.clone()) {
+            let (layer, content) = Config::read_layer(&path);
+            if Some(&path) != user_global.as_ref() {
+                Config::check_project_layer(&layer, &path)?;
+            }
+            sources.record(&layer, |key| Source::file(&path, &content, key));
+            if let Some(ref profiles) = layer.profiles {
+                for name in profiles.keys() {
+                    profile_files.insert(name.clone(), (path.clone(), content.clone()));
                 }
             }
-            None => return Ok(Default::default()),
+            config.override_with(layer);
+        }
+
+        if let Some(name) = Config::profile_name(profile) {
+            let profile_config = config.profile(&name)?;
+            let (path, content) = match profile_files.remove(&name) {
+                Some((path, content)) => (Some(path), content),
+                None => (None, String::new()),
+            };
+            sources.record(&profile_config, |key| {
+                Source::profile(&name, path.as_ref(), &content, key)
+            });
+            config.override_with(profile_config);
+        }
+
+        let env_config = Config::from_env_vars(env_variable)?;
+        sources.record(&env_config, |key| Source::Env {
+            variable: Config::env_var_name(key),
+        });
+        config.override_with(env_config);
+
+        Ok((config, sources))
+    }
+
+    // The credential helpers run arbitrary commands, a cli.toml that can be
+    // committed to a project (or any other directory) is not allowed to set
+    // them, not even in its profiles.
+    fn check_project_layer(layer: &Config, path: &PathBuf) -> DeliveryResult<()> {
+        let profiles = layer.profiles.iter().flat_map(|p| p.values());
+        for config in Some(layer).into_iter().chain(profiles) {
+            let key = if config.password_command.is_some() {
+                "password_command"
+            } else if config.token_command.is_some() {
+                "token_command"
+            } else {
+                continue;
+            };
+            return Err(DeliveryError {
+                kind: Kind::InvalidConfig,
+                detail: Some(format!(
+                    "{} is set in {}\nThe credential helpers can only be set in \
+                     ~/.delivery/cli.toml or through the DELIVERY_{} environment variable.",
+                    key,
+                    path.display(),
+                    key.to_uppercase()
+                )),
+            });
+        }
+        Ok(())
+    }
+
+    // Read a single layer of the configuration, that is only the keys that
+    // the cli.toml actually sets, along with the content of the file. A file
+    // that we can't parse doesn't contribute any key so we fallback to the
+    // rest of the layers.
+    fn read_layer(path: &PathBuf) -> (Config, String) {
+        let content = read_file(path).unwrap_or_default();
+        match toml::from_str::<Config>(&content) {
+            Ok(c) => {
+                debug!("Loaded config layer {}: {:?}", path.display(), c);
+                (c, content)
+            }
+            Err(e) => {
+                debug!("Ignoring config layer {}: {:?}", path.display(), e);
+                let empty =
+                    toml::from_str::<Config>("").expect("An empty config must always parse");
+                (empty, content)
+            }
+        }
+    }
+
+    /// Build a config out of the `DELIVERY_<FIELD>` environment variables,
+    /// (i.e. `DELIVERY_SERVER`, `DELIVERY_API_PORT` or `DELIVERY_FIPS`)
+    /// only the fields with a variable set are defined.
+    ///
+    /// The lookup function receives the name of the variable, this lets us
+    /// use `utils::env_variable` or any other source of variables.
+    pub fn from_env_vars<F>(lookup: F) -> DeliveryResult<Config>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut config = toml::from_str::<Config>("")?;
+        macro_rules! env_field {
+            ($field:ident, Bool) => {
+                let var = Config::env_var_name(stringify!($field));
+                config.$field = match lookup(&var) {
+                    Some(v) => Some(Config::parse_env_bool(&var, &v)?),
+                    None => None,
+                };
+            };
+            ($field:ident, $kind:ident) => {
+                config.$field = lookup(&Config::env_var_name(stringify!($field)));
+            };
+        }
+        macro_rules! env_fields {
+            ($($field:ident: $kind:ident,)*) => {
+                $(env_field!($field, $kind);)*
+            };
+        }
+        with_config_fields!(env_fields);
+        Ok(config)
+    }
+
+    /// Return the name of the environment variable that overrides a field.
+    pub fn env_var_name(field: &str) -> String {
+        format!("DELIVERY_{}", field.to_uppercase())
+    }
+
+    /// The `DELIVERY_<FIELD>` environment variables that reproduce the
+    /// plain values of this config, the opposite of `from_env_vars`.
+    pub fn to_env_vars(&self) -> Vec<(String, String)> {
+        let mut vars = Vec::new();
+        for (key, value) in sources::set_keys(self).into_iter() {
+            let value = match value {
+                toml::Value::String(s) => s,
+                toml::Value::Boolean(b) => b.to_string(),
+                _ => continue,
+            };
+            vars.push((Config::env_var_name(&key), value));
+        }
+        vars
+    }
+
+    fn parse_env_bool(var: &str, value: &str) -> DeliveryResult<bool> {
+        match value.trim().to_lowercase().as_ref() {
+            "true" | "1" | "yes" => Ok(true),
+            "false" | "0" | "no" => Ok(false),
+            _ => Err(DeliveryError {
+                kind: Kind::ConfigParse,
+                detail: Some(format!(
+                    "Invalid value '{}' for {}; expected true or false",
+                    value, var
+                )),
+            }),
+        }
+    }
+
+    // Returns the name of the profile to use, the provided one takes
+    // precedence over the `DELIVERY_PROFILE` environment variable.
+    fn profile_name(profile: Option<&str>) -> Option<String> {
+        match profile {
+            Some(p) if !p.is_empty() => Some(p.to_string()),
+            _ => env_variable("DELIVERY_PROFILE").and_then(|p| {
+                if p.is_empty() {
+                    None
+                } else {
+                    Some(p)
+                }
+            }),
+        }
+    }
+
+    /// Override the current config with the keys of the named profile.
+    pub fn apply_profile(&mut self, name: &str) -> DeliveryResult<()> {
+        let profile = self.profile(name)?;
+        debug!("Applying profile '{}': {:?}", name, profile);
+        self.override_with(profile);
+        Ok(())
+    }
+
+    /// Return the keys that the named profile sets.
+    pub fn profile(&self, name: &str) -> DeliveryResult<Config> {
+        match self.profiles.as_ref().and_then(|p| p.get(name)) {
+            Some(p) => Ok(p.clone()),
+            None => {
+                let available = match self.profiles {
+                    Some(ref p) if !p.is_empty() => {
+                        p.keys().cloned().collect::<Vec<String>>().join(", ")
+                    }
+                    _ => String::from("none"),
+                };
+                Err(DeliveryError {
+                    kind: Kind::ProfileNotFound(name.to_string()),
+                    detail: Some(format!("Available profiles: {}", available)),
+                })
+            }
         }
     }
 
     pub fn write_file<P>(&self, path: P) -> DeliveryResult<String>
+    where
+        P: AsRef<Path>,
+    {
+        let toml_string = toml::to_string(self)?;
+        Config::write_toml(path, &toml_string)?;
+        Ok(toml_string)
+    }
+
+    /// Write the config as a named profile of the cli.toml located in
+    /// the provided path, keeping the rest of the file untouched.
+    ///
+    /// Returns the TOML of the profile that was written.
+    pub fn write_profile_file<P>(&self, path: P, profile: &str) -> DeliveryResult<String>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file_config = Config::read_profile_base(&path)?;
+        let mut profile_config = self.clone();
+        profile_config.profiles = None;
+        let profile_string = toml::to_string(&profile_config)?;
+        file_config
+            .profiles
+            .get_or_insert_with(BTreeMap::new)
+            .insert(profile.to_string(), profile_config);
+        Config::write_toml(path, &toml::to_string(&file_config)?)?;
+        Ok(profile_string)
+    }
+
+    /// Return the keys that the named profile sets in the cli.toml located
+    /// in the provided path without merging them with any other config,
+    /// if the profile is not defined the returned config is empty.
+    pub fn read_profile<P>(path: P, profile: &str) -> DeliveryResult<Config>
+    where
+        P: AsRef<Path>,
+    {
+        let file_config = Config::read_profile_base(&path)?;
+        match file_config.profiles.and_then(|mut p| p.remove(profile)) {
+            Some(c) => Ok(c),
+            None => Ok(toml::from_str::<Config>("")?),
+        }
+    }
+
+    /// Load only the cli.toml located in the provided path on top of the
+    /// built-in defaults, without any of the other layers.
+    ///
+    /// This is what `delivery setup` overlays the command line options on,
+    /// so that we don't copy values from the rest of the layers in the file.
+    pub fn load_file_config<P>(path: P) -> DeliveryResult<Config>
+    where
+        P: AsRef<Path>,
+    {
+        let mut config: Config = Default::default();
+        config.override_with(Config::read_profile_base(&path)?);
+        Ok(config)
+    }
+
+    // Read the raw content of the cli.toml from the provided path, that is
+    // without any defaults, or an empty config if the file doesn't exist.
+    fn read_profile_base<P>(path: P) -> DeliveryResult<Config>
+    where
+        P: AsRef<Path>,
+    {
+        let toml_path = path.as_ref().join_many(&[".delivery", "cli.toml"]);
+        let toml_str = if is_file(&toml_path) {
+            read_file(&toml_path)?
+        } else {
+            String::new()
+        };
+        Ok(toml::from_str::<Config>(&toml_str)?)
+    }
+
+    fn write_toml<P>(path: P, toml_string: &str) -> DeliveryResult<()>
     where
         P: AsRef<Path>,
     {
@@ -295,9 +684,8 @@ impl Config {
         }
         let write_file = write_dir.join_many(&["cli.toml"]);
         let mut f = try!(File::create(&write_file));
-        let toml_string = toml::to_string(self)?;
         try!(f.write_all(toml_string.as_bytes()));
-        Ok(toml_string)
+        Ok(())
     }
 
     pub fn parse_config(toml_str: &str) -> DeliveryResult<Self> {
@@ -313,67 +701,27 @@ impl Config {
     // `self` with another provided Config. This is useful,
     // for example, if you need to merge two configs.
     pub fn override_with(&mut self, config: Config) {
-        // (afiune) TODO: I think we could do better by implementing some
-        // sort of Iterator or other thing that let us loop through the
-        // fields, but for now this is good enough.
-        //
         // If the `config` has some new config, override `self`
-        if config.server.is_some() {
-            self.server = config.server
-        }
-        if config.api_port.is_some() {
-            self.api_port = config.api_port
-        }
-        if config.pipeline.is_some() {
-            self.pipeline = config.pipeline
-        }
-        if config.project.is_some() {
-            self.project = config.project
-        }
-        if config.enterprise.is_some() {
-            self.enterprise = config.enterprise
-        }
-        if config.organization.is_some() {
-            self.organization = config.organization
-        }
-        if config.user.is_some() {
-            self.user = config.user
-        }
-        if config.git_port.is_some() {
-            self.git_port = config.git_port
-        }
-        if config.token_file.is_some() {
-            self.token_file = config.token_file
-        }
-        if config.generator.is_some() {
-            self.generator = config.generator
-        }
-        if config.non_interactive.is_some() {
-            self.non_interactive = config.non_interactive
-        }
-        if config.auto_bump.is_some() {
-            self.auto_bump = config.auto_bump
-        }
-        if config.config_json.is_some() {
-            self.config_json = config.config_json
-        }
-        if config.saml.is_some() {
-            self.saml = config.saml
-        }
-        if config.fips.is_some() {
-            self.fips = config.fips
-        }
-        if config.fips_git_port.is_some() {
-            self.fips_git_port = config.fips_git_port
-        }
-        if config.fips_custom_cert_filename.is_some() {
-            self.fips_custom_cert_filename = config.fips_custom_cert_filename
+        macro_rules! override_fields {
+            ($($field:ident: $kind:ident,)*) => {
+                $(
+                    if config.$field.is_some() {
+                        self.$field = config.$field
+                    }
+                )*
+            };
         }
-        if config.api_protocol.is_some() {
-            self.api_protocol = config.api_protocol
+        with_config_fields!(override_fields);
+        // Aliases are merged by name, just like the profiles below.
+        if let Some(alias) = config.alias {
+            self.alias.get_or_insert_with(BTreeMap::new).extend(alias);
         }
-        if config.a2_mode.is_some() {
-            self.a2_mode = config.a2_mode
+        // Profiles are merged by name, the incoming definition of
+        // a profile replaces an existing one with the same name.
+        if let Some(profiles) = config.profiles {
+            self.profiles
+                .get_or_insert_with(BTreeMap::new)
+                .extend(profiles);
         }
     }
 
@@ -406,12 +754,62 @@ impl Config {
             }
         }
     }
+
+    /// Return every cli.toml that makes up the configuration of the provided
+    /// path, ordered from the lowest to the highest precedence:
+    ///
+    /// 1. The user-global `~/.delivery/cli.toml`
+    /// 2. Every `.delivery/cli.toml` from the root of the filesystem down to
+    ///    the provided path
+    ///
+    /// The built-in defaults sit below all of them and the command line
+    /// options above them.
+    pub fn cli_toml_paths<P>(orig_path: P) -> Vec<PathBuf>
+    where
+        P: AsRef<Path>,
+    {
+        let user_global = home_dir(&[".delivery", "cli.toml"]).ok();
+        Config::layered_cli_toml_paths(orig_path, user_global)
+    }
+
+    fn layered_cli_toml_paths<P>(orig_path: P, user_global: Option<PathBuf>) -> Vec<PathBuf>
+    where
+        P: AsRef<Path>,
+    {
+        let mut paths: Vec<PathBuf> = Vec::new();
+        let mut path = orig_path.as_ref().to_owned();
+        loop {
+            if let Some(cli_toml) = Config::check_dot_delivery_cli(path.clone()) {
+                paths.push(cli_toml);
+            }
+            if !path.pop() {
+                break;
+            }
+        }
+        paths.reverse();
+        // The user-global config might already be part of the tree when
+        // the project lives inside the home directory.
+        if let Some(global) = user_global {
+            if is_file(&global) && !paths.contains(&global) {
+                paths.insert(0, global);
+            }
+        }
+        paths
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Config;
+    use errors::Kind;
+    use std::collections::BTreeMap;
     use std::default::Default;
+    use std::fs::File;
+    use std::io::prelude::*;
+    use std::path::PathBuf;
+    use tempdir::TempDir;
+    use utils::mkdir_recursive;
+    use utils::path_join_many::PathJoinMany;
 
     #[test]
     fn parse_config_with_defaults() {
@@ -581,4 +979,251 @@ mod tests {
         assert!(conf.server.is_none());
         assert!(conf.delivery_git_ssh_url().is_err());
     }
+
+    #[test]
+    fn parse_config_with_profiles() {
+        let toml = r#"
+            server = "prod.example.com"
+            enterprise = "chef"
+            user = "adam"
+
+            [profiles.staging]
+            server = "staging.example.com"
+            git_port = "2222"
+
+            [profiles.lab]
+            server = "lab.example.com"
+            enterprise = "lab"
+"#;
+        let mut config = Config::parse_config(toml).unwrap();
+        assert_eq!(Some("prod.example.com".to_string()), config.server);
+        assert_eq!(2, config.profiles.as_ref().unwrap().len());
+
+        config.apply_profile("staging").unwrap();
+        assert_eq!(Some("staging.example.com".to_string()), config.server);
+        assert_eq!(Some("2222".to_string()), config.git_port);
+        // Keys that are not set by the profile comes from the base config
+        assert_eq!(Some("chef".to_string()), config.enterprise);
+        assert_eq!(Some("adam".to_string()), config.user);
+        assert_eq!(Some("master".to_string()), config.pipeline);
+    }
+
+    #[test]
+    fn apply_profile_not_found() {
+        let toml = r#"
+            server = "prod.example.com"
+
+            [profiles.staging]
+            server = "staging.example.com"
+"#;
+        let mut config = Config::parse_config(toml).unwrap();
+        let result = config.apply_profile("lab");
+        assert!(result.is_err());
+        let e = result.unwrap_err();
+        assert_enum!(e.kind, Kind::ProfileNotFound(_));
+        assert_eq!(Some("Available profiles: staging".to_string()), e.detail);
+        assert_eq!(Some("prod.example.com".to_string()), config.server);
+    }
+
+    #[test]
+    fn strict_checks_the_combination_of_values_after_the_options() {
+        let tempdir = TempDir::new("strict").unwrap();
+        let path = tempdir.path().join(".delivery");
+        mkdir_recursive(&path).unwrap();
+        let mut file = File::create(path.join("cli.toml")).unwrap();
+        file.write_all(b"strict = true\nfips = true\n").unwrap();
+
+        let config = Config::load_config_for_profile(&tempdir.path().to_path_buf(), None)
+            .unwrap();
+        assert!(config.validate_merged().is_err());
+        let config = config.set_fips_git_port("36534");
+        assert!(config.validate_merged().is_ok());
+    }
+
+    #[test]
+    fn write_profile_file_keeps_base_config() {
+        let tempdir = TempDir::new("profiles").unwrap();
+        let path = tempdir.path();
+        let base = Config::default().set_server("prod.example.com");
+        base.write_file(path).unwrap();
+
+        let staging = Config::read_profile(path, "staging")
+            .unwrap()
+            .set_server("staging.example.com");
+        assert_eq!(None, staging.git_port);
+        staging.write_profile_file(path, "staging").unwrap();
+
+        let mut config = Config::load_config_for_profile(&path.to_path_buf(), None).unwrap();
+        assert_eq!(Some("prod.example.com".to_string()), config.server);
+        config.apply_profile("staging").unwrap();
+        assert_eq!(Some("staging.example.com".to_string()), config.server);
+        assert_eq!(Some("8989".to_string()), config.git_port);
+
+        let config =
+            Config::load_config_for_profile(&path.to_path_buf(), Some("staging")).unwrap();
+        assert_eq!(Some("staging.example.com".to_string()), config.server);
+    }
+
+    #[test]
+    fn layered_cli_toml_paths_order() {
+        let tempdir = TempDir::new("layers").unwrap();
+        let root = tempdir.path().to_path_buf();
+        let project = root.join_many(&["team", "project"]);
+        let global = root.join_many(&["home"]);
+        mkdir_recursive(&project).unwrap();
+        Config::default().write_file(&root).unwrap();
+        Config::default().write_file(&project).unwrap();
+        Config::default().write_file(&global).unwrap();
+        let global_toml = global.join_many(&[".delivery", "cli.toml"]);
+
+        let paths = Config::layered_cli_toml_paths(&project, Some(global_toml.clone()));
+        assert_eq!(
+            vec![
+                global_toml.clone(),
+                root.join_many(&[".delivery", "cli.toml"]),
+                project.join_many(&[".delivery", "cli.toml"]),
+            ],
+            paths
+        );
+
+        // The user-global config is not duplicated if it is part of the tree
+        let root_toml = root.join_many(&[".delivery", "cli.toml"]);
+        let paths = Config::layered_cli_toml_paths(&project, Some(root_toml));
+        assert_eq!(2, paths.len());
+    }
+
+    #[test]
+    fn load_config_merges_every_layer() {
+        let tempdir = TempDir::new("layers").unwrap();
+        let root = tempdir.path().to_path_buf();
+        let project = root.join_many(&["project"]);
+        mkdir_recursive(&project).unwrap();
+        write_toml(
+            &root,
+            r#"
+            server = "automate.example.com"
+            user = "adam"
+            fips_git_port = "36534"
+"#,
+        );
+        write_toml(
+            &project,
+            r#"
+            enterprise = "chef"
+            user = "salim"
+"#,
+        );
+
+        let mut config: Config = Default::default();
+        for path in Config::layered_cli_toml_paths(&project, None) {
+            config.override_with(Config::read_layer(&path).0);
+        }
+        assert_eq!(Some("automate.example.com".to_string()), config.server);
+        assert_eq!(Some("salim".to_string()), config.user);
+        assert_eq!(Some("chef".to_string()), config.enterprise);
+        assert_eq!(Some("36534".to_string()), config.fips_git_port);
+        // Defaults are kept when no layer sets them
+        assert_eq!(Some("8989".to_string()), config.git_port);
+        assert_eq!(Some("https".to_string()), config.api_protocol);
+    }
+
+    #[test]
+    fn credential_helpers_only_from_the_user_global_config() {
+        let path = PathBuf::from("/project/.delivery/cli.toml");
+        let layer = Config::parse_config("server = \"automate.example.com\"").unwrap();
+        assert!(Config::check_project_layer(&layer, &path).is_ok());
+
+        let layer = Config::parse_config("token_command = \"cat /tmp/token\"").unwrap();
+        let err = Config::check_project_layer(&layer, &path).unwrap_err();
+        assert_enum!(err.kind, Kind::InvalidConfig);
+        assert!(err
+            .detail
+            .unwrap()
+            .starts_with("token_command is set in /project/.delivery/cli.toml"));
+
+        let layer = Config::parse_config(
+            r#"
+            [profiles.ci]
+            password_command = "echo secret"
+"#,
+        )
+        .unwrap();
+        let err = Config::check_project_layer(&layer, &path).unwrap_err();
+        assert!(err.detail.unwrap().starts_with("password_command is set"));
+    }
+
+    fn write_toml(path: &PathBuf, toml: &str) {
+        let dot_delivery = path.join_many(&[".delivery"]);
+        mkdir_recursive(&dot_delivery).unwrap();
+        let mut f = File::create(dot_delivery.join_many(&["cli.toml"])).unwrap();
+        f.write_all(toml.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn from_env_vars_sets_only_defined_fields() {
+        let config = Config::from_env_vars(|var| match var {
+            "DELIVERY_SERVER" => Some("ci.example.com".to_string()),
+            "DELIVERY_ENTERPRISE" => Some("ci".to_string()),
+            "DELIVERY_ORGANIZATION" => Some("builders".to_string()),
+            "DELIVERY_USER" => Some("ci-bot".to_string()),
+            "DELIVERY_PIPELINE" => Some("release".to_string()),
+            "DELIVERY_API_PORT" => Some("8443".to_string()),
+            "DELIVERY_FIPS" => Some("1".to_string()),
+            "DELIVERY_A2_MODE" => Some("false".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(Some("ci.example.com".to_string()), config.server);
+        assert_eq!(Some("ci".to_string()), config.enterprise);
+        assert_eq!(Some("builders".to_string()), config.organization);
+        assert_eq!(Some("ci-bot".to_string()), config.user);
+        assert_eq!(Some("release".to_string()), config.pipeline);
+        assert_eq!(Some("8443".to_string()), config.api_port);
+        assert_eq!(Some(true), config.fips);
+        assert_eq!(Some(false), config.a2_mode);
+        assert_eq!(None, config.git_port);
+        assert_eq!(None, config.saml);
+
+        let mut base = Config::default().set_server("automate.example.com");
+        base.override_with(config);
+        assert_eq!(Some("ci.example.com".to_string()), base.server);
+        assert_eq!(Some("8989".to_string()), base.git_port);
+    }
+
+    #[test]
+    fn from_env_vars_invalid_bool() {
+        let result = Config::from_env_vars(|var| match var {
+            "DELIVERY_SAML" => Some("maybe".to_string()),
+            _ => None,
+        });
+        assert!(result.is_err());
+        assert_enum!(result.unwrap_err().kind, Kind::ConfigParse);
+    }
+
+    #[test]
+    fn to_env_vars_round_trip() {
+        let mut config = Config::default()
+            .set_server("ci.example.com")
+            .set_user("ci-bot");
+        config.saml = Some(true);
+        config.alias = Some(BTreeMap::new());
+        let vars = config.to_env_vars();
+        assert!(vars.contains(&("DELIVERY_SERVER".to_string(), "ci.example.com".to_string())));
+        assert!(vars.contains(&("DELIVERY_SAML".to_string(), "true".to_string())));
+        assert!(vars.iter().all(|v| v.0 != "DELIVERY_ALIAS"));
+
+        let from_env = Config::from_env_vars(|var| {
+            vars.iter().find(|v| v.0 == var).map(|v| v.1.clone())
+        }).unwrap();
+        assert_eq!(config.server, from_env.server);
+        assert_eq!(config.user, from_env.user);
+        assert_eq!(config.saml, from_env.saml);
+        assert_eq!(config.git_port, from_env.git_port);
+    }
+
+    #[test]
+    fn env_var_name_for_fields() {
+        assert_eq!("DELIVERY_SERVER", Config::env_var_name("server"));
+        assert_eq!("DELIVERY_FIPS_GIT_PORT", Config::env_var_name("fips_git_port"));
+    }
 }
@@ -0,0 +1,227 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Config Sources
+//!
+//! Keep track of where every value of the effective `Config` came from,
+//! a cli.toml, a profile, an environment variable, a command line option
+//! or the built-in defaults.
+//!
+use config::Config;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use toml;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Source {
+    Default,
+    File {
+        path: String,
+        line: Option<usize>,
+    },
+    Profile {
+        name: String,
+        path: Option<String>,
+        line: Option<usize>,
+    },
+    Env {
+        variable: String,
+    },
+    Flag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::Default => write!(f, "default"),
+            Source::File { ref path, line } => match line {
+                Some(l) => write!(f, "{}:{}", path, l),
+                None => write!(f, "{}", path),
+            },
+            Source::Profile {
+                ref name,
+                ref path,
+                line,
+            } => match (path, line) {
+                (&Some(ref p), Some(l)) => write!(f, "profile '{}' ({}:{})", name, p, l),
+                (&Some(ref p), None) => write!(f, "profile '{}' ({})", name, p),
+                _ => write!(f, "profile '{}'", name),
+            },
+            Source::Env { ref variable } => write!(f, "env {}", variable),
+            Source::Flag => write!(f, "command line option"),
+        }
+    }
+}
+
+impl Source {
+    /// A key set in the provided cli.toml, the line is looked up in
+    /// the content of the file.
+    pub fn file<P: AsRef<Path>>(path: P, content: &str, key: &str) -> Source {
+        Source::File {
+            path: path.as_ref().display().to_string(),
+            line: key_line(content, None, key),
+        }
+    }
+
+    /// A key set in a `[profiles.<name>]` table of the provided cli.toml.
+    pub fn profile<P>(name: &str, path: Option<P>, content: &str, key: &str) -> Source
+    where
+        P: AsRef<Path>,
+    {
+        let table = format!("profiles.{}", name);
+        Source::Profile {
+            name: name.to_string(),
+            path: path.as_ref().map(|p| p.as_ref().display().to_string()),
+            line: path.and(key_line(content, Some(&table), key)),
+        }
+    }
+}
+
+/// The origin of every key of a `Config`
+#[derive(Debug, Clone, Default)]
+pub struct Sources {
+    sources: BTreeMap<String, Source>,
+}
+
+impl Sources {
+    pub fn new() -> Sources {
+        Default::default()
+    }
+
+    /// Record the source of every key that the provided config sets.
+    pub fn record<F>(&mut self, config: &Config, source: F)
+    where
+        F: Fn(&str) -> Source,
+    {
+        for key in set_keys(config).keys() {
+            self.sources.insert(key.clone(), source(key));
+        }
+    }
+
+    /// Record the source of the keys whose value changed between the
+    /// two provided configs.
+    pub fn record_changes<F>(&mut self, before: &Config, after: &Config, source: F)
+    where
+        F: Fn(&str) -> Source,
+    {
+        let before_keys = set_keys(before);
+        for (key, value) in set_keys(after).iter() {
+            if before_keys.get(key) != Some(value) {
+                self.sources.insert(key.clone(), source(key));
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Source> {
+        self.sources.get(key)
+    }
+}
+
+/// Return the keys that the config sets with their values, the
+/// profiles are left out since they are not part of the effective config.
+pub fn set_keys(config: &Config) -> BTreeMap<String, toml::Value> {
+    let mut keys = BTreeMap::new();
+    if let Ok(toml::Value::Table(table)) = toml::Value::try_from(config) {
+        for (key, value) in table.into_iter() {
+            if key != "profiles" {
+                keys.insert(key, value);
+            }
+        }
+    }
+    keys
+}
+
+/// Find the line (starting at 1) where the key is defined inside the
+/// provided table, `None` means the top level of the document.
+///
+/// This is not a full TOML parser, it is just good enough to point the
+/// end-user at the right line of a cli.toml.
+pub fn key_line(content: &str, table: Option<&str>, key: &str) -> Option<usize> {
+    let mut current_table: Option<String> = None;
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            let name = line.trim_matches(|c| c == '[' || c == ']').trim();
+            current_table = Some(name.to_string());
+            continue;
+        }
+        if current_table.as_ref().map(|t| t.as_str()) != table {
+            continue;
+        }
+        let mut split = line.splitn(2, '=');
+        if let (Some(k), Some(_)) = (split.next(), split.next()) {
+            if k.trim().trim_matches('"') == key {
+                return Some(index + 1);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &'static str = r#"
+server = "automate.example.com"
+user = "adam"
+
+[profiles.staging]
+server = "staging.example.com"
+"#;
+
+    #[test]
+    fn key_line_top_level() {
+        assert_eq!(Some(2), key_line(TOML, None, "server"));
+        assert_eq!(Some(3), key_line(TOML, None, "user"));
+        assert_eq!(None, key_line(TOML, None, "enterprise"));
+    }
+
+    #[test]
+    fn key_line_inside_a_table() {
+        assert_eq!(Some(6), key_line(TOML, Some("profiles.staging"), "server"));
+        assert_eq!(None, key_line(TOML, Some("profiles.staging"), "user"));
+        assert_eq!(None, key_line(TOML, Some("profiles.lab"), "server"));
+    }
+
+    #[test]
+    fn record_changes_only_tracks_new_values() {
+        let before = Config::default().set_server("automate.example.com");
+        let after = before.clone().set_user("adam").set_server("automate.example.com");
+        let mut sources = Sources::new();
+        sources.record(&before, |_| Source::Default);
+        sources.record_changes(&before, &after, |_| Source::Flag);
+        assert_eq!(Some(&Source::Default), sources.get("server"));
+        assert_eq!(Some(&Source::Flag), sources.get("user"));
+        assert_eq!(Some(&Source::Default), sources.get("git_port"));
+        assert_eq!(None, sources.get("enterprise"));
+    }
+
+    #[test]
+    fn display_sources() {
+        let file = Source::file("/tmp/.delivery/cli.toml", TOML, "user");
+        assert_eq!("/tmp/.delivery/cli.toml:3", format!("{}", file));
+        let profile = Source::profile("staging", Some("/tmp/cli.toml"), TOML, "server");
+        assert_eq!("profile 'staging' (/tmp/cli.toml:6)", format!("{}", profile));
+        let env = Source::Env {
+            variable: "DELIVERY_USER".to_string(),
+        };
+        assert_eq!("env DELIVERY_USER", format!("{}", env));
+    }
+}
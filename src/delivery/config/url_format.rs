@@ -34,6 +34,13 @@ impl Config {
         Ok(format!("{}://{}/e/{}/#/users", p, s, e))
     }
 
+    // Dashboard url
+    //
+    // The landing page of an enterprise, it shows the changes in flight.
+    pub fn dashboard_url(&self) -> DeliveryResult<String> {
+        Ok(format!("{}/dashboard", self.enterprise_url()?))
+    }
+
     // Organizations url
     //
     // List of organizations within an enterprise.
@@ -54,6 +61,55 @@ impl Config {
         let o = self.organization()?;
         Ok(format!("{}://{}/e/{}/#/organizations/{}", p, s, e, o))
     }
+
+    // Project url
+    //
+    // The page of the project, it lists its open and merged changes.
+    pub fn project_url(&self) -> DeliveryResult<String> {
+        let o = self.organization()?;
+        let p = self.project()?;
+        Ok(format!(
+            "{}/organizations/{}/projects/{}",
+            self.enterprise_url()?,
+            o,
+            p
+        ))
+    }
+
+    // Pipeline url
+    //
+    // The changes of the project that target a specific pipeline.
+    pub fn pipeline_url(&self) -> DeliveryResult<String> {
+        let p = self.pipeline()?;
+        Ok(format!("{}/pipelines/{}", self.project_url()?, p))
+    }
+
+    // Change url
+    //
+    // The review page of a change, the same one that `delivery review`
+    // prints after pushing a change to the server.
+    pub fn change_url(&self, change_id: &str) -> DeliveryResult<String> {
+        Ok(format!("{}/changes/{}", self.project_url()?, change_id))
+    }
+
+    // Change stage url
+    //
+    // The status of a change in one of the stages of the pipeline.
+    // (verify, build, acceptance, union, rehearsal or delivered)
+    pub fn change_stage_url(&self, change_id: &str, stage: &str) -> DeliveryResult<String> {
+        Ok(format!("{}/status/{}", self.change_url(change_id)?, stage))
+    }
+
+    // Base url of the enterprise for the pages that `delivery open` shows.
+    //
+    // It is built from the same base as the API client, so it honors
+    // the api_port and the `/workflow` prefix of A2 mode.
+    fn enterprise_url(&self) -> DeliveryResult<String> {
+        let p = self.api_protocol()?;
+        let b = self.api_base_resource()?;
+        let e = self.enterprise()?;
+        Ok(format!("{}://{}/e/{}/#", p, b, e))
+    }
 }
 
 #[cfg(test)]
@@ -93,4 +149,97 @@ mod tests {
             conf.projects_url().unwrap()
         );
     }
+
+    fn full_conf() -> Config {
+        let mut conf = Config::default();
+        conf.server = Some("server".to_string());
+        conf.enterprise = Some("test".to_string());
+        conf.organization = Some("org".to_string());
+        conf.project = Some("proj".to_string());
+        conf
+    }
+
+    #[test]
+    fn test_dashboard_url() {
+        assert_eq!(
+            "https://server/e/test/#/dashboard".to_string(),
+            full_conf().dashboard_url().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_project_url() {
+        assert_eq!(
+            "https://server/e/test/#/organizations/org/projects/proj".to_string(),
+            full_conf().project_url().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pipeline_url() {
+        assert_eq!(
+            "https://server/e/test/#/organizations/org/projects/proj/pipelines/master".to_string(),
+            full_conf().pipeline_url().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_change_urls() {
+        let conf = full_conf();
+        let id = "6d8f6ec6-e8d0-4a39-a4d3-c8ae6d5a4c4d";
+        assert_eq!(
+            format!("https://server/e/test/#/organizations/org/projects/proj/changes/{}", id),
+            conf.change_url(id).unwrap()
+        );
+        assert_eq!(
+            format!(
+                "https://server/e/test/#/organizations/org/projects/proj/changes/{}/status/verify",
+                id
+            ),
+            conf.change_stage_url(id, "verify").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_urls_with_api_port_and_a2_mode() {
+        let mut conf = full_conf();
+        conf.api_port = Some("8443".to_string());
+        assert_eq!(
+            "https://server:8443/e/test/#/dashboard".to_string(),
+            conf.dashboard_url().unwrap()
+        );
+        conf.a2_mode = Some(true);
+        assert_eq!(
+            "https://server:8443/workflow/e/test/#/organizations/org/projects/proj".to_string(),
+            conf.project_url().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_existing_urls_ignore_api_port_and_a2_mode() {
+        let mut conf = full_conf();
+        conf.api_port = Some("8443".to_string());
+        conf.a2_mode = Some(true);
+        assert_eq!(
+            "https://server/e/test/#/users".to_string(),
+            conf.users_url().unwrap()
+        );
+        assert_eq!(
+            "https://server/e/test/#/organizations".to_string(),
+            conf.organizations_url().unwrap()
+        );
+        assert_eq!(
+            "https://server/e/test/#/organizations/org".to_string(),
+            conf.projects_url().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_urls_require_config() {
+        let mut conf = full_conf();
+        conf.project = None;
+        assert!(conf.project_url().is_err());
+        assert!(conf.change_url("123").is_err());
+        assert!(conf.projects_url().is_ok());
+    }
 }
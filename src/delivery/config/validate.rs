@@ -0,0 +1,451 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Config Validation
+//!
+//! Strict checks for the cli.toml files. Serde silently ignores the keys
+//! it doesn't know and a value of the wrong type makes the whole file to
+//! be ignored, here we report all those problems along with the lines
+//! where they are, plus any inconsistent combination of values in the
+//! effective config.
+//!
+use config::sources::key_line;
+use config::Config;
+use std::fmt;
+use std::path::Path;
+use toml;
+use utils::read_file;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldType {
+    Text,
+    Port,
+    Protocol,
+    Bool,
+    Seconds,
+}
+
+// Every key that a cli.toml (or a profile) can set
+macro_rules! field_types {
+    ($($field:ident: $kind:ident,)*) => {
+        const FIELDS: &'static [(&'static str, FieldType)] = &[
+            $((stringify!($field), FieldType::$kind),)*
+        ];
+    };
+}
+with_config_fields!(field_types);
+
+const PROTOCOLS: &'static [&'static str] = &["http", "https"];
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub source: String,
+    pub line: Option<usize>,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(l) => write!(f, "{}:{}: {}", self.source, l, self.message),
+            None => write!(f, "{}: {}", self.source, self.message),
+        }
+    }
+}
+
+/// Validate the cli.toml located at the provided path.
+pub fn validate_file<P: AsRef<Path>>(path: P) -> Vec<Issue> {
+    let source = path.as_ref().display().to_string();
+    match read_file(&path) {
+        Ok(content) => validate_toml(&content, &source),
+        Err(e) => vec![Issue {
+            source: source,
+            line: None,
+            key: None,
+            message: format!("unable to read the file: {}", e.detail().unwrap_or_default()),
+        }],
+    }
+}
+
+/// Validate the content of a cli.toml, the source is only used to
+/// report where the issues are.
+pub fn validate_toml(content: &str, source: &str) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    match toml::from_str::<toml::Value>(content) {
+        Ok(toml::Value::Table(table)) => {
+            validate_table(&table, content, source, None, &mut issues);
+        }
+        Ok(_) => issues.push(Issue {
+            source: source.to_string(),
+            line: None,
+            key: None,
+            message: String::from("expected a TOML table"),
+        }),
+        Err(e) => issues.push(Issue {
+            source: source.to_string(),
+            line: None,
+            key: None,
+            message: format!("invalid TOML: {}", e),
+        }),
+    }
+    issues
+}
+
+/// Validate the combination of values of the effective config.
+pub fn validate_config(config: &Config) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut push = |key: &str, message: &str| {
+        issues.push(Issue {
+            source: String::from("effective config"),
+            line: None,
+            key: Some(key.to_string()),
+            message: message.to_string(),
+        })
+    };
+    let fips = config.fips.unwrap_or(false);
+    if fips && config.fips_git_port.is_none() {
+        push(
+            "fips_git_port",
+            "fips is enabled but fips_git_port is not set; set it to any free port \
+             on localhost (i.e. `fips_git_port = \"36534\"`)",
+        );
+    }
+    if !fips && config.fips_custom_cert_filename.is_some() {
+        push(
+            "fips_custom_cert_filename",
+            "fips_custom_cert_filename is set but fips is not enabled",
+        );
+    }
+    if config.token_command.is_some() && config.password_command.is_some() {
+        push(
+            "password_command",
+            "password_command is ignored because token_command is set",
+        );
+    }
+    issues
+}
+
+fn validate_table(
+    table: &toml::value::Table,
+    content: &str,
+    source: &str,
+    profile: Option<&str>,
+    issues: &mut Vec<Issue>,
+) {
+    let table_name = profile.map(|p| format!("profiles.{}", p));
+    for (key, value) in table.iter() {
+        let line = key_line(content, table_name.as_ref().map(|t| t.as_str()), key);
+
+        if key == "profiles" && profile.is_none() {
+            match *value {
+                toml::Value::Table(ref profiles) => {
+                    for (name, p) in profiles.iter() {
+                        match *p {
+                            toml::Value::Table(ref p_table) => {
+                                validate_table(p_table, content, source, Some(name), issues)
+                            }
+                            _ => issues.push(issue(
+                                source,
+                                None,
+                                &format!("profiles.{}", name),
+                                format!("profile '{}' must be a table", name),
+                            )),
+                        }
+                    }
+                }
+                _ => issues.push(issue(
+                    source,
+                    line,
+                    key,
+                    String::from("profiles must be a table"),
+                )),
+            }
+            continue;
+        }
+
+        if key == "alias" {
+            match *value {
+                toml::Value::Table(ref aliases) => {
+                    for (name, command) in aliases.iter() {
+                        if !command.is_str() {
+                            issues.push(issue(
+                                source,
+                                key_line(content, Some(&alias_table(profile)), name),
+                                &format!("alias.{}", name),
+                                format!(
+                                    "alias '{}' must be a string, found {}",
+                                    name,
+                                    command.type_str()
+                                ),
+                            ));
+                        }
+                    }
+                }
+                _ => issues.push(issue(
+                    source,
+                    line,
+                    key,
+                    String::from("alias must be a table"),
+                )),
+            }
+            continue;
+        }
+
+        let message = match field_type(key) {
+            Some(t) => validate_value(key, t, value),
+            None => match suggest(key) {
+                Some(s) => Some(format!("unknown key '{}', did you mean '{}'?", key, s)),
+                None => Some(format!("unknown key '{}'", key)),
+            },
+        };
+        if let Some(msg) = message {
+            issues.push(issue(source, line, key, msg));
+        }
+    }
+}
+
+// Name of the alias table, at the top level or inside a profile
+fn alias_table(profile: Option<&str>) -> String {
+    match profile {
+        Some(p) => format!("profiles.{}.alias", p),
+        None => String::from("alias"),
+    }
+}
+
+fn issue(source: &str, line: Option<usize>, key: &str, message: String) -> Issue {
+    Issue {
+        source: source.to_string(),
+        line: line,
+        key: Some(key.to_string()),
+        message: message,
+    }
+}
+
+fn field_type(key: &str) -> Option<FieldType> {
+    FIELDS.iter().find(|f| f.0 == key).map(|f| f.1)
+}
+
+fn validate_value(key: &str, field_type: FieldType, value: &toml::Value) -> Option<String> {
+    match (field_type, value) {
+        (FieldType::Bool, &toml::Value::Boolean(_)) => None,
+        (FieldType::Bool, v) => Some(format!(
+            "{} must be a boolean (true or false), found {}",
+            key,
+            v.type_str()
+        )),
+        (FieldType::Text, &toml::Value::String(_)) => None,
+        (FieldType::Port, &toml::Value::String(ref s)) => match s.parse::<u16>() {
+            Ok(p) if p > 0 => None,
+            _ => Some(format!(
+                "{} must be a port number between 1 and 65535, found \"{}\"",
+                key, s
+            )),
+        },
+        (FieldType::Port, &toml::Value::Integer(i)) => Some(format!(
+            "{} must be a quoted string (i.e. {} = \"{}\")",
+            key, key, i
+        )),
+        (FieldType::Seconds, &toml::Value::String(ref s)) => match s.parse::<u64>() {
+            Ok(t) if t > 0 => None,
+            _ => Some(format!(
+                "{} must be a number of seconds greater than 0, found \"{}\"",
+                key, s
+            )),
+        },
+        (FieldType::Seconds, &toml::Value::Integer(i)) => Some(format!(
+            "{} must be a quoted string (i.e. {} = \"{}\")",
+            key, key, i
+        )),
+        (FieldType::Protocol, &toml::Value::String(ref s)) => {
+            if PROTOCOLS.contains(&s.as_str()) {
+                None
+            } else {
+                Some(format!(
+                    "{} must be one of {}, found \"{}\"",
+                    key,
+                    PROTOCOLS.join(", "),
+                    s
+                ))
+            }
+        }
+        (_, v) => Some(format!("{} must be a string, found {}", key, v.type_str())),
+    }
+}
+
+// Suggest the closest known key for a misspelled one
+fn suggest(key: &str) -> Option<&'static str> {
+    FIELDS
+        .iter()
+        .map(|f| (f.0, distance(key, f.0)))
+        .filter(|&(_, d)| d <= 2)
+        .min_by_key(|&(_, d)| d)
+        .map(|(name, _)| name)
+}
+
+/// Levenshtein distance between two strings, used to suggest the right
+/// name of a misspelled key.
+pub fn distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..b_chars.len() + 1).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            let value = *[prev[j] + cost, prev[j + 1] + 1, current[j] + 1]
+                .iter()
+                .min()
+                .unwrap();
+            current.push(value);
+        }
+        prev = current;
+    }
+    prev[b_chars.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_toml_has_no_issues() {
+        let toml = r#"
+server = "automate.example.com"
+api_protocol = "http"
+git_port = "8989"
+fips = true
+fips_git_port = "36534"
+
+[alias]
+rr = "review --no-open"
+
+[profiles.staging]
+server = "staging.example.com"
+"#;
+        assert!(validate_toml(toml, "cli.toml").is_empty());
+    }
+
+    #[test]
+    fn alias_must_be_strings() {
+        let toml = r#"
+[alias]
+rr = "review --no-open"
+ll = 42
+"#;
+        let issues = validate_toml(toml, "cli.toml");
+        assert_eq!(1, issues.len());
+        assert_eq!(
+            "cli.toml:4: alias 'll' must be a string, found integer",
+            format!("{}", issues[0])
+        );
+    }
+
+    #[test]
+    fn unknown_keys_with_suggestions() {
+        let toml = r#"
+server = "automate.example.com"
+enterprize = "chef"
+
+[profiles.staging]
+orgnization = "builders"
+favorite_color = "blue"
+"#;
+        let issues = validate_toml(toml, "cli.toml");
+        assert_eq!(3, issues.len());
+        assert_eq!(Some(3), issues[0].line);
+        assert_eq!(
+            "unknown key 'enterprize', did you mean 'enterprise'?",
+            issues[0].message
+        );
+        assert_eq!(Some(7), issues[1].line);
+        assert_eq!("unknown key 'favorite_color'", issues[1].message);
+        assert_eq!(Some(6), issues[2].line);
+        assert_eq!(
+            "unknown key 'orgnization', did you mean 'organization'?",
+            issues[2].message
+        );
+    }
+
+    #[test]
+    fn type_and_range_errors() {
+        let toml = r#"
+api_protocol = "ftp"
+git_port = 8989
+api_port = "99999"
+fips_git_port = "abc"
+saml = "yes"
+user = 42
+credential_timeout = "0"
+"#;
+        let issues = validate_toml(toml, "cli.toml");
+        let messages: Vec<String> = issues.iter().map(|i| format!("{}", i)).collect();
+        assert_eq!(7, messages.len());
+        assert!(messages.contains(&String::from(
+            "cli.toml:8: credential_timeout must be a number of seconds greater than 0, found \"0\""
+        )));
+        assert!(messages.contains(&String::from(
+            "cli.toml:2: api_protocol must be one of http, https, found \"ftp\""
+        )));
+        assert!(messages.contains(&String::from(
+            "cli.toml:3: git_port must be a quoted string (i.e. git_port = \"8989\")"
+        )));
+        assert!(messages.contains(&String::from(
+            "cli.toml:4: api_port must be a port number between 1 and 65535, found \"99999\""
+        )));
+        assert!(messages.contains(&String::from(
+            "cli.toml:5: fips_git_port must be a port number between 1 and 65535, found \"abc\""
+        )));
+        assert!(messages.contains(&String::from(
+            "cli.toml:6: saml must be a boolean (true or false), found string"
+        )));
+        assert!(messages.contains(&String::from(
+            "cli.toml:7: user must be a string, found integer"
+        )));
+    }
+
+    #[test]
+    fn invalid_toml_syntax() {
+        let issues = validate_toml("server = ", "cli.toml");
+        assert_eq!(1, issues.len());
+        assert!(issues[0].message.starts_with("invalid TOML"));
+    }
+
+    #[test]
+    fn inconsistent_fips_config() {
+        let mut config = Config::default();
+        config.fips = Some(true);
+        let issues = validate_config(&config);
+        assert_eq!(1, issues.len());
+        assert_eq!(Some("fips_git_port".to_string()), issues[0].key);
+
+        config.fips_git_port = Some("36534".to_string());
+        assert!(validate_config(&config).is_empty());
+
+        config.fips = Some(false);
+        config.fips_git_port = None;
+        config.fips_custom_cert_filename = Some("cert.pem".to_string());
+        assert_eq!(1, validate_config(&config).len());
+    }
+
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(0, distance("server", "server"));
+        assert_eq!(1, distance("sever", "server"));
+        assert_eq!(2, distance("gti_port", "git_port"));
+        assert_eq!(3, distance("abc", ""));
+    }
+}
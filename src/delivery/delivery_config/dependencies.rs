@@ -0,0 +1,322 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Project Dependencies
+//!
+//! The `dependencies` of a config.json list the projects that a project
+//! depends on, every entry has the format `[organization/]project[:pipeline]`.
+//! This module builds the graph of those dependencies across projects.
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Dependency {
+    pub organization: Option<String>,
+    pub project: String,
+    pub pipeline: Option<String>,
+}
+
+impl Dependency {
+    /// Parse an entry of the `dependencies` of a config.json
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use delivery::delivery_config::dependencies::Dependency;
+    ///
+    /// let dep = Dependency::parse("chef/bldr:release").unwrap();
+    /// assert_eq!(Some("chef".to_string()), dep.organization);
+    /// assert_eq!("bldr", dep.project);
+    /// assert_eq!(Some("release".to_string()), dep.pipeline);
+    /// ```
+    pub fn parse(entry: &str) -> Option<Dependency> {
+        let (name, pipeline) = match entry.find(':') {
+            Some(i) => (&entry[..i], Some(entry[i + 1..].to_string())),
+            None => (entry, None),
+        };
+        let (organization, project) = match name.find('/') {
+            Some(i) => (Some(name[..i].to_string()), &name[i + 1..]),
+            None => (None, name),
+        };
+        let empty_pipeline = pipeline.as_ref().map(|p| p.is_empty()).unwrap_or(false);
+        if project.is_empty() || project.contains('/') || empty_pipeline {
+            return None;
+        }
+        Some(Dependency {
+            organization: organization,
+            project: project.to_string(),
+            pipeline: pipeline,
+        })
+    }
+
+    /// The name of the dependency in the graph, `organization/project`
+    /// unless it belongs to `organization`, the one of the current project.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use delivery::delivery_config::dependencies::Dependency;
+    ///
+    /// let dep = Dependency::parse("chef/bldr").unwrap();
+    /// assert_eq!("bldr", dep.name(Some("chef")));
+    /// assert_eq!("chef/bldr", dep.name(Some("habitat")));
+    /// assert_eq!("chef/bldr", dep.name(None));
+    /// ```
+    pub fn name(&self, organization: Option<&str>) -> String {
+        match self.organization {
+            Some(ref org) if Some(org.as_str()) != organization => {
+                format!("{}/{}", org, self.project)
+            }
+            _ => self.project.clone(),
+        }
+    }
+}
+
+/// A directed graph where every project points to its dependencies
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> DependencyGraph {
+        DependencyGraph::default()
+    }
+
+    pub fn add(&mut self, project: &str, dependencies: &[String]) {
+        let deps = self
+            .edges
+            .entry(project.to_string())
+            .or_insert_with(BTreeSet::new);
+        deps.extend(dependencies.iter().cloned());
+    }
+
+    /// The projects that we know about, the ones that were added with
+    /// their dependencies.
+    pub fn contains(&self, project: &str) -> bool {
+        self.edges.contains_key(project)
+    }
+
+    pub fn dependencies(&self, project: &str) -> Vec<&String> {
+        self.edges
+            .get(project)
+            .map(|d| d.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Every project that depends on `project`, directly or through other
+    /// projects, that is, what ships if `project` changes.
+    pub fn reverse_dependencies(&self, project: &str) -> BTreeSet<String> {
+        let mut dependents = BTreeSet::new();
+        let mut pending = vec![project.to_string()];
+        while let Some(current) = pending.pop() {
+            for (p, deps) in self.edges.iter() {
+                if deps.contains(&current) && p != project && dependents.insert(p.clone()) {
+                    pending.push(p.clone());
+                }
+            }
+        }
+        dependents
+    }
+
+    /// Find the dependency cycles of the graph, every cycle starts and
+    /// ends with the same project (i.e. `[a, b, a]`).
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles = Vec::new();
+        let mut done = BTreeSet::new();
+        for project in self.edges.keys() {
+            let mut path = Vec::new();
+            self.find_cycles(project, &mut path, &mut done, &mut cycles);
+        }
+        cycles
+    }
+
+    fn find_cycles(
+        &self,
+        project: &str,
+        path: &mut Vec<String>,
+        done: &mut BTreeSet<String>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if let Some(i) = path.iter().position(|p| p == project) {
+            let mut cycle = path[i..].to_vec();
+            cycle.push(project.to_string());
+            cycles.push(cycle);
+            return;
+        }
+        if done.contains(project) {
+            return;
+        }
+        path.push(project.to_string());
+        for dep in self.dependencies(project) {
+            self.find_cycles(dep, path, done, cycles);
+        }
+        path.pop();
+        done.insert(project.to_string());
+    }
+
+    /// Render the dependencies of `root` as a tree, a dependency that is
+    /// already one of its ancestors is marked as a cycle.
+    pub fn tree(&self, root: &str) -> Vec<String> {
+        let mut lines = vec![root.to_string()];
+        let mut ancestors = vec![root.to_string()];
+        self.tree_lines(root, "", &mut ancestors, &mut lines);
+        lines
+    }
+
+    fn tree_lines(
+        &self,
+        project: &str,
+        prefix: &str,
+        ancestors: &mut Vec<String>,
+        lines: &mut Vec<String>,
+    ) {
+        let deps = self.dependencies(project);
+        for (i, dep) in deps.iter().enumerate() {
+            let last = i == deps.len() - 1;
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            if ancestors.contains(*dep) {
+                lines.push(format!("{}{}{} (cycle)", prefix, branch, dep));
+                continue;
+            }
+            let suffix = if self.contains(dep) {
+                ""
+            } else {
+                " (not found)"
+            };
+            lines.push(format!("{}{}{}{}", prefix, branch, dep, suffix));
+            ancestors.push(dep.to_string());
+            self.tree_lines(dep, &format!("{}{}", prefix, indent), ancestors, lines);
+            ancestors.pop();
+        }
+    }
+
+    /// Render the whole graph in the DOT language of Graphviz
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for (project, deps) in self.edges.iter() {
+            dot.push_str(&format!("  {:?};\n", project));
+            for dep in deps.iter() {
+                dot.push_str(&format!("  {:?} -> {:?};\n", project, dep));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        graph.add("web", &[String::from("api"), String::from("ui")]);
+        graph.add("api", &[String::from("db")]);
+        graph.add("ui", &[String::from("api")]);
+        graph.add("db", &[]);
+        graph
+    }
+
+    #[test]
+    fn parse_dependencies() {
+        assert_eq!(
+            Some(Dependency {
+                organization: None,
+                project: String::from("api"),
+                pipeline: None,
+            }),
+            Dependency::parse("api")
+        );
+        assert_eq!(None, Dependency::parse("a/b/c"));
+        assert_eq!(None, Dependency::parse("api:"));
+        assert_eq!(None, Dependency::parse("chef/"));
+    }
+
+    #[test]
+    fn projects_of_other_organizations_are_different_nodes() {
+        let a = Dependency::parse("org-a/foo").unwrap();
+        let b = Dependency::parse("org-b/foo:release").unwrap();
+        let local = Dependency::parse("foo").unwrap();
+        assert_eq!("foo", a.name(Some("org-a")));
+        assert_eq!("org-b/foo", b.name(Some("org-a")));
+        assert_eq!("foo", local.name(Some("org-a")));
+        assert_eq!("foo", local.name(None));
+    }
+
+    #[test]
+    fn reverse_dependencies_are_transitive() {
+        let graph = graph();
+        let expected: Vec<&str> = vec!["api", "ui", "web"];
+        assert_eq!(
+            expected,
+            graph
+                .reverse_dependencies("db")
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<&str>>()
+        );
+        assert!(graph.reverse_dependencies("web").is_empty());
+    }
+
+    #[test]
+    fn detect_cycles() {
+        let mut graph = graph();
+        assert!(graph.cycles().is_empty());
+        graph.add("db", &[String::from("web")]);
+        assert_eq!(
+            vec![
+                vec!["api", "db", "web", "api"],
+                vec!["api", "db", "web", "ui", "api"],
+            ],
+            graph.cycles()
+        );
+    }
+
+    #[test]
+    fn render_a_tree() {
+        let mut graph = graph();
+        graph.add("db", &[String::from("cache"), String::from("web")]);
+        assert_eq!(
+            vec![
+                "web",
+                "├── api",
+                "│   └── db",
+                "│       ├── cache (not found)",
+                "│       └── web (cycle)",
+                "└── ui",
+                "    └── api",
+                "        └── db",
+                "            ├── cache (not found)",
+                "            └── web (cycle)",
+            ],
+            graph.tree("web")
+        );
+    }
+
+    #[test]
+    fn render_dot() {
+        let mut graph = DependencyGraph::new();
+        graph.add("web", &[String::from("api")]);
+        assert_eq!(
+            "digraph dependencies {\n  \"web\";\n  \"web\" -> \"api\";\n}\n",
+            graph.to_dot()
+        );
+    }
+}
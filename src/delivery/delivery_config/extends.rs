@@ -0,0 +1,506 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Config Inheritance
+//!
+//! A `.delivery/config.json` can inherit from a base config with the
+//! `extends` key, so that many projects can share the same config and only
+//! declare the keys that are different. The `extends` is one of:
+//!
+//! * A path relative to the directory of the config.json
+//!   (`"extends": "../../shared/config.json"`)
+//! * An http URL (`"extends": "http://example.com/config.json"`), an https
+//!   URL is rejected since it can't be downloaded yet, use the git form
+//! * The git URL of a repository and the path of the file in it
+//!   (`"extends": {"git": "https://...", "path": "config.json", "branch": "main"}`)
+//!
+//! The local config is deep-merged on top of its base: objects are merged
+//! key by key and any other value (including lists) replaces the value of
+//! the base. The `build_cookbook` is replaced as a whole since it has only
+//! one source. A base can extend another config itself.
+//!
+//! Remote bases are cached under `~/.delivery/cache/config_extends` and
+//! fetched again once the cached copy is older than `CACHE_TTL_SECS`. If a
+//! base can't be fetched, a stale copy from the cache is used instead.
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use errors::{DeliveryError, Kind};
+use git;
+use http::download;
+use serde_json;
+use serde_json::Value as SerdeJson;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempdir::TempDir;
+use types::DeliveryResult;
+use utils;
+use utils::path_ext::is_file;
+use utils::read_file;
+use utils::say::sayln;
+
+/// How long a cached remote base is used before fetching it again
+pub const CACHE_TTL_SECS: u64 = 15 * 60;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Base {
+    Path(PathBuf),
+    Url(String),
+    Git {
+        url: String,
+        path: String,
+        branch: Option<String>,
+    },
+}
+
+impl Base {
+    /// Parse the value of an `extends`, `dir` is the directory of the
+    /// config that extends it or `None` if that config is remote, a
+    /// remote config can't extend a relative path.
+    pub fn parse(value: &SerdeJson, dir: Option<&Path>) -> DeliveryResult<Base> {
+        match *value {
+            SerdeJson::String(ref s) if s.starts_with("http://") => Ok(Base::Url(s.clone())),
+            SerdeJson::String(ref s) if s.starts_with("https://") => Err(extends_error(format!(
+                "https URLs like '{}' can't be downloaded yet, put the config in a git \
+                 repository and extend it with {{\"git\": \"<url>\", \"path\": \"<file>\"}}",
+                s
+            ))),
+            SerdeJson::String(ref s) => match dir {
+                Some(d) => Ok(Base::Path(d.join(s))),
+                None => Err(extends_error(format!(
+                    "the relative path '{}' can only be extended from a local config.json",
+                    s
+                ))),
+            },
+            SerdeJson::Object(ref map) => {
+                let field = |k: &str| map.get(k).and_then(|v| v.as_str()).map(String::from);
+                match (field("git"), field("path")) {
+                    (Some(url), Some(path)) => Ok(Base::Git {
+                        url: url,
+                        path: path,
+                        branch: field("branch"),
+                    }),
+                    _ => Err(extends_error(format!(
+                        "a git extends needs the 'git' URL and the 'path' of the file, \
+                         found {}",
+                        value
+                    ))),
+                }
+            }
+            ref v => Err(extends_error(format!(
+                "expected a path, a URL or a git object, found {}",
+                v
+            ))),
+        }
+    }
+
+    // Identifies a base, used to detect cycles and as key of the cache
+    fn id(&self) -> String {
+        match *self {
+            Base::Path(ref p) => canonical_path(p),
+            Base::Url(ref u) => u.clone(),
+            Base::Git {
+                ref url,
+                ref path,
+                ref branch,
+            } => format!(
+                "{}#{}@{}",
+                url,
+                path,
+                branch.as_ref().map_or("", |b| b.as_str())
+            ),
+        }
+    }
+}
+
+/// The cache of the remote bases, shared by every project
+pub struct BaseCache {
+    pub root: PathBuf,
+}
+
+impl BaseCache {
+    pub fn new() -> DeliveryResult<BaseCache> {
+        Ok(BaseCache {
+            root: utils::home_dir(&[".delivery", "cache", "config_extends"])?,
+        })
+    }
+
+    pub fn entry(&self, base: &Base) -> PathBuf {
+        let mut sha = Sha256::new();
+        sha.input_str(&base.id());
+        self.root.join(format!("{}.json", sha.result_str()))
+    }
+
+    // The content of a remote base, from the cache while it is fresh
+    fn fetch(&self, base: &Base) -> DeliveryResult<String> {
+        let entry = self.entry(base);
+        if is_fresh(&entry) {
+            debug!(
+                "Using the cached base {} for {}",
+                entry.display(),
+                base.id()
+            );
+            return read_file(&entry);
+        }
+        utils::mkdir_recursive(&self.root)?;
+        match download_base(base, &entry) {
+            Ok(()) => read_file(&entry),
+            Err(e) => {
+                if !is_file(&entry) {
+                    return Err(e);
+                }
+                sayln(
+                    "yellow",
+                    &format!(
+                        "Unable to fetch {}, using the cached copy: {}",
+                        base.id(),
+                        e.detail().unwrap_or_default()
+                    ),
+                );
+                read_file(&entry)
+            }
+        }
+    }
+}
+
+/// Load a config.json and merge it on top of the configs that it extends,
+/// the result has no `extends` key.
+pub fn resolve(config_path: &Path, cache: &BaseCache) -> DeliveryResult<SerdeJson> {
+    let content = read_file(config_path)?;
+    let json = parse(&content, &utils::path_to_string(config_path))?;
+    let dir = config_path.parent().unwrap_or(Path::new("."));
+    let mut chain = vec![canonical_path(config_path)];
+    resolve_json(json, Some(dir), cache, &mut chain)
+}
+
+fn resolve_json(
+    mut json: SerdeJson,
+    dir: Option<&Path>,
+    cache: &BaseCache,
+    chain: &mut Vec<String>,
+) -> DeliveryResult<SerdeJson> {
+    let extends = match json.as_object_mut().and_then(|m| m.remove("extends")) {
+        Some(e) => e,
+        None => return Ok(json),
+    };
+    let base = Base::parse(&extends, dir)?;
+    let id = base.id();
+    if chain.contains(&id) {
+        chain.push(id);
+        return Err(extends_error(format!(
+            "the config.json extends itself: {}",
+            chain.join(" -> ")
+        )));
+    }
+    chain.push(id.clone());
+    let (content, base_dir) = match base {
+        Base::Path(ref p) => (read_file(p)?, p.parent().map(Path::to_path_buf)),
+        _ => (cache.fetch(&base)?, None),
+    };
+    let base_json = parse(&content, &id)?;
+    let mut merged = resolve_json(
+        base_json,
+        base_dir.as_ref().map(|d| d.as_path()),
+        cache,
+        chain,
+    )?;
+    merge(&mut merged, json);
+    Ok(merged)
+}
+
+/// Deep-merge the config `overrides` into its `base`
+///
+/// The `build_cookbook` is the exception: it replaces the one of the base
+/// as a whole, otherwise a `path` in the config and a `git` in its base
+/// would end up as one build_cookbook with two sources.
+///
+/// # Examples
+///
+/// ```
+/// extern crate serde_json;
+/// # extern crate delivery;
+/// use delivery::delivery_config::extends::merge;
+///
+/// # fn main() {
+/// let mut base = serde_json::from_str(r#"{"a": {"b": 1, "c": 2}, "d": [1]}"#).unwrap();
+/// let overrides = serde_json::from_str(r#"{"a": {"c": 3}, "d": [2]}"#).unwrap();
+/// merge(&mut base, overrides);
+/// assert_eq!(r#"{"a":{"b":1,"c":3},"d":[2]}"#, base.to_string());
+/// # }
+/// ```
+pub fn merge(base: &mut SerdeJson, overrides: SerdeJson) {
+    match (base, overrides) {
+        (&mut SerdeJson::Object(ref mut base_map), SerdeJson::Object(overrides_map)) => {
+            for (key, value) in overrides_map {
+                if key != "build_cookbook" {
+                    if let Some(existing) = base_map.get_mut(&key) {
+                        deep_merge(existing, value);
+                        continue;
+                    }
+                }
+                base_map.insert(key, value);
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+fn deep_merge(base: &mut SerdeJson, overrides: SerdeJson) {
+    match (base, overrides) {
+        (&mut SerdeJson::Object(ref mut base_map), SerdeJson::Object(overrides_map)) => {
+            for (key, value) in overrides_map {
+                if let Some(existing) = base_map.get_mut(&key) {
+                    deep_merge(existing, value);
+                    continue;
+                }
+                base_map.insert(key, value);
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+fn download_base(base: &Base, dest: &Path) -> DeliveryResult<()> {
+    // Download to a temporary file so that a failure never leaves a
+    // partial entry in the cache
+    let tmp = dest.with_extension("tmp");
+    match *base {
+        Base::Url(ref url) => {
+            download::to_file(url, &tmp)
+                .map_err(|e| extends_error(e.detail().unwrap_or_default()))?;
+        }
+        Base::Git {
+            ref url,
+            ref path,
+            ref branch,
+        } => {
+            let tempdir = TempDir::new("config-extends")?;
+            let clone = tempdir.path().join("repo");
+            let clone_str = utils::path_to_string(&clone);
+            let mut args = vec!["clone", "--depth", "1"];
+            if let Some(ref b) = *branch {
+                args.push("--branch");
+                args.push(b);
+            }
+            args.push(url);
+            args.push(&clone_str);
+            git::git_command(&args, tempdir.path())?;
+            let file = clone.join(path);
+            if !is_file(&file) {
+                return Err(extends_error(format!("{} has no file {}", url, path)));
+            }
+            fs::copy(&file, &tmp)?;
+        }
+        Base::Path(_) => unreachable!("local bases are not cached"),
+    }
+    fs::rename(&tmp, dest)?;
+    Ok(())
+}
+
+// The same file can be extended through different relative paths
+fn canonical_path(path: &Path) -> String {
+    utils::path_to_string(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+}
+
+fn is_fresh(entry: &Path) -> bool {
+    fs::metadata(entry)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map(|age| age < Duration::from_secs(CACHE_TTL_SECS))
+        .unwrap_or(false)
+}
+
+fn parse(content: &str, source: &str) -> DeliveryResult<SerdeJson> {
+    serde_json::from_str(content).map_err(|e| DeliveryError {
+        kind: Kind::DeliveryConfigParse,
+        detail: Some(format!("{}: {}", source, e)),
+    })
+}
+
+fn extends_error(detail: String) -> DeliveryError {
+    DeliveryError {
+        kind: Kind::DeliveryConfigExtends,
+        detail: Some(detail),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, SERVER_ADDRESS};
+    use std::fs::File;
+    use std::io::prelude::*;
+
+    fn write(path: &Path, content: &str) {
+        utils::mkdir_recursive(path.parent().unwrap()).unwrap();
+        File::create(path)
+            .unwrap()
+            .write_all(content.as_bytes())
+            .unwrap();
+    }
+
+    fn cache(tempdir: &TempDir) -> BaseCache {
+        BaseCache {
+            root: tempdir.path().join("cache"),
+        }
+    }
+
+    #[test]
+    fn parse_the_extends() {
+        let dir = Path::new("/project/.delivery");
+        let json = |s: &str| serde_json::from_str::<SerdeJson>(s).unwrap();
+        assert_eq!(
+            Base::Path(dir.join("../base.json")),
+            Base::parse(&json(r#""../base.json""#), Some(dir)).unwrap()
+        );
+        assert_eq!(
+            Base::Url(String::from("http://example.com/config.json")),
+            Base::parse(&json(r#""http://example.com/config.json""#), None).unwrap()
+        );
+        let https = Base::parse(&json(r#""https://example.com/config.json""#), None);
+        assert!(https.unwrap_err().detail().unwrap().contains(r#"{"git": "<url>""#));
+        assert_eq!(
+            Base::Git {
+                url: String::from("https://example.com/shared.git"),
+                path: String::from("config.json"),
+                branch: None,
+            },
+            Base::parse(
+                &json(r#"{"git": "https://example.com/shared.git", "path": "config.json"}"#),
+                Some(dir)
+            )
+            .unwrap()
+        );
+        assert!(Base::parse(&json(r#""base.json""#), None).is_err());
+        assert!(Base::parse(&json(r#"{"git": "https://example.com/shared.git"}"#), None).is_err());
+        assert!(Base::parse(&json("1"), Some(dir)).is_err());
+    }
+
+    #[test]
+    fn resolve_local_bases() {
+        let tempdir = TempDir::new("config-extends").unwrap();
+        let root = tempdir.path();
+        write(
+            &root.join("shared/base.json"),
+            r#"{
+                "version": "2",
+                "skip_phases": ["smoke"],
+                "delivery-truck": {"lint": {"foodcritic": {"fail_tags": ["any"]}}}
+            }"#,
+        );
+        write(
+            &root.join("shared/cookbooks.json"),
+            r#"{
+                "extends": "base.json",
+                "build_cookbook": {"name": "build_cookbook", "path": ".delivery/build_cookbook"}
+            }"#,
+        );
+        let config = root.join("project/.delivery/config.json");
+        write(
+            &config,
+            r#"{
+                "extends": "../../shared/cookbooks.json",
+                "skip_phases": [],
+                "delivery-truck": {"publish": {"chef_server": true}}
+            }"#,
+        );
+
+        let merged = resolve(&config, &cache(&tempdir)).unwrap();
+        let expected: SerdeJson = serde_json::from_str(
+            r#"{
+                "version": "2",
+                "build_cookbook": {"name": "build_cookbook", "path": ".delivery/build_cookbook"},
+                "skip_phases": [],
+                "delivery-truck": {
+                    "lint": {"foodcritic": {"fail_tags": ["any"]}},
+                    "publish": {"chef_server": true}
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(expected, merged);
+    }
+
+    #[test]
+    fn the_build_cookbook_replaces_the_one_of_the_base() {
+        let mut base: SerdeJson = serde_json::from_str(
+            r#"{
+                "build_cookbook": {"name": "shared", "git": "https://example.com/bc.git"},
+                "delivery-truck": {"build_cookbook": {"a": 1}}
+            }"#,
+        )
+        .unwrap();
+        let config: SerdeJson = serde_json::from_str(
+            r#"{
+                "build_cookbook": {"name": "local", "path": ".delivery/build_cookbook"},
+                "delivery-truck": {"build_cookbook": {"b": 2}}
+            }"#,
+        )
+        .unwrap();
+        merge(&mut base, config);
+        let expected: SerdeJson = serde_json::from_str(
+            r#"{
+                "build_cookbook": {"name": "local", "path": ".delivery/build_cookbook"},
+                "delivery-truck": {"build_cookbook": {"a": 1, "b": 2}}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(expected, base);
+    }
+
+    #[test]
+    fn detect_a_cycle() {
+        let tempdir = TempDir::new("config-extends").unwrap();
+        let root = tempdir.path();
+        write(&root.join("a.json"), r#"{"extends": "b.json"}"#);
+        write(&root.join("b.json"), r#"{"extends": "a.json"}"#);
+        let error = resolve(&root.join("a.json"), &cache(&tempdir)).unwrap_err();
+        match error.kind {
+            Kind::DeliveryConfigExtends => {}
+            _ => panic!(
+                "expected a DeliveryConfigExtends error, got {:?}",
+                error.kind
+            ),
+        }
+        assert!(error.detail().unwrap().contains("extends itself"));
+    }
+
+    #[test]
+    fn cache_a_remote_base() {
+        let tempdir = TempDir::new("config-extends").unwrap();
+        let url = format!("http://{}/shared/config.json", SERVER_ADDRESS);
+        let config = tempdir.path().join(".delivery/config.json");
+        write(
+            &config,
+            &format!(r#"{{"extends": "{}", "version": "2"}}"#, url),
+        );
+
+        let m = mock("GET", "/shared/config.json")
+            .with_status(200)
+            .with_body(r#"{"version": "1", "skip_phases": ["smoke"]}"#)
+            .create();
+        let cache = cache(&tempdir);
+        let merged = resolve(&config, &cache).unwrap();
+        assert_eq!(Some("2"), merged["version"].as_str());
+        assert_eq!(Some("smoke"), merged["skip_phases"][0].as_str());
+        assert!(is_file(&cache.entry(&Base::Url(url))));
+
+        // The second time it comes from the cache
+        drop(m);
+        assert_eq!(merged, resolve(&config, &cache).unwrap());
+    }
+}
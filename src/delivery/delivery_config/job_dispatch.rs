@@ -0,0 +1,375 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Job Dispatch Filters
+//!
+//! Evaluates the `job_dispatch` filters of a config.json against the
+//! attributes of a set of nodes, the same way the server selects the
+//! builders of each phase:
+//!
+//! * The filters of a phase are a list of clauses, a node is eligible
+//!   to run the phase if it matches any of them.
+//! * A clause maps attribute names to a list of values, a node matches
+//!   the clause if, for every attribute, its value is one of the list.
+//! * A phase without filters can run on any builder.
+//!
+//! The attributes of a node are looked up at the top level of its JSON
+//! (ohai output) and then under `automatic` (a saved node object).
+use delivery_config::JobDispatch;
+use serde_json::Value as SerdeJson;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The phases that run on a builder in every pipeline
+pub const PHASES: &'static [&'static str] = &[
+    "unit",
+    "lint",
+    "syntax",
+    "security",
+    "quality",
+    "publish",
+    "provision",
+    "deploy",
+    "smoke",
+    "functional",
+];
+
+pub type Clause = BTreeMap<String, Vec<String>>;
+
+pub struct Node {
+    pub name: String,
+    pub attributes: SerdeJson,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FilterError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClauseReport {
+    pub clause: Clause,
+    pub nodes: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PhaseReport {
+    pub phase: String,
+    // `None` when the phase has no filters
+    pub clauses: Option<Vec<ClauseReport>>,
+    pub eligible: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Explanation {
+    pub errors: Vec<FilterError>,
+    pub phases: Vec<PhaseReport>,
+}
+
+impl Explanation {
+    /// The phases that no node is able to run
+    pub fn unassignable_phases(&self) -> Vec<&str> {
+        self.phases
+            .iter()
+            .filter(|p| p.eligible.is_empty())
+            .map(|p| p.phase.as_str())
+            .collect()
+    }
+}
+
+/// Parse the filters of a `job_dispatch`, every malformed phase or
+/// clause is reported and left out.
+pub fn parse_filters(
+    job_dispatch: &JobDispatch,
+) -> (BTreeMap<String, Vec<Clause>>, Vec<FilterError>) {
+    let mut filters = BTreeMap::new();
+    let mut errors = Vec::new();
+    let raw = match job_dispatch.filters {
+        Some(ref f) => f,
+        None => return (filters, errors),
+    };
+    if job_dispatch.version != "v2" {
+        errors.push(error(
+            "$.job_dispatch.version",
+            format!(
+                "filters are only used by job_dispatch version v2, found {}",
+                job_dispatch.version
+            ),
+        ));
+    }
+    let sorted: BTreeMap<&String, &SerdeJson> = raw.iter().collect();
+    for (phase, value) in sorted {
+        let path = format!("$.job_dispatch.filters.{}", phase);
+        if !PHASES.contains(&phase.as_str()) {
+            errors.push(error(
+                &path,
+                format!("unknown phase '{}', expected one of: {}", phase, PHASES.join(", ")),
+            ));
+            continue;
+        }
+        let clauses = match value.as_array() {
+            Some(c) => c,
+            None => {
+                errors.push(error(&path, String::from("expected a list of clauses")));
+                continue;
+            }
+        };
+        let mut parsed = Vec::new();
+        for (i, clause) in clauses.iter().enumerate() {
+            match parse_clause(clause, &format!("{}[{}]", path, i)) {
+                Ok(c) => parsed.push(c),
+                Err(e) => errors.extend(e),
+            }
+        }
+        filters.insert(phase.clone(), parsed);
+    }
+    (filters, errors)
+}
+
+fn parse_clause(clause: &SerdeJson, path: &str) -> Result<Clause, Vec<FilterError>> {
+    let map = match clause.as_object() {
+        Some(m) if !m.is_empty() => m,
+        Some(_) => return Err(vec![error(path, String::from("empty clause"))]),
+        None => {
+            return Err(vec![error(
+                path,
+                String::from("expected an object of attributes"),
+            )])
+        }
+    };
+    let mut parsed = Clause::new();
+    let mut errors = Vec::new();
+    for (attribute, values) in map.iter() {
+        let attr_path = format!("{}.{}", path, attribute);
+        let strings: Option<Vec<String>> = values
+            .as_array()
+            .and_then(|v| v.iter().map(|s| s.as_str().map(String::from)).collect());
+        match strings {
+            Some(ref s) if !s.is_empty() => {
+                parsed.insert(attribute.clone(), s.clone());
+            }
+            _ => errors.push(error(
+                &attr_path,
+                String::from("expected a non-empty list of strings"),
+            )),
+        }
+    }
+    if errors.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Report which clause matches which node for every phase
+pub fn explain(
+    job_dispatch: Option<&JobDispatch>,
+    phases: &[String],
+    nodes: &[Node],
+) -> Explanation {
+    let (filters, errors) = match job_dispatch {
+        Some(jd) => parse_filters(jd),
+        None => (BTreeMap::new(), Vec::new()),
+    };
+    let all_nodes: Vec<String> = nodes.iter().map(|n| n.name.clone()).collect();
+    let reports = phases
+        .iter()
+        .map(|phase| match filters.get(phase) {
+            Some(clauses) => {
+                let clause_reports: Vec<ClauseReport> = clauses
+                    .iter()
+                    .map(|c| ClauseReport {
+                        clause: c.clone(),
+                        nodes: nodes
+                            .iter()
+                            .filter(|n| matches(c, &n.attributes))
+                            .map(|n| n.name.clone())
+                            .collect(),
+                    })
+                    .collect();
+                let eligible = all_nodes
+                    .iter()
+                    .filter(|n| clause_reports.iter().any(|c| c.nodes.contains(*n)))
+                    .cloned()
+                    .collect();
+                PhaseReport {
+                    phase: phase.clone(),
+                    clauses: Some(clause_reports),
+                    eligible: eligible,
+                }
+            }
+            None => PhaseReport {
+                phase: phase.clone(),
+                clauses: None,
+                eligible: all_nodes.clone(),
+            },
+        })
+        .collect();
+    Explanation {
+        errors: errors,
+        phases: reports,
+    }
+}
+
+/// Verify if the attributes of a node match every attribute of a clause
+pub fn matches(clause: &Clause, attributes: &SerdeJson) -> bool {
+    clause
+        .iter()
+        .all(|(attribute, values)| match lookup(attributes, attribute) {
+            Some(&SerdeJson::Array(ref list)) => {
+                list.iter().any(|v| values.contains(&value_to_string(v)))
+            }
+            Some(v) => values.contains(&value_to_string(v)),
+            None => false,
+        })
+}
+
+fn lookup<'a>(attributes: &'a SerdeJson, attribute: &str) -> Option<&'a SerdeJson> {
+    attributes
+        .get(attribute)
+        .or_else(|| attributes.get("automatic").and_then(|a| a.get(attribute)))
+}
+
+fn value_to_string(value: &SerdeJson) -> String {
+    match *value {
+        SerdeJson::String(ref s) => s.clone(),
+        ref v => v.to_string(),
+    }
+}
+
+fn error(path: &str, message: String) -> FilterError {
+    FilterError {
+        path: path.to_string(),
+        message: message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn job_dispatch(filters: &str) -> JobDispatch {
+        JobDispatch {
+            version: String::from("v2"),
+            filters: Some(serde_json::from_str(filters).unwrap()),
+        }
+    }
+
+    fn node(name: &str, attributes: &str) -> Node {
+        Node {
+            name: String::from(name),
+            attributes: serde_json::from_str(attributes).unwrap(),
+        }
+    }
+
+    fn nodes() -> Vec<Node> {
+        vec![
+            node(
+                "ubuntu",
+                r#"{"platform_family": "debian", "platform_version": "14.04"}"#,
+            ),
+            node(
+                "centos",
+                r#"{"automatic": {"platform_family": "rhel", "platform_version": "7.2"}}"#,
+            ),
+        ]
+    }
+
+    #[test]
+    fn explain_the_filters_of_every_phase() {
+        let jd = job_dispatch(
+            r#"{
+                "unit": [
+                    {"platform_family": ["debian"], "platform_version": ["14.04"]},
+                    {"platform_family": ["rhel"]}
+                ],
+                "syntax": [{"platform_family": ["windows"]}]
+            }"#,
+        );
+        let phases = vec![
+            String::from("unit"),
+            String::from("syntax"),
+            String::from("lint"),
+        ];
+        let explanation = explain(Some(&jd), &phases, &nodes());
+        assert!(explanation.errors.is_empty());
+
+        let unit = &explanation.phases[0];
+        let clauses = unit.clauses.as_ref().unwrap();
+        assert_eq!(vec!["ubuntu"], clauses[0].nodes);
+        assert_eq!(vec!["centos"], clauses[1].nodes);
+        assert_eq!(vec!["ubuntu", "centos"], unit.eligible);
+
+        // A phase without filters runs anywhere
+        assert!(explanation.phases[2].clauses.is_none());
+        assert_eq!(2, explanation.phases[2].eligible.len());
+
+        assert_eq!(vec!["syntax"], explanation.unassignable_phases());
+    }
+
+    #[test]
+    fn match_a_list_attribute() {
+        let mut clause = Clause::new();
+        clause.insert(String::from("tags"), vec![String::from("builder")]);
+        let tagged: SerdeJson = serde_json::from_str(r#"{"tags": ["builder", "x"]}"#).unwrap();
+        let untagged: SerdeJson = serde_json::from_str(r#"{"tags": []}"#).unwrap();
+        assert!(matches(&clause, &tagged));
+        assert!(!matches(&clause, &untagged));
+    }
+
+    #[test]
+    fn report_malformed_filters() {
+        let mut jd = job_dispatch(
+            r#"{
+                "unit": {"platform_family": ["debian"]},
+                "units": [{"platform_family": ["debian"]}],
+                "lint": [
+                    "debian",
+                    {},
+                    {"platform_family": "debian"},
+                    {"platform_family": ["rhel"]}
+                ]
+            }"#,
+        );
+        jd.version = String::from("v1");
+        let (filters, errors) = parse_filters(&jd);
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            vec![
+                "$.job_dispatch.version: filters are only used by job_dispatch version v2, \
+                 found v1",
+                "$.job_dispatch.filters.lint[0]: expected an object of attributes",
+                "$.job_dispatch.filters.lint[1]: empty clause",
+                "$.job_dispatch.filters.lint[2].platform_family: expected a non-empty list \
+                 of strings",
+                "$.job_dispatch.filters.unit: expected a list of clauses",
+                "$.job_dispatch.filters.units: unknown phase 'units', expected one of: unit, \
+                 lint, syntax, security, quality, publish, provision, deploy, smoke, functional",
+            ],
+            messages
+        );
+        // The valid clause is still evaluated
+        assert_eq!(1, filters["lint"].len());
+    }
+}
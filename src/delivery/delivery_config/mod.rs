@@ -15,6 +15,7 @@
 // limitations under the License.
 //
 
+use self::paths::Paths;
 use errors::{DeliveryError, Kind};
 use git;
 use serde_json;
@@ -26,9 +27,15 @@ use std::fmt::Debug;
 use std::path::{Path, PathBuf};
 use types::DeliveryResult;
 use utils::path_join_many::PathJoinMany;
+use utils::say::sayln;
 use utils::{copy_recursive, file_needs_updated, read_file, walk_tree_for_path};
 
+pub mod dependencies;
+pub mod extends;
+pub mod job_dispatch;
+pub mod paths;
 pub mod project;
+pub mod schema;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct DeliveryConfig {
@@ -38,6 +45,7 @@ pub struct DeliveryConfig {
     pub build_nodes: Option<HashMap<String, Vec<String>>>,
     pub job_dispatch: Option<JobDispatch>,
     pub dependencies: Option<Vec<String>>,
+    pub paths: Option<Paths>,
 }
 
 // JobDispatch Struct
@@ -98,6 +106,7 @@ impl Default for DeliveryConfig {
             build_nodes: None,
             job_dispatch: Some(JobDispatch::default()),
             dependencies: Some(Vec::new()),
+            paths: None,
         }
     }
 }
@@ -110,23 +119,39 @@ impl Default for DeliveryConfig {
 //   * Supermarket - From a Supermarket Server
 //   * Workflow    - From the Workflow Server
 //   * ChefServer  - From the Chef Server
+//   * Url         - A tarball from an HTTP(S) server, verified by its sha256
 //
 // Examples: https://docs.chef.io/config_json_delivery.html#examples
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum BuildCookbookLocation {
     Local,
     Git,
     Supermarket,
     Workflow,
     ChefServer,
+    Url,
 }
 
+// The key of the build_cookbook that defines each location, in order
+// of precedence. A valid config uses only one of them (the schema
+// validation reports a conflict otherwise) but if more than one is
+// present the first one in this list wins.
+pub const BUILD_COOKBOOK_SOURCES: &'static [(&'static str, BuildCookbookLocation)] = &[
+    ("path", BuildCookbookLocation::Local),
+    ("git", BuildCookbookLocation::Git),
+    ("supermarket", BuildCookbookLocation::Supermarket),
+    ("enterprise", BuildCookbookLocation::Workflow),
+    ("server", BuildCookbookLocation::ChefServer),
+    ("url", BuildCookbookLocation::Url),
+];
+
 impl DeliveryConfig {
     /// Return the build_cookbook location
     ///
     /// Searches for the right field inside the build_cookbook HashMap
-    /// and translates it to a BuildCookbookLocation Enum, if none of
-    /// the possible entries exist, throws a `Err()`
+    /// following the precedence of `BUILD_COOKBOOK_SOURCES` and translates
+    /// it to a BuildCookbookLocation Enum, if none of the possible entries
+    /// exist, throws a `Err()`
     ///
     /// # Examples
     ///
@@ -138,14 +163,9 @@ impl DeliveryConfig {
     /// assert_eq!(BuildCookbookLocation::Local, location);
     /// ```
     pub fn build_cookbook_location(&self) -> DeliveryResult<BuildCookbookLocation> {
-        for (key, _) in self.build_cookbook.iter() {
-            match key.as_str() {
-                "path" => return Ok(BuildCookbookLocation::Local),
-                "git" => return Ok(BuildCookbookLocation::Git),
-                "supermarket" => return Ok(BuildCookbookLocation::Supermarket),
-                "enterprise" => return Ok(BuildCookbookLocation::Workflow),
-                "server" => return Ok(BuildCookbookLocation::ChefServer),
-                _ => continue,
+        for &(key, location) in BUILD_COOKBOOK_SOURCES.iter() {
+            if self.build_cookbook.contains_key(key) {
+                return Ok(location);
             }
         }
         Err(DeliveryError {
@@ -186,6 +206,28 @@ impl DeliveryConfig {
         self.build_cookbook_get("name")
     }
 
+    /// Split the provided phases into the ones to run and the ones the
+    /// project skips through the `skip_phases` field, keeping their order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use delivery::delivery_config::DeliveryConfig;
+    ///
+    /// let mut config = DeliveryConfig::default();
+    /// config.skip_phases = Some(vec!["syntax".to_string()]);
+    /// let (run, skipped) = config.partition_phases(&["lint", "syntax", "unit"]);
+    /// assert_eq!(vec!["lint", "unit"], run);
+    /// assert_eq!(vec!["syntax"], skipped);
+    /// ```
+    pub fn partition_phases<'a>(&self, phases: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+        let skip_phases = self.skip_phases.clone().unwrap_or_default();
+        phases
+            .iter()
+            .cloned()
+            .partition(|p| !skip_phases.iter().any(|s| s.as_str() == *p))
+    }
+
     /// Copy a provided `config.json` file to `.delivery/` of
     /// the project root path. Also verify that the config is
     /// valid and finally add/commit the changes.
@@ -208,6 +250,21 @@ impl DeliveryConfig {
     }
 
     pub fn git_add_commit_config<P>(proj_path: P) -> DeliveryResult<bool>
+    where
+        P: AsRef<Path>,
+    {
+        DeliveryConfig::git_add_commit_config_with_message(
+            proj_path,
+            "Adds custom Delivery config",
+        )
+    }
+
+    // Add/commit the config.json with a custom commit message, returns
+    // `false` if there was nothing to commit.
+    pub fn git_add_commit_config_with_message<P>(
+        proj_path: P,
+        message: &str,
+    ) -> DeliveryResult<bool>
     where
         P: AsRef<Path>,
     {
@@ -217,7 +274,7 @@ impl DeliveryConfig {
 
         // Commit the changes made in .delivery but detect if nothing has changed,
         // if that is the case, we are Ok() to continue
-        match git::git_commit("Adds custom Delivery config") {
+        match git::git_commit(message) {
             Ok(_) => Ok(true),
             Err(DeliveryError {
                 kind: Kind::EmptyGitCommit,
@@ -235,7 +292,7 @@ impl DeliveryConfig {
         p_path.as_ref().join_many(&[".delivery", "config.json"])
     }
 
-    fn find_config_file<P>(proj_path: P) -> DeliveryResult<PathBuf>
+    pub fn find_config_file<P>(proj_path: P) -> DeliveryResult<PathBuf>
     where
         P: AsRef<Path>,
     {
@@ -252,11 +309,65 @@ impl DeliveryConfig {
     }
 
     // Validate the config.json file
+    //
+    // The file is validated against the JSON Schema of its version first,
+    // that way we report every problem with its location instead of the
+    // first error that serde finds. They are only warnings here, a config
+    // that we can load is good enough to run a job; `delivery config-json
+    // validate` is the one that fails on them.
     pub fn validate_config_file<P>(p_path: P) -> DeliveryResult<bool>
     where
         P: AsRef<Path> + Debug,
     {
-        DeliveryConfig::load_config(p_path).and(Ok(true))
+        DeliveryConfig::load_validated_config(p_path).and(Ok(true))
+    }
+
+    // Read the config.json merged with the configs it `extends`, warn
+    // about its schema errors and make sure that we can load it.
+    fn load_validated_config<P>(p_path: P) -> DeliveryResult<SerdeJson>
+    where
+        P: AsRef<Path> + Debug,
+    {
+        let config_path = DeliveryConfig::find_config_file(&p_path)?;
+        let content = read_file(&config_path)?;
+        let config_json = extends::resolve(&config_path, &extends::BaseCache::new()?)?;
+        let errors = schema::validate_merged(&content, &config_json);
+        if !errors.is_empty() {
+            sayln(
+                "yellow",
+                "The config.json doesn't match its schema, see: \
+                 https://docs.chef.io/config_json_delivery.html",
+            );
+            for e in errors.iter() {
+                sayln("yellow", &format!("  {}:{}", config_path.display(), e));
+            }
+        }
+        DeliveryConfig::from_json(config_json.clone())?;
+        Ok(config_json)
+    }
+
+    // Validate a config.json against the JSON Schema of its version
+    //
+    // A config.json that `extends` another one is validated once merged
+    // with it, its own keys are not a complete config.
+    pub fn schema_errors(config_path: &Path) -> DeliveryResult<Vec<schema::SchemaError>> {
+        let content = read_file(config_path)?;
+        match serde_json::from_str::<SerdeJson>(&content) {
+            Ok(ref json) if json.get("extends").is_some() => {
+                let merged = extends::resolve(config_path, &extends::BaseCache::new()?)?;
+                Ok(schema::validate_merged(&content, &merged))
+            }
+            _ => Ok(schema::validate(&content)),
+        }
+    }
+
+    // Read the .delivery/config.json merged with the configs it `extends`
+    pub fn resolve_config<P>(p_path: P) -> DeliveryResult<SerdeJson>
+    where
+        P: AsRef<Path>,
+    {
+        let config_path = DeliveryConfig::find_config_file(p_path)?;
+        extends::resolve(&config_path, &extends::BaseCache::new()?)
     }
 
     // Load the .delivery/config.json into a DeliveryConfig object
@@ -269,17 +380,21 @@ impl DeliveryConfig {
         P: AsRef<Path> + Debug,
     {
         debug!("Loading config.json into memory from path: {:?}", p_path);
-        let config_json = read_file(&DeliveryConfig::find_config_file(&p_path)?)?;
+        DeliveryConfig::from_json(DeliveryConfig::resolve_config(&p_path)?)
+    }
 
+    // Decode a config.json that is already merged with the configs it
+    // `extends`, see `load_config`.
+    fn from_json(config_json: SerdeJson) -> DeliveryResult<Self> {
         // Try to decode the config, but if you are unable to, try V1;
         // If you are still unable; just fail
-        let json: DeliveryConfig = serde_json::from_str(&config_json).or_else(|e_v2| {
+        let json: DeliveryConfig = serde_json::from_value(config_json.clone()).or_else(|e_v2| {
             debug!(
                 "Unable to parse DeliveryConfig: {}\nV2 Error: {}",
                 config_json, e_v2
             );
             debug!("Attempting to load version: 1");
-            let v1_config = DeliveryConfigV1::load_config(&p_path).or_else(|e_v1| {
+            let v1_config: DeliveryConfigV1 = serde_json::from_value(config_json).or_else(|e_v1| {
                 debug!("Unable to parse DeliveryConfigV1: {}", e_v1);
                 // If we couldn't parse any version of the delivery config,
                 // lets make sure we give the user the right error message
@@ -299,6 +414,29 @@ impl DeliveryConfig {
         Ok(json)
     }
 
+    // Migrate a RAW config.json from V1 to V2
+    //
+    // Only the `version` and the `build_cookbook` change, any other field,
+    // including the custom attributes for the build_cookbook, is kept as-is.
+    pub fn migrate_v1_to_v2(raw: &SerdeJson) -> DeliveryResult<SerdeJson> {
+        let v1: DeliveryConfigV1 = serde_json::from_value(raw.clone()).map_err(|e| {
+            DeliveryError {
+                kind: Kind::DeliveryConfigParse,
+                detail: Some(format!("  version 1: {}", e)),
+            }
+        })?;
+        let v2 = v1.convert_to_v2()?;
+        let mut migrated = raw.clone();
+        if let Some(map) = migrated.as_object_mut() {
+            map.insert(String::from("version"), SerdeJson::String(String::from("2")));
+            map.insert(
+                String::from("build_cookbook"),
+                serde_json::to_value(&v2.build_cookbook)?,
+            );
+        }
+        Ok(migrated)
+    }
+
     // Load RAW .delivery/config.json
     //
     // At the moment we allow the config to have an infinite possibility of options
@@ -306,16 +444,14 @@ impl DeliveryConfig {
     // to have a mechanism to load them all without excluding non-reserved ones
     //
     // This method will first verify that the config is valid and if it is, load it
-    // in a RAW format by leveraging the `serde_json::Value` Enum.
+    // in a RAW format by leveraging the `serde_json::Value` Enum. The config is
+    // merged with the configs it `extends`, so this is the final document.
     pub fn load_raw_config(p_path: &PathBuf) -> DeliveryResult<SerdeJson> {
-        DeliveryConfig::validate_config_file(p_path)?;
         debug!(
             "Loading RAW config.json into memory from path: {}",
             p_path.display()
         );
-        let config_json = read_file(&DeliveryConfig::find_config_file(p_path)?)?;
-        let json: SerdeJson = serde_json::from_str(&config_json)?;
-        Ok(json)
+        DeliveryConfig::load_validated_config(p_path)
     }
 }
 
@@ -394,6 +530,7 @@ impl DeliveryConfigV1 {
                 build_nodes: self.build_nodes,
                 job_dispatch: None,
                 dependencies: None,
+                paths: None,
             },
         )
     }
@@ -417,6 +554,37 @@ mod tests {
             assert_eq!(c_v1.skip_phases, Some(Vec::new()));
             assert_eq!(c_v1.build_nodes, Some(HashMap::new()));
         }
+
+        #[test]
+        fn migrate_v1_to_v2() {
+            let raw: SerdeJson = serde_json::from_str(
+                r#"{
+                    "version": "1",
+                    "build_cookbook": "./.delivery/build_cookbook",
+                    "skip_phases": ["smoke"],
+                    "delivery-truck": { "publish": { "chef_server": true } }
+                }"#,
+            ).unwrap();
+            let migrated = DeliveryConfig::migrate_v1_to_v2(&raw).unwrap();
+            assert_eq!("2", migrated["version"]);
+            assert_eq!("build_cookbook", migrated["build_cookbook"]["name"]);
+            assert_eq!(
+                "./.delivery/build_cookbook",
+                migrated["build_cookbook"]["path"]
+            );
+            assert_eq!(raw["skip_phases"], migrated["skip_phases"]);
+            assert_eq!(raw["delivery-truck"], migrated["delivery-truck"]);
+            assert!(schema::validate(&serde_json::to_string(&migrated).unwrap()).is_empty());
+        }
+
+        #[test]
+        fn migrate_v1_to_v2_from_the_chef_server() {
+            let raw: SerdeJson =
+                serde_json::from_str(r#"{ "version": "1", "build_cookbook": "httpd" }"#).unwrap();
+            let migrated = DeliveryConfig::migrate_v1_to_v2(&raw).unwrap();
+            assert_eq!("httpd", migrated["build_cookbook"]["name"]);
+            assert_eq!("true", migrated["build_cookbook"]["server"]);
+        }
     }
 
     mod v2 {
@@ -623,6 +791,22 @@ mod tests {
                         );
                     }
 
+                    #[test]
+                    fn precedence_with_multiple_sources() {
+                        let mut config = DeliveryConfig::default();
+                        config.build_cookbook = super::git();
+                        config
+                            .build_cookbook
+                            .insert("server".to_string(), "true".to_string());
+                        config
+                            .build_cookbook
+                            .insert("path".to_string(), "cookbooks/build".to_string());
+                        assert_eq!(
+                            BuildCookbookLocation::Local,
+                            config.build_cookbook_location().unwrap()
+                        );
+                    }
+
                     #[test]
                     fn failure_invalid_build_cookbook() {
                         let mut config = DeliveryConfig::default();
@@ -0,0 +1,249 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Path-Based Phase Triggering
+//!
+//! The `paths` of a config.json declare which files of the repository
+//! each phase is about, so a change that doesn't touch them (i.e. a docs
+//! only change in a monorepo) can skip the phase:
+//!
+//! ```json
+//! "paths": {
+//!   "unit": ["src/**", "Cargo.toml"],
+//!   "projects": {
+//!     "frontend": { "paths": ["web/**"], "phases": ["lint", "unit"] }
+//!   }
+//! }
+//! ```
+//!
+//! A phase runs if one of the changed files matches one of its globs, or
+//! one of the globs of a sub-project that lists the phase. A phase that
+//! has no globs at all always runs, and so does every phase when the
+//! changed files are unknown.
+//!
+//! In a glob, `*` matches any characters but `/`, `**` matches any
+//! characters including `/`, and `?` matches a single character but `/`.
+use errors::{DeliveryError, Kind};
+use regex::{self, Regex};
+use std::collections::BTreeMap;
+use types::DeliveryResult;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Paths {
+    pub projects: Option<BTreeMap<String, SubProject>>,
+    // The globs of every phase
+    #[serde(flatten)]
+    pub phases: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SubProject {
+    pub paths: Vec<String>,
+    pub phases: Vec<String>,
+}
+
+/// Whether a phase runs and why, written into the `dna.json`
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PhaseDecision {
+    pub run: bool,
+    pub reason: String,
+    pub matched_files: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PathsDecision {
+    // `None` when the changed files couldn't be found
+    pub changed_files: Option<Vec<String>>,
+    pub phases: BTreeMap<String, PhaseDecision>,
+}
+
+impl PathsDecision {
+    pub fn runs(&self, phase: &str) -> bool {
+        self.phases.get(phase).map(|d| d.run).unwrap_or(true)
+    }
+}
+
+impl Paths {
+    /// The globs that trigger a phase, directly or through sub-projects
+    pub fn globs(&self, phase: &str) -> Vec<&String> {
+        let mut globs: Vec<&String> = self
+            .phases
+            .get(phase)
+            .map(|g| g.iter().collect())
+            .unwrap_or_default();
+        if let Some(ref projects) = self.projects {
+            for project in projects.values() {
+                if project.phases.iter().any(|p| p == phase) {
+                    globs.extend(project.paths.iter());
+                }
+            }
+        }
+        globs
+    }
+
+    /// Decide which phases run for the files changed by a change
+    pub fn decide(
+        &self,
+        phases: &[&str],
+        changed_files: Option<&Vec<String>>,
+    ) -> DeliveryResult<PathsDecision> {
+        let mut decisions = BTreeMap::new();
+        for phase in phases {
+            let globs = self.globs(phase);
+            let decision = match changed_files {
+                _ if globs.is_empty() => PhaseDecision {
+                    run: true,
+                    reason: String::from("the phase has no paths"),
+                    matched_files: Vec::new(),
+                },
+                None => PhaseDecision {
+                    run: true,
+                    reason: String::from("the changed files are unknown"),
+                    matched_files: Vec::new(),
+                },
+                Some(files) => {
+                    let patterns: Vec<Regex> = globs
+                        .iter()
+                        .map(|g| glob_to_regex(g))
+                        .collect::<DeliveryResult<_>>()?;
+                    let matched: Vec<String> = files
+                        .iter()
+                        .filter(|f| patterns.iter().any(|p| p.is_match(f)))
+                        .cloned()
+                        .collect();
+                    PhaseDecision {
+                        run: !matched.is_empty(),
+                        reason: if matched.is_empty() {
+                            String::from("no changed file matches its paths")
+                        } else {
+                            String::from("changed files match its paths")
+                        },
+                        matched_files: matched,
+                    }
+                }
+            };
+            decisions.insert(phase.to_string(), decision);
+        }
+        Ok(PathsDecision {
+            changed_files: changed_files.cloned(),
+            phases: decisions,
+        })
+    }
+}
+
+/// Translate a glob into a regex that matches a whole relative path
+///
+/// # Examples
+///
+/// ```
+/// use delivery::delivery_config::paths::glob_to_regex;
+///
+/// let re = glob_to_regex("src/**/*.rs").unwrap();
+/// assert!(re.is_match("src/main.rs"));
+/// assert!(re.is_match("src/job/workspace.rs"));
+/// assert!(!re.is_match("docs/src/main.rs"));
+/// ```
+pub fn glob_to_regex(glob: &str) -> DeliveryResult<Regex> {
+    let mut re = String::from("^");
+    let chars: Vec<char> = glob.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                // `**/` also matches no directory at all
+                if chars.get(i + 2) == Some(&'/') {
+                    re.push_str("(?:.*/)?");
+                    i += 1;
+                } else {
+                    re.push_str(".*");
+                }
+                i += 1;
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    re.push('$');
+    Regex::new(&re).map_err(|e| DeliveryError {
+        kind: Kind::InvalidDeliveryConfig,
+        detail: Some(format!("invalid glob '{}' in the paths: {}", glob, e)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn paths() -> Paths {
+        serde_json::from_str(
+            r#"{
+                "unit": ["src/**", "Cargo.toml"],
+                "projects": {
+                    "frontend": { "paths": ["web/**"], "phases": ["lint", "unit"] }
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn files(files: &[&str]) -> Vec<String> {
+        files.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn globs() {
+        let matches = |glob: &str, path: &str| glob_to_regex(glob).unwrap().is_match(path);
+        assert!(matches("src/**", "src/a/b.rs"));
+        assert!(matches("**/*.md", "README.md"));
+        assert!(matches("**/*.md", "docs/guide/intro.md"));
+        assert!(matches("*.toml", "Cargo.toml"));
+        assert!(!matches("*.toml", "config/cli.toml"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("Cargo.toml", "Cargo_toml"));
+    }
+
+    #[test]
+    fn globs_of_phases_and_sub_projects() {
+        let paths = paths();
+        let globs =
+            |phase: &str| -> Vec<String> { paths.globs(phase).into_iter().cloned().collect() };
+        assert_eq!(vec!["src/**", "Cargo.toml", "web/**"], globs("unit"));
+        assert_eq!(vec!["web/**"], globs("lint"));
+        assert!(paths.globs("syntax").is_empty());
+    }
+
+    #[test]
+    fn decide_which_phases_run() {
+        let changed = files(&["docs/index.md", "web/app.js"]);
+        let decision = paths()
+            .decide(&["unit", "lint", "syntax"], Some(&changed))
+            .unwrap();
+        assert_eq!(vec!["web/app.js"], decision.phases["unit"].matched_files);
+        assert!(decision.runs("lint"));
+        assert!(decision.runs("syntax"));
+
+        let changed = files(&["docs/index.md"]);
+        let decision = paths().decide(&["unit", "syntax"], Some(&changed)).unwrap();
+        assert!(!decision.runs("unit"));
+        assert!(decision.runs("syntax"));
+
+        let decision = paths().decide(&["unit"], None).unwrap();
+        assert!(decision.runs("unit"));
+    }
+}
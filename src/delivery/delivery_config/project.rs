@@ -23,6 +23,7 @@
 use errors::{DeliveryError, Kind};
 use hyper::Client as HyperClient;
 use project;
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::fmt::{Display, Error, Formatter};
 use std::io::Read;
@@ -36,18 +37,141 @@ use utils::path_join_many::PathJoinMany;
 pub struct ProjectToml {
     pub remote_file: Option<String>,
     pub local_phases: Option<LocalPhases>,
+    pub stages: Option<BTreeMap<String, Vec<String>>>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
-pub struct LocalPhases {
-    pub unit: Option<String>,
-    pub lint: Option<String>,
-    pub syntax: Option<String>,
-    pub provision: Option<String>,
-    pub deploy: Option<String>,
-    pub smoke: Option<String>,
-    pub functional: Option<String>,
-    pub cleanup: Option<String>,
+// The local phases by name, the standard Workflow phases and any other
+// phase that a project needs (i.e. `security` or `docs`).
+pub type LocalPhases = BTreeMap<String, LocalPhase>;
+
+/// The phases of Workflow, a project.toml doesn't need to declare them
+pub const STANDARD_PHASES: &'static [&'static str] = &[
+    "unit",
+    "lint",
+    "syntax",
+    "provision",
+    "deploy",
+    "smoke",
+    "functional",
+    "cleanup",
+];
+
+/// The stages of Workflow, a project.toml can declare other stages in
+/// its `[stages]` table or change the phases of these ones:
+///
+/// ```toml
+/// [stages]
+/// precommit = ["lint", "security"]
+/// ```
+pub const STANDARD_STAGES: &'static [&'static str] = &["verify", "acceptance", "all"];
+
+// A local phase is either a string that runs through the shell of the
+// user, so pipes, quotes and `&&` work as usual:
+//
+//   unit = "rspec spec/ && cookstyle"
+//
+// or a table that runs a command without a shell, or a list of steps
+// that run in order until one of them fails:
+//
+//   [local_phases.unit]
+//   command = "rspec"
+//   args = ["spec/", "--format", "documentation"]
+//   env = { RAILS_ENV = "test" }
+//   cwd = "cookbooks/app"
+//
+//   [local_phases.lint]
+//   steps = [{ command = "cookstyle" }, { command = "foodcritic", args = ["."] }]
+//
+// TOML doesn't allow strings and tables in the same array, so the steps
+// are either all shell strings or all tables.
+//
+// The `env` and `cwd` of a table also apply to its steps. The `cwd` is
+// relative to the root of the project, or to the `cwd` of the table for
+// its steps.
+//
+// A table can also be killed after `timeout` seconds, and run again up to
+// `retries` times when it fails, waiting `retry_delay` seconds in between:
+//
+//   [local_phases.functional]
+//   steps = ["chef exec kitchen verify"]
+//   timeout = 1800
+//   retries = 2
+//   retry_delay = 30
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum LocalPhase {
+    Shell(String),
+    Command(PhaseCommand),
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct PhaseCommand {
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<String>,
+    pub steps: Option<Vec<LocalPhase>>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+    pub retry_delay: Option<u64>,
+}
+
+impl Display for LocalPhase {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            LocalPhase::Shell(ref cmd) => write!(f, "{}", cmd),
+            LocalPhase::Command(PhaseCommand {
+                command: Some(ref cmd),
+                ref args,
+                ..
+            }) => {
+                let mut words = vec![cmd.clone()];
+                words.extend(args.iter().map(|a| format!("{:?}", a)));
+                write!(f, "{}", words.join(" "))
+            }
+            LocalPhase::Command(PhaseCommand {
+                steps: Some(ref steps),
+                ..
+            }) => {
+                let steps: Vec<String> = steps.iter().map(|s| s.to_string()).collect();
+                write!(f, "{}", steps.join("; "))
+            }
+            LocalPhase::Command(_) => write!(f, "(nothing)"),
+        }
+    }
+}
+
+impl LocalPhase {
+    // A table has either a `command` or `steps`
+    pub fn validate(&self) -> DeliveryResult<()> {
+        let table = match *self {
+            LocalPhase::Shell(_) => return Ok(()),
+            LocalPhase::Command(ref t) => t,
+        };
+        if table.timeout == Some(0) {
+            return Err(DeliveryError {
+                kind: Kind::InvalidLocalPhase,
+                detail: Some(String::from(
+                    "The 'timeout' of a local phase must be greater than 0",
+                )),
+            });
+        }
+        match (&table.command, &table.steps) {
+            (&Some(_), &None) => Ok(()),
+            (&None, &Some(ref steps)) if !steps.is_empty() => {
+                steps.iter().map(|s| s.validate()).collect()
+            }
+            _ => Err(DeliveryError {
+                kind: Kind::InvalidLocalPhase,
+                detail: Some(String::from(
+                    "A local phase table needs either a 'command' or a non-empty list of \
+                     'steps'",
+                )),
+            }),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -60,13 +184,17 @@ pub enum Phase {
     Smoke,
     Functional,
     Cleanup,
+    // A phase that only the project.toml declares
+    Custom(String),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Stage {
     Verify,
     Acceptance,
     All,
+    // A stage of the project.toml, with the phases it runs in order
+    Custom(String, Vec<Phase>),
 }
 
 // Modify how we display this enum so we can print the phases
@@ -82,6 +210,7 @@ impl Display for Phase {
             Phase::Smoke => write!(f, "smoke"),
             Phase::Functional => write!(f, "functional"),
             Phase::Cleanup => write!(f, "cleanup"),
+            Phase::Custom(ref name) => write!(f, "{}", name),
         }
     }
 }
@@ -92,6 +221,34 @@ impl Display for Stage {
             Stage::Verify => write!(f, "Verify"),
             Stage::Acceptance => write!(f, "Acceptance"),
             Stage::All => write!(f, "Every"),
+            Stage::Custom(ref name, _) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Phase {
+    /// The phase with that name, the standard phases are aliases of
+    /// their variant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use delivery::delivery_config::project::Phase;
+    ///
+    /// assert_eq!(Phase::Lint, Phase::from_name("lint"));
+    /// assert_eq!(Phase::Custom("security".to_string()), Phase::from_name("security"));
+    /// ```
+    pub fn from_name(name: &str) -> Phase {
+        match name {
+            "unit" => Phase::Unit,
+            "lint" => Phase::Lint,
+            "syntax" => Phase::Syntax,
+            "provision" => Phase::Provision,
+            "deploy" => Phase::Deploy,
+            "smoke" => Phase::Smoke,
+            "functional" => Phase::Functional,
+            "cleanup" => Phase::Cleanup,
+            _ => Phase::Custom(name.to_string()),
         }
     }
 }
@@ -117,6 +274,23 @@ impl Stage {
                 Phase::Functional,
                 Phase::Cleanup,
             ],
+            Stage::Custom(_, ref phases) => phases.clone(),
+        }
+    }
+
+    /// The phases of the stage grouped so that the phases of a group are
+    /// independent of each other and can run at the same time, the groups
+    /// have to run in order.
+    pub fn phase_groups(&self) -> Vec<Vec<Phase>> {
+        let verify = vec![Phase::Lint, Phase::Syntax, Phase::Unit];
+        // Every acceptance phase works on what the previous one did
+        let acceptance = Stage::Acceptance.phases().into_iter().map(|p| vec![p]);
+        match *self {
+            Stage::Verify => vec![verify],
+            Stage::Acceptance => acceptance.collect(),
+            Stage::All => Some(verify).into_iter().chain(acceptance).collect(),
+            // We don't know which phases of a custom stage are independent
+            Stage::Custom(_, ref phases) => phases.iter().map(|p| vec![p.clone()]).collect(),
         }
     }
 }
@@ -125,16 +299,8 @@ impl Default for ProjectToml {
     fn default() -> Self {
         ProjectToml {
             remote_file: None,
-            local_phases: Some(LocalPhases {
-                unit: None,
-                lint: None,
-                syntax: None,
-                provision: None,
-                deploy: None,
-                smoke: None,
-                functional: None,
-                cleanup: None,
-            }),
+            local_phases: Some(LocalPhases::new()),
+            stages: None,
         }
     }
 }
@@ -155,6 +321,12 @@ impl ProjectToml {
         }
     }
 
+    /// Load the project.toml of the project without following its
+    /// `remote_file`, if it has one.
+    pub fn load_local_toml() -> DeliveryResult<ProjectToml> {
+        ProjectToml::load_toml_file(ProjectToml::toml_file_path(project::project_path()?))
+    }
+
     fn load_toml_file(toml_path: PathBuf) -> DeliveryResult<ProjectToml> {
         debug!("Loading local project.toml from {:?}", toml_path);
         ProjectToml::validate_file(&toml_path)?;
@@ -179,24 +351,21 @@ impl ProjectToml {
         }
     }
 
-    pub fn local_phase(&self, phase: Option<Phase>) -> DeliveryResult<Option<String>> {
+    pub fn local_phase(&self, phase: Option<Phase>) -> DeliveryResult<Option<LocalPhase>> {
         if let Some(p) = phase {
-            match self.local_phases {
-                Some(ref phases) => match p {
-                    Phase::Unit => Ok(phases.unit.clone()),
-                    Phase::Lint => Ok(phases.lint.clone()),
-                    Phase::Syntax => Ok(phases.syntax.clone()),
-                    Phase::Provision => Ok(phases.provision.clone()),
-                    Phase::Deploy => Ok(phases.deploy.clone()),
-                    Phase::Smoke => Ok(phases.smoke.clone()),
-                    Phase::Functional => Ok(phases.functional.clone()),
-                    Phase::Cleanup => Ok(phases.cleanup.clone()),
-                },
-                None => Err(DeliveryError {
-                    kind: Kind::LocalPhasesNotFound,
-                    detail: None,
-                }),
+            let local_phase = match self.local_phases {
+                Some(ref phases) => phases.get(&p.to_string()).cloned(),
+                None => {
+                    return Err(DeliveryError {
+                        kind: Kind::LocalPhasesNotFound,
+                        detail: None,
+                    })
+                }
+            };
+            if let Some(ref l) = local_phase {
+                l.validate()?;
             }
+            Ok(local_phase)
         } else {
             Err(DeliveryError {
                 kind: Kind::PhaseNotFound,
@@ -205,6 +374,76 @@ impl ProjectToml {
         }
     }
 
+    /// The names of the phases and the stages that the project can run,
+    /// the standard ones first.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = STANDARD_PHASES
+            .iter()
+            .chain(STANDARD_STAGES.iter())
+            .map(|n| n.to_string())
+            .collect();
+        let declared = self
+            .local_phases
+            .iter()
+            .flat_map(|p| p.keys())
+            .chain(self.stages.iter().flat_map(|s| s.keys()));
+        for name in declared {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
+    /// The phase with that name, if it is a standard phase or one of the
+    /// `local_phases`.
+    pub fn phase(&self, name: &str) -> Option<Phase> {
+        let declared = self
+            .local_phases
+            .as_ref()
+            .map(|p| p.contains_key(name))
+            .unwrap_or(false);
+        if declared || STANDARD_PHASES.contains(&name) {
+            Some(Phase::from_name(name))
+        } else {
+            None
+        }
+    }
+
+    /// The stage with that name, a stage of the `[stages]` table comes
+    /// before a standard one.
+    pub fn stage(&self, name: &str) -> DeliveryResult<Option<Stage>> {
+        let declared = self.stages.as_ref().and_then(|s| s.get(name));
+        if (declared.is_some() || STANDARD_STAGES.contains(&name)) && self.phase(name).is_some() {
+            return Err(DeliveryError {
+                kind: Kind::InvalidLocalPhase,
+                detail: Some(format!("'{}' is both a phase and a stage", name)),
+            });
+        }
+        if let Some(phase_names) = declared {
+            let phases = phase_names
+                .iter()
+                .map(|p| {
+                    self.phase(p).ok_or_else(|| DeliveryError {
+                        kind: Kind::InvalidLocalPhase,
+                        detail: Some(format!(
+                            "The stage '{}' runs the phase '{}' that is not in the \
+                             [local_phases] of your .delivery/project.toml",
+                            name, p
+                        )),
+                    })
+                })
+                .collect::<DeliveryResult<Vec<Phase>>>()?;
+            return Ok(Some(Stage::Custom(name.to_string(), phases)));
+        }
+        Ok(match name {
+            "verify" => Some(Stage::Verify),
+            "acceptance" => Some(Stage::Acceptance),
+            "all" => Some(Stage::All),
+            _ => None,
+        })
+    }
+
     fn toml_file_path(proj_path: PathBuf) -> PathBuf {
         proj_path.join_many(&[".delivery", "project.toml"])
     }
@@ -232,18 +471,18 @@ impl ProjectToml {
 
 #[cfg(test)]
 mod tests {
-    pub use super::{Phase, ProjectToml, Stage};
+    pub use super::{LocalPhase, Phase, ProjectToml, Stage};
 
     #[test]
     fn test_project_toml_with_defaults_plus_overrides() {
         let p_toml = ProjectToml::default();
-        let unit = "mvn test".to_string();
+        let unit = LocalPhase::Shell("mvn test".to_string());
         p_toml.local_phases.map(|mut phases| {
             // default is empty phases
-            assert_eq!(None, phases.unit);
+            assert_eq!(None, phases.get("unit"));
             // But if we fill them in
-            phases.unit = Some(unit.clone());
-            assert_eq!(unit, phases.unit.unwrap());
+            phases.insert("unit".to_string(), unit.clone());
+            assert_eq!(unit, phases["unit"].clone());
         });
     }
 
@@ -264,8 +503,21 @@ mod tests {
         // If one works all of them does :)
         assert_eq!(
             p_toml.local_phase(Some(Phase::Unit)).unwrap(),
-            p_toml.local_phases.unwrap().unit
+            p_toml.local_phases.unwrap().get("unit").cloned()
+        );
+    }
+
+    #[test]
+    fn test_stages_phase_groups() {
+        assert_eq!(
+            vec![vec![Phase::Lint, Phase::Syntax, Phase::Unit]],
+            Stage::Verify.phase_groups()
         );
+        let all = Stage::All.phase_groups();
+        assert_eq!(6, all.len());
+        assert_eq!(vec![Phase::Provision], all[1]);
+        let flattened: Vec<Phase> = all.into_iter().flatten().collect();
+        assert_eq!(Stage::All.phases(), flattened);
     }
 
     #[test]
@@ -294,7 +546,7 @@ mod tests {
     }
 
     mod when_project_toml {
-        pub use super::{Phase, ProjectToml};
+        pub use super::{LocalPhase, Phase, ProjectToml, Stage};
         mod is_well_configured {
             fn toml<'a>() -> &'a str {
                 r#"
@@ -312,32 +564,39 @@ mod tests {
 
             #[test]
             fn parse_project_config() {
+                use super::LocalPhase;
                 let project_toml = super::ProjectToml::parse_config(toml());
                 match project_toml {
                     Ok(p_toml) => {
                         p_toml.local_phases.map(|phases| {
-                            assert_eq!("rspec spec/".to_string(), phases.unit.unwrap());
-                            assert_eq!("cookstyle".to_string(), phases.lint.unwrap());
                             assert_eq!(
-                                "foodcritic . --exclude spec -f any".to_string(),
-                                phases.syntax.unwrap()
+                                LocalPhase::Shell("rspec spec/".to_string()),
+                                phases["unit"].clone()
+                            );
+                            assert_eq!(
+                                LocalPhase::Shell("cookstyle".to_string()),
+                                phases["lint"].clone()
+                            );
+                            assert_eq!(
+                                LocalPhase::Shell("foodcritic . --exclude spec -f any".to_string()),
+                                phases["syntax"].clone()
                             );
                             assert_eq!(
-                                "chef exec kitchen create".to_string(),
-                                phases.provision.unwrap()
+                                LocalPhase::Shell("chef exec kitchen create".to_string()),
+                                phases["provision"].clone()
                             );
                             assert_eq!(
-                                "chef exec kitchen converge".to_string(),
-                                phases.deploy.unwrap()
+                                LocalPhase::Shell("chef exec kitchen converge".to_string()),
+                                phases["deploy"].clone()
                             );
                             assert_eq!(
-                                "chef exec kitchen verify".to_string(),
-                                phases.smoke.unwrap()
+                                LocalPhase::Shell("chef exec kitchen verify".to_string()),
+                                phases["smoke"].clone()
                             );
-                            assert_eq!(None, phases.functional);
+                            assert_eq!(None, phases.get("functional"));
                             assert_eq!(
-                                "chef exec kitchen destroy".to_string(),
-                                phases.cleanup.unwrap()
+                                LocalPhase::Shell("chef exec kitchen destroy".to_string()),
+                                phases["cleanup"].clone()
                             );
                         });
                     }
@@ -359,20 +618,30 @@ mod tests {
 
             #[test]
             fn parse_project_config() {
+                use super::LocalPhase;
                 let project_toml = super::ProjectToml::parse_config(toml());
                 match project_toml {
                     Ok(p_toml) => {
                         p_toml.local_phases.map(|phases| {
-                            assert_eq!("rspec spec/".to_string(), phases.unit.unwrap());
-                            assert_eq!("cookstyle".to_string(), phases.lint.unwrap());
-                            assert_eq!("something".to_string(), phases.syntax.unwrap());
+                            assert_eq!(
+                                LocalPhase::Shell("rspec spec/".to_string()),
+                                phases["unit"].clone()
+                            );
+                            assert_eq!(
+                                LocalPhase::Shell("cookstyle".to_string()),
+                                phases["lint"].clone()
+                            );
+                            assert_eq!(
+                                LocalPhase::Shell("something".to_string()),
+                                phases["syntax"].clone()
+                            );
                             // The rest should be defined as None
                             // but we shouldn't fail parsing the file
-                            assert!(phases.provision.is_none());
-                            assert!(phases.deploy.is_none());
-                            assert!(phases.smoke.is_none());
-                            assert!(phases.functional.is_none());
-                            assert!(phases.cleanup.is_none());
+                            assert!(!phases.contains_key("provision"));
+                            assert!(!phases.contains_key("deploy"));
+                            assert!(!phases.contains_key("smoke"));
+                            assert!(!phases.contains_key("functional"));
+                            assert!(!phases.contains_key("cleanup"));
                         });
                     }
                     Err(e) => panic!("Failed to parse: {:?}", e.detail),
@@ -403,7 +672,127 @@ mod tests {
             }
         }
 
-        mod is_misconfigured {}
+        mod has_structured_phases {
+            use super::LocalPhase;
+
+            fn toml<'a>() -> &'a str {
+                r#"
+                [local_phases]
+                unit = "rspec spec/ | tee rspec.log"
+
+                [local_phases.lint]
+                command = "cookstyle"
+                args = ["--format", "progress"]
+                env = { LANG = "C" }
+                cwd = "cookbooks/app"
+
+                [local_phases.syntax]
+                steps = [
+                    { command = "foodcritic", args = ["."] },
+                    { command = "cookstyle", args = ["-l"] },
+                ]
+                timeout = 60
+                retries = 2
+                "#
+            }
+
+            #[test]
+            fn parse_project_config() {
+                let p_toml = super::ProjectToml::parse_config(toml()).unwrap();
+                let phases = p_toml.local_phases.unwrap();
+                match phases["lint"].clone() {
+                    LocalPhase::Command(table) => {
+                        assert_eq!(Some("cookstyle".to_string()), table.command);
+                        assert_eq!(vec!["--format", "progress"], table.args);
+                        assert_eq!(Some(&"C".to_string()), table.env.get("LANG"));
+                        assert_eq!(Some("cookbooks/app".to_string()), table.cwd);
+                    }
+                    l => panic!("Expected a table, got {:?}", l),
+                }
+                let syntax = phases["syntax"].clone();
+                assert!(syntax.validate().is_ok());
+                match syntax {
+                    LocalPhase::Command(ref table) => {
+                        assert_eq!(Some(60), table.timeout);
+                        assert_eq!(Some(2), table.retries);
+                        assert_eq!(None, table.retry_delay);
+                    }
+                    ref l => panic!("Expected a table, got {:?}", l),
+                }
+                assert_eq!("foodcritic \".\"; cookstyle \"-l\"", syntax.to_string());
+            }
+        }
+
+        mod is_misconfigured {
+            fn toml<'a>() -> &'a str {
+                r#"
+                [local_phases.unit]
+                args = ["spec/"]
+                "#
+            }
+
+            #[test]
+            fn a_table_without_command_or_steps() {
+                let p_toml = super::ProjectToml::parse_config(toml()).unwrap();
+                assert!(p_toml.local_phase(Some(super::Phase::Unit)).is_err());
+            }
+        }
+        mod has_custom_phases_and_stages {
+            use super::{Phase, Stage};
+
+            fn toml<'a>() -> &'a str {
+                r#"
+                [local_phases]
+                lint = "cookstyle"
+                security = "bundle audit"
+                docs = "yard"
+
+                [stages]
+                precommit = ["lint", "security"]
+                verify = ["docs", "unit"]
+                broken = ["lint", "nope"]
+                "#
+            }
+
+            #[test]
+            fn resolve_phases_and_stages() {
+                let p_toml = super::ProjectToml::parse_config(toml()).unwrap();
+                assert_eq!(Some(Phase::Lint), p_toml.phase("lint"));
+                assert_eq!(
+                    Some(Phase::Custom("security".to_string())),
+                    p_toml.phase("security")
+                );
+                assert_eq!(None, p_toml.phase("precommit"));
+                assert_eq!(
+                    Some(Stage::Custom(
+                        "precommit".to_string(),
+                        vec![Phase::Lint, Phase::Custom("security".to_string())]
+                    )),
+                    p_toml.stage("precommit").unwrap()
+                );
+                assert_eq!(
+                    vec![Phase::Custom("docs".to_string()), Phase::Unit],
+                    p_toml.stage("verify").unwrap().unwrap().phases()
+                );
+                assert_eq!(Some(Stage::All), p_toml.stage("all").unwrap());
+                assert_eq!(None, p_toml.stage("lint").unwrap());
+                assert!(p_toml.stage("broken").is_err());
+                assert_eq!(
+                    vec!["docs", "security", "broken", "precommit"],
+                    p_toml.names()[11..].to_vec()
+                );
+            }
+
+            #[test]
+            fn a_phase_and_a_stage_with_the_same_name() {
+                let p_toml = super::ProjectToml::parse_config(
+                    "[local_phases]\nall = \"make\"\n[stages]\nunit = [\"lint\"]\n",
+                )
+                .unwrap();
+                assert!(p_toml.stage("all").is_err());
+                assert!(p_toml.stage("unit").is_err());
+            }
+        }
     }
 
     mod toml_file_path {
@@ -420,7 +809,7 @@ mod tests {
     }
 
     mod load_toml_file {
-        pub use super::ProjectToml;
+        pub use super::{LocalPhase, ProjectToml};
         use std::fs::File;
         use std::io::Write;
         use std::path::PathBuf;
@@ -446,14 +835,14 @@ cleanup = "echo local-cleanup"
 
             let local_toml = ProjectToml::load_toml_file(path).unwrap();
             assert_eq!(
-                "echo local-unit".to_string(),
-                local_toml.local_phases.unwrap().unit.unwrap()
+                LocalPhase::Shell("echo local-unit".to_string()),
+                local_toml.local_phases.unwrap()["unit"]
             );
         }
     }
 
     mod load_toml_remote {
-        pub use super::ProjectToml;
+        pub use super::{LocalPhase, ProjectToml};
         use mockito::mock;
 
         #[test]
@@ -479,8 +868,8 @@ cleanup = "echo remote-cleanup"
 
             let remote_toml = ProjectToml::load_toml_remote(url).unwrap();
             assert_eq!(
-                "echo remote-unit".to_string(),
-                remote_toml.local_phases.unwrap().unit.unwrap()
+                LocalPhase::Shell("echo remote-unit".to_string()),
+                remote_toml.local_phases.unwrap()["unit"]
             );
         }
     }
@@ -0,0 +1,680 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Config.json Schema Validation
+//!
+//! Validates a `.delivery/config.json` against the JSON Schema of its
+//! version, published under the `schemas/` directory of this repository,
+//! plus a few rules that a schema can't express: misspelled reserved
+//! keys and a `build_cookbook` with more than one source.
+//!
+//! Every error points to the JSON path of the offending value along with
+//! the line and column where it is in the file.
+//!
+//! Only the subset of JSON Schema used by our schemas is implemented:
+//! `type` (a name or a list of names), `enum`, `pattern`, `required`, `properties`,
+//! `additionalProperties` and `items`.
+//!
+use config::validate::distance;
+use delivery_config::BUILD_COOKBOOK_SOURCES;
+use regex::Regex;
+use serde_json;
+use serde_json::Value as SerdeJson;
+use std::collections::HashMap;
+use std::fmt;
+
+pub const V1_SCHEMA: &'static str = include_str!("../../../schemas/delivery-config-v1.json");
+pub const V2_SCHEMA: &'static str = include_str!("../../../schemas/delivery-config-v2.json");
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(l), Some(c)) => write!(f, "{}:{}: {}: {}", l, c, self.path, self.message),
+            _ => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Validate the content of a config.json, returns all the errors found.
+pub fn validate(content: &str) -> Vec<SchemaError> {
+    let json: SerdeJson = match serde_json::from_str(content) {
+        Ok(j) => j,
+        Err(e) => {
+            return vec![SchemaError {
+                path: String::from("$"),
+                line: Some(e.line()),
+                column: Some(e.column()),
+                message: format!("invalid JSON: {}", e),
+            }]
+        }
+    };
+
+    locate_errors(content, validate_json(&json))
+}
+
+/// Validate a config.json merged with the config that it `extends`, the
+/// errors are located in `content` (the local file) if the offending
+/// value is defined there.
+pub fn validate_merged(content: &str, merged: &SerdeJson) -> Vec<SchemaError> {
+    locate_errors(content, validate_json(merged))
+}
+
+fn validate_json(json: &SerdeJson) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    let schema_str = match json.get("version") {
+        Some(&SerdeJson::String(ref v)) if v == "1" => V1_SCHEMA,
+        Some(&SerdeJson::String(ref v)) if v == "2" => V2_SCHEMA,
+        // The schema will report the missing version
+        None => V2_SCHEMA,
+        Some(v) => {
+            errors.push(error(
+                "$.version",
+                format!("version must be \"1\" or \"2\", found {}", v),
+            ));
+            return errors;
+        }
+    };
+    let schema: SerdeJson =
+        serde_json::from_str(schema_str).expect("The embedded JSON Schemas are valid JSON");
+
+    check(&schema, json, "$", &mut errors);
+    check_reserved_keys(&schema, json, &mut errors);
+    if schema_str == V2_SCHEMA {
+        check_build_cookbook_sources(json, &mut errors);
+        check_build_cookbook_git_ref(json, &mut errors);
+        check_build_cookbook_url(json, &mut errors);
+    }
+    errors
+}
+
+// Validate a value against a (sub)schema
+fn check(schema: &SerdeJson, value: &SerdeJson, path: &str, errors: &mut Vec<SchemaError>) {
+    // The type is a name or a list of names
+    let types: Vec<&str> = match schema.get("type") {
+        Some(&SerdeJson::String(ref t)) => vec![t.as_str()],
+        Some(&SerdeJson::Array(ref t)) => t.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.contains(&type_name(value)) {
+        errors.push(error(
+            path,
+            format!("expected {}, found {}", types.join(" or "), type_name(value)),
+        ));
+        return;
+    }
+
+    if let Some(values) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !values.contains(value) {
+            let allowed: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            errors.push(error(
+                path,
+                format!("{} is not one of {}", value, allowed.join(", ")),
+            ));
+        }
+    }
+
+    if let (Some(p), Some(s)) = (
+        schema.get("pattern").and_then(|p| p.as_str()),
+        value.as_str(),
+    ) {
+        let re = Regex::new(p).expect("The patterns of the embedded JSON Schemas are valid");
+        if !re.is_match(s) {
+            errors.push(error(
+                path,
+                format!("{} doesn't match the pattern {}", value, p),
+            ));
+        }
+    }
+
+    match *value {
+        SerdeJson::Object(ref map) => {
+            if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+                for key in required.iter().filter_map(|r| r.as_str()) {
+                    if !map.contains_key(key) {
+                        errors.push(error(path, format!("missing required key '{}'", key)));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            for (key, child_value) in map.iter() {
+                let child = child_path(path, key);
+                match properties.and_then(|p| p.get(key)) {
+                    Some(child_schema) => check(child_schema, child_value, &child, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(&SerdeJson::Bool(false)) => {
+                            let known: Vec<&str> = properties
+                                .map(|p| p.keys().map(|k| k.as_str()).collect())
+                                .unwrap_or_default();
+                            errors.push(error(&child, unknown_key(key, &known)));
+                        }
+                        Some(s @ &SerdeJson::Object(_)) => check(s, child_value, &child, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        SerdeJson::Array(ref items) => {
+            if let Some(items_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(items_schema, item, &format!("{}[{}]", path, i), errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// Custom attributes are allowed at the top level, but one that is a
+// typo of a reserved key (i.e. `skip_phase`) is most likely a mistake.
+fn check_reserved_keys(schema: &SerdeJson, json: &SerdeJson, errors: &mut Vec<SchemaError>) {
+    let reserved: Vec<&str> = match schema.get("properties").and_then(|p| p.as_object()) {
+        Some(p) => p.keys().map(|k| k.as_str()).collect(),
+        None => return,
+    };
+    if let Some(map) = json.as_object() {
+        for key in map.keys() {
+            if reserved.contains(&key.as_str()) {
+                continue;
+            }
+            if let Some(s) = suggest(key, &reserved) {
+                errors.push(error(
+                    &child_path("$", key),
+                    format!("unknown key '{}', did you mean '{}'?", key, s),
+                ));
+            }
+        }
+    }
+}
+
+// A build_cookbook must be fetched from exactly one source
+fn check_build_cookbook_sources(json: &SerdeJson, errors: &mut Vec<SchemaError>) {
+    let build_cookbook = match json.get("build_cookbook").and_then(|b| b.as_object()) {
+        Some(b) => b,
+        None => return,
+    };
+    let all: Vec<&str> = BUILD_COOKBOOK_SOURCES.iter().map(|s| s.0).collect();
+    let found: Vec<&str> = all
+        .iter()
+        .cloned()
+        .filter(|s| build_cookbook.contains_key(*s))
+        .collect();
+    let message = match found.len() {
+        0 => format!("the build_cookbook needs a source, one of: {}", all.join(", ")),
+        // A tarball is only trusted if we can verify its checksum
+        1 if found[0] == "url" && !build_cookbook.contains_key("sha256") => {
+            String::from("a build_cookbook from a url needs the 'sha256' of the tarball")
+        }
+        1 => return,
+        _ => format!(
+            "the build_cookbook has more than one source ({}), use only one of: {}",
+            found.join(", "),
+            all.join(", ")
+        ),
+    };
+    errors.push(error("$.build_cookbook", message));
+}
+
+// A git build_cookbook is pinned to a single branch, tag or revision
+fn check_build_cookbook_git_ref(json: &SerdeJson, errors: &mut Vec<SchemaError>) {
+    let build_cookbook = match json.get("build_cookbook").and_then(|b| b.as_object()) {
+        Some(b) => b,
+        None => return,
+    };
+    let refs: Vec<&str> = ["branch", "tag", "revision"]
+        .iter()
+        .cloned()
+        .filter(|r| build_cookbook.contains_key(*r))
+        .collect();
+    if refs.is_empty() {
+        return;
+    }
+    if !build_cookbook.contains_key("git") {
+        errors.push(error(
+            &child_path("$.build_cookbook", refs[0]),
+            format!("'{}' can only be used with a git build_cookbook", refs[0]),
+        ));
+    } else if refs.len() > 1 {
+        errors.push(error(
+            "$.build_cookbook",
+            format!("use only one of branch, tag or revision, found {}", refs.join(", ")),
+        ));
+    }
+}
+
+// The tarball of a build_cookbook can only be downloaded over http, an
+// https url gets a clearer message than the pattern of the schema
+fn check_build_cookbook_url(json: &SerdeJson, errors: &mut Vec<SchemaError>) {
+    let url = json.get("build_cookbook").and_then(|b| b.get("url")).and_then(|u| u.as_str());
+    if !url.map(|u| u.starts_with("https://")).unwrap_or(false) {
+        return;
+    }
+    let path = "$.build_cookbook.url";
+    errors.retain(|e| e.path != path);
+    errors.push(error(
+        path,
+        String::from(
+            "https urls are not supported yet, serve the tarball over http or use a git \
+             build_cookbook",
+        ),
+    ));
+}
+
+fn error(path: &str, message: String) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        line: None,
+        column: None,
+        message: message,
+    }
+}
+
+fn unknown_key(key: &str, known: &[&str]) -> String {
+    match suggest(key, known) {
+        Some(s) => format!("unknown key '{}', did you mean '{}'?", key, s),
+        None => format!("unknown key '{}', expected one of: {}", key, known.join(", ")),
+    }
+}
+
+fn suggest<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|k| (*k, distance(key, k)))
+        .filter(|&(_, d)| d <= 2)
+        .min_by_key(|&(_, d)| d)
+        .map(|(k, _)| k)
+}
+
+fn type_name(value: &SerdeJson) -> &'static str {
+    match *value {
+        SerdeJson::Null => "null",
+        SerdeJson::Bool(_) => "boolean",
+        SerdeJson::Number(_) => "number",
+        SerdeJson::String(_) => "string",
+        SerdeJson::Array(_) => "array",
+        SerdeJson::Object(_) => "object",
+    }
+}
+
+/// The JSON path of a key inside an object, i.e. `$.build_cookbook.name`
+pub fn child_path(path: &str, key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{:?}]", path, key)
+    }
+}
+
+fn locate_errors(content: &str, mut errors: Vec<SchemaError>) -> Vec<SchemaError> {
+    let positions = locate(content);
+    for e in errors.iter_mut() {
+        if let Some(&(line, column)) = positions.get(&e.path) {
+            e.line = Some(line);
+            e.column = Some(column);
+        }
+    }
+    errors
+}
+
+/// Map every JSON path of the document to the line and column (starting
+/// at 1) where it is defined, for the members of an object that is the
+/// position of the key.
+///
+/// The content must be valid JSON, this is not a validating parser.
+pub fn locate(content: &str) -> HashMap<String, (usize, usize)> {
+    let mut locator = Locator {
+        chars: content.chars().collect(),
+        index: 0,
+        line: 1,
+        column: 1,
+        positions: HashMap::new(),
+    };
+    locator.value("$");
+    locator.positions
+}
+
+struct Locator {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+    positions: HashMap<String, (usize, usize)>,
+}
+
+impl Locator {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).cloned()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.index += 1;
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            self.bump();
+        }
+    }
+
+    fn record(&mut self, path: &str, position: (usize, usize)) {
+        self.positions
+            .entry(path.to_string())
+            .or_insert(position);
+    }
+
+    fn value(&mut self, path: &str) {
+        self.skip_whitespace();
+        let position = (self.line, self.column);
+        self.record(path, position);
+        match self.peek() {
+            Some('{') => self.object(path),
+            Some('[') => self.array(path),
+            Some('"') => {
+                self.string();
+            }
+            Some(_) => self.literal(),
+            None => {}
+        }
+    }
+
+    fn object(&mut self, path: &str) {
+        self.bump();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return;
+                }
+                Some('"') => {
+                    let position = (self.line, self.column);
+                    let key = self.string();
+                    let child = child_path(path, &key);
+                    self.record(&child, position);
+                    self.skip_whitespace();
+                    if self.peek() == Some(':') {
+                        self.bump();
+                    }
+                    self.value(&child);
+                }
+                Some(_) => self.bump(),
+                None => return,
+            }
+        }
+    }
+
+    fn array(&mut self, path: &str) {
+        self.bump();
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(']') => {
+                    self.bump();
+                    return;
+                }
+                Some(',') => {
+                    self.bump();
+                    index += 1;
+                }
+                Some(_) => self.value(&format!("{}[{}]", path, index)),
+                None => return,
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let mut s = String::new();
+        self.bump();
+        while let Some(c) = self.peek() {
+            self.bump();
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = self.peek() {
+                        self.bump();
+                        s.push(match escaped {
+                            'n' => '\n',
+                            't' => '\t',
+                            'r' => '\r',
+                            other => other,
+                        });
+                    }
+                }
+                _ => s.push(c),
+            }
+        }
+        s
+    }
+
+    fn literal(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ',' || c == ']' || c == '}' || c.is_whitespace() {
+                break;
+            }
+            self.bump();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_schemas_are_valid_json() {
+        assert!(serde_json::from_str::<SerdeJson>(V1_SCHEMA).is_ok());
+        assert!(serde_json::from_str::<SerdeJson>(V2_SCHEMA).is_ok());
+    }
+
+    #[test]
+    fn valid_configs() {
+        let v2 = r#"{
+  "version": "2",
+  "build_cookbook": { "name": "build", "git": "https://github.com/a/b", "branch": "main" },
+  "skip_phases": ["smoke"],
+  "job_dispatch": { "version": "v2" },
+  "paths": {
+    "unit": ["src/**"],
+    "projects": { "web": { "paths": ["web/**"], "phases": ["lint"] } }
+  },
+  "delivery-truck": { "publish": { "chef_server": true } }
+}"#;
+        assert_eq!(Vec::<SchemaError>::new(), validate(v2));
+
+        let v1 = r#"{ "version": "1", "build_cookbook": "./.delivery/build_cookbook" }"#;
+        assert_eq!(Vec::<SchemaError>::new(), validate(v1));
+    }
+
+    #[test]
+    fn typos_and_types_with_locations() {
+        let content = r#"{
+  "version": "2",
+  "build_cookbook": {
+    "name": "build",
+    "path": ".delivery/build_cookbook",
+    "brnach": "master"
+  },
+  "skip_phase": ["smoke"],
+  "dependencies": "projectA"
+}"#;
+        let errors: Vec<String> = validate(content).iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            vec![
+                "6:5: $.build_cookbook.brnach: unknown key 'brnach', did you mean 'branch'?",
+                "9:3: $.dependencies: expected array, found string",
+                "8:3: $.skip_phase: unknown key 'skip_phase', did you mean 'skip_phases'?",
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn build_cookbook_sources() {
+        let conflict = r#"{
+  "version": "2",
+  "build_cookbook": { "name": "build", "path": "cb", "git": "https://x" }
+}"#;
+        let errors = validate(conflict);
+        assert_eq!(1, errors.len());
+        assert_eq!(Some(3), errors[0].line);
+        assert_eq!(
+            "the build_cookbook has more than one source (path, git), use only one of: \
+             path, git, supermarket, enterprise, server, url",
+            errors[0].message
+        );
+
+        let none = r#"{ "version": "2", "build_cookbook": { "name": "build" } }"#;
+        assert!(validate(none)[0].message.contains("needs a source"));
+    }
+
+    #[test]
+    fn build_cookbook_git_refs() {
+        let pinned = r#"{ "version": "2", "build_cookbook": {
+            "name": "build", "git": "https://x", "revision": "5c0e8e4"
+        } }"#;
+        assert!(validate(pinned).is_empty());
+
+        let both = r#"{ "version": "2", "build_cookbook": {
+            "name": "build", "git": "https://x", "tag": "v1.0.0", "revision": "5c0e8e4"
+        } }"#;
+        assert_eq!(
+            "use only one of branch, tag or revision, found tag, revision",
+            validate(both)[0].message
+        );
+
+        let no_git = r#"{ "version": "2", "build_cookbook": {
+            "name": "build", "path": "cb", "tag": "v1.0.0"
+        } }"#;
+        let errors = validate(no_git);
+        assert_eq!("$.build_cookbook.tag", errors[0].path);
+        assert_eq!(Some(2), errors[0].line);
+    }
+
+    #[test]
+    fn build_cookbook_from_url() {
+        let sha = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let valid = format!(
+            r#"{{ "version": "2", "build_cookbook": {{
+                "name": "build", "url": "http://artifacts/build-1.0.0.tgz", "sha256": "{}"
+            }} }}"#,
+            sha
+        );
+        assert!(validate(&valid).is_empty());
+
+        let no_sha = r#"{ "version": "2", "build_cookbook": {
+            "name": "build", "url": "http://artifacts/build-1.0.0.tgz"
+        } }"#;
+        let errors = validate(no_sha);
+        assert_eq!(1, errors.len());
+        assert_eq!(
+            "a build_cookbook from a url needs the 'sha256' of the tarball",
+            errors[0].message
+        );
+
+        let bad = r#"{ "version": "2", "build_cookbook": {
+            "name": "build", "url": "ftp://artifacts/build.tgz", "sha256": "abc"
+        } }"#;
+        let errors = validate(bad);
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(vec!["$.build_cookbook.sha256", "$.build_cookbook.url"], paths);
+
+        let https = format!(
+            r#"{{ "version": "2", "build_cookbook": {{
+                "name": "build", "url": "https://artifacts/build-1.0.0.tgz", "sha256": "{}"
+            }} }}"#,
+            sha
+        );
+        let errors = validate(&https);
+        assert_eq!(1, errors.len());
+        assert_eq!("$.build_cookbook.url", errors[0].path);
+        assert_eq!(
+            "https urls are not supported yet, serve the tarball over http or use a git \
+             build_cookbook",
+            errors[0].message
+        );
+    }
+
+    #[test]
+    fn extends_and_merged_configs() {
+        let content = r#"{
+  "extends": 1,
+  "skip_phase": ["smoke"]
+}"#;
+        let messages: Vec<String> = validate(content).iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            vec![
+                "1:1: $: missing required key 'version'",
+                "1:1: $: missing required key 'build_cookbook'",
+                "2:3: $.extends: expected string or object, found number",
+                "3:3: $.skip_phase: unknown key 'skip_phase', did you mean 'skip_phases'?",
+            ],
+            messages
+        );
+
+        // The keys of the base are not in the local file, so they have no location
+        let content = r#"{ "extends": "../base.json", "skip_phases": ["smok"] }"#;
+        let merged: SerdeJson = serde_json::from_str(
+            r#"{
+  "version": "2",
+  "build_cookbook": { "name": "build", "path": ".delivery/build" },
+  "skip_phases": ["smok"],
+  "build_nodes": { "unit": "linux" }
+}"#,
+        ).unwrap();
+        let errors = validate_merged(content, &merged);
+        assert_eq!(2, errors.len());
+        assert_eq!("$.build_nodes.unit", errors[0].path);
+        assert_eq!(None, errors[0].line);
+        assert_eq!("$.skip_phases[0]", errors[1].path);
+        assert_eq!(Some(1), errors[1].line);
+    }
+
+    #[test]
+    fn invalid_json_and_version() {
+        let errors = validate("{\n  \"version\": \"2\",\n}");
+        assert_eq!(1, errors.len());
+        assert_eq!(Some(3), errors[0].line);
+
+        let errors = validate(r#"{ "version": 3 }"#);
+        assert_eq!("version must be \"1\" or \"2\", found 3", errors[0].message);
+    }
+
+    #[test]
+    fn locate_paths() {
+        let positions = locate("{\n  \"a\": [1,\n    {\"b c\": true}]\n}");
+        assert_eq!(Some(&(1, 1)), positions.get("$"));
+        assert_eq!(Some(&(2, 3)), positions.get("$.a"));
+        assert_eq!(Some(&(2, 9)), positions.get("$.a[0]"));
+        assert_eq!(Some(&(3, 5)), positions.get("$.a[1]"));
+        assert_eq!(Some(&(3, 6)), positions.get("$.a[1][\"b c\"]"));
+    }
+}
@@ -30,8 +30,11 @@ use types::ExitCode;
 pub enum Kind {
     ChangeNotFound,
     PhaseNotFound,
-    PhaseFailed(ExitCode),
+    PhaseFailed(PhaseFailure),
+    PhaseCancelled,
+    Interrupted,
     LocalPhasesNotFound,
+    InvalidLocalPhase,
     AuthenticationFailed,
     ForbiddenRequest,
     InternalServerError,
@@ -54,6 +57,7 @@ pub enum Kind {
     UnknownProjectType,
     ProjectNotFound(String),
     UserNotFound(String),
+    ProfileNotFound(String),
     GitFailed,
     UnauthorizedAction,
     MissingSshPubKey,
@@ -61,9 +65,14 @@ pub enum Kind {
     GitSetupFailed,
     ConfigParse,
     DeliveryConfigParse,
+    DeliveryConfigExtends,
     MissingConfig,
     MissingConfigFile,
     ConfigValidation,
+    InvalidConfig,
+    InvalidAlias,
+    CredentialCommandFailed,
+    InvalidDeliveryConfig,
     IoError,
     JsonError,
     JsonEncode,
@@ -79,12 +88,16 @@ pub enum Kind {
     RemoveFailed,
     CloneFailed,
     TarFailed,
+    DownloadFailed,
+    ChecksumMismatch,
+    BuildCookbookRevisionMismatch,
     MissingBuildCookbookField,
     ChefServerFailed,
     ChefdkGenerateFailed,
     ChownFailed,
     ChefFailed,
     ChmodFailed,
+    TouchFailed,
     UnsupportedHttpMethod,
     HttpError(HttpError),
     UnsupportedProtocol,
@@ -104,6 +117,42 @@ pub enum Kind {
     BranchNotFoundOnDeliveryRemote,
 }
 
+/// How a local phase failed, a phase can be retried and can time out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseFailure {
+    pub exit_code: ExitCode,
+    /// The attempt that failed, starting at 1
+    pub attempt: u32,
+    pub attempts: u32,
+    pub timed_out: bool,
+}
+
+impl PhaseFailure {
+    /// The only attempt of a phase exited with `exit_code`
+    pub fn new(exit_code: ExitCode) -> PhaseFailure {
+        PhaseFailure {
+            exit_code: exit_code,
+            attempt: 1,
+            attempts: 1,
+            timed_out: false,
+        }
+    }
+}
+
+impl fmt::Display for PhaseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.timed_out {
+            try!(write!(f, "Phase timed out"));
+        } else {
+            try!(write!(f, "Phase failed with exit code ({})", self.exit_code));
+        }
+        if self.attempts > 1 {
+            try!(write!(f, " on attempt {} of {}", self.attempt, self.attempts));
+        }
+        write!(f, "!")
+    }
+}
+
 #[derive(Debug)]
 pub struct DeliveryError {
     pub kind: Kind,
@@ -142,7 +191,10 @@ impl error::Error for DeliveryError {
             Kind::ChangeNotFound => "GET failed for specific change",
             Kind::PhaseNotFound => "Phase not implemented",
             Kind::PhaseFailed(_) => "Phase failed!",
+            Kind::PhaseCancelled => "The phase was cancelled",
+            Kind::Interrupted => "Interrupted, the processes that were running were stopped",
             Kind::LocalPhasesNotFound => "LocalPhases tag not found",
+            Kind::InvalidLocalPhase => "A local phase of your .delivery/project.toml is invalid",
             Kind::NoMatchingCommand => "No command matches your arguments - likely unimplemented feature",
             Kind::ClapArgAliasOverlap => "There was an argument/alias overlap.",
             Kind::NotOnABranch => "You must be on a branch",
@@ -166,11 +218,17 @@ impl error::Error for DeliveryError {
             Kind::UnknownProjectType => "Unknown Project Type",
             Kind::ProjectNotFound(_) => "Project Not Found!",
             Kind::UserNotFound(_) => "User Not Found!",
+            Kind::ProfileNotFound(_) => "Profile Not Found!",
             Kind::ConfigParse => "Failed to parse the cli config file",
             Kind::DeliveryConfigParse => "Unable to parse the config.json file.",
+            Kind::DeliveryConfigExtends => "Unable to load the config that your .delivery/config.json extends",
             Kind::MissingConfig => "A configuration value is missing",
             Kind::MissingConfigFile => "Could not find the configuration file.",
             Kind::ConfigValidation => "A required option is missing - use the command line options or 'delivery setup'",
+            Kind::InvalidConfig => "Your cli.toml configuration is invalid",
+            Kind::InvalidAlias => "Unable to expand the alias from your cli.toml",
+            Kind::InvalidDeliveryConfig => "The .delivery/config.json is invalid",
+            Kind::CredentialCommandFailed => "Unable to get the credentials from the command in your cli.toml",
             Kind::IoError => "An I/O Error occurred",
             Kind::JsonError => "A JSON Parser error occurred",
             Kind::JsonEncode => "A JSON Encoding error occurred",
@@ -183,6 +241,9 @@ impl error::Error for DeliveryError {
             Kind::CopyFailed => "Failed to copy files",
             Kind::SupermarketFailed => "Failed to download a cookbook from the supermarket",
             Kind::TarFailed => "Cannot untar a file",
+            Kind::DownloadFailed => "Failed to download the build_cookbook",
+            Kind::ChecksumMismatch => "The sha256 checksum of the build_cookbook doesn't match",
+            Kind::BuildCookbookRevisionMismatch => "The build_cookbook revision doesn't match the tag or revision in your config.json",
             Kind::MoveFailed => "Cannot move a file",
             Kind::RemoveFailed => "Cannot remove a file or directory",
             Kind::CloneFailed => "Unable to clone project.",
@@ -192,6 +253,7 @@ impl error::Error for DeliveryError {
             Kind::ChownFailed => "Cannot set ownership to the dbuild user and group",
             Kind::ChefFailed => "Chef Client failed",
             Kind::ChmodFailed => "Cannot set permissions",
+            Kind::TouchFailed => "Cannot update the modification time",
             Kind::UnsupportedHttpMethod => "Unsupported HTTP method",
             Kind::UnsupportedProtocol => "Unsupported protocol",
             Kind::HttpError(_) => "An HTTP Error occurred",
@@ -231,9 +293,12 @@ impl error::Error for DeliveryError {
 impl fmt::Display for DeliveryError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let msg = match self.kind {
-            Kind::PhaseFailed(ref e) => format!("Phase failed with exit code ({})!", e),
+            Kind::PhaseFailed(ref e) => e.to_string(),
             Kind::ProjectNotFound(ref e) => format!("The project '{}' was not found.", e),
             Kind::UserNotFound(ref e) => format!("The user '{}' was not found.", e),
+            Kind::ProfileNotFound(ref e) => {
+                format!("The profile '{}' was not found in your cli.toml.", e)
+            }
             _ => self.description().to_string(),
         };
         write!(f, "{}", msg)
@@ -329,4 +394,21 @@ mod tests {
             assert_enum!(e.kind, super::JsonError);
         }
     }
+
+    mod phase_failure {
+        use super::super::{Kind, PhaseFailure};
+
+        #[test]
+        fn reports_the_attempt_and_the_timeout() {
+            let e = super::DeliveryError::throw(Kind::PhaseFailed(PhaseFailure::new(2)), None);
+            assert_eq!("Phase failed with exit code (2)!", e.to_string());
+            let failure = PhaseFailure {
+                exit_code: 1,
+                attempt: 3,
+                attempts: 3,
+                timed_out: true,
+            };
+            assert_eq!("Phase timed out on attempt 3 of 3!", failure.to_string());
+        }
+    }
 }
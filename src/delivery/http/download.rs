@@ -0,0 +1,103 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use errors::{DeliveryError, Kind};
+use hyper;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use types::DeliveryResult;
+
+/// Download the content of a `url` into the file `dest`, any response
+/// that is not a `2xx` is an error.
+///
+/// Our hyper client has no TLS connector, so only `http://` urls can be
+/// downloaded, an `https://` one is rejected with an explicit error
+/// instead of hyper's "Invalid scheme for Http".
+pub fn to_file<P>(url: &str, dest: P) -> DeliveryResult<()>
+where
+    P: AsRef<Path>,
+{
+    if url.to_lowercase().starts_with("https://") {
+        return Err(DeliveryError {
+            kind: Kind::DownloadFailed,
+            detail: Some(format!(
+                "GET {}: https urls are not supported yet, serve the file over \
+                 http or use a path or a git source instead",
+                url
+            )),
+        });
+    }
+    debug!("Downloading {} to {}", url, dest.as_ref().display());
+    let client = hyper::Client::new();
+    let mut response = client.get(url).send().map_err(|e| DeliveryError {
+        kind: Kind::DownloadFailed,
+        detail: Some(format!("GET {}: {}", url, e)),
+    })?;
+    if !response.status.is_success() {
+        return Err(DeliveryError {
+            kind: Kind::DownloadFailed,
+            detail: Some(format!("GET {} returned {}", url, response.status)),
+        });
+    }
+    let mut file = File::create(&dest)?;
+    io::copy(&mut response, &mut file)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{mock, SERVER_ADDRESS};
+    use tempdir::TempDir;
+    use utils::read_file;
+
+    #[test]
+    fn download_to_file() {
+        let _m = mock("GET", "/build_cookbook-1.0.0.tgz")
+            .with_status(200)
+            .with_body("tarball")
+            .create();
+        let tempdir = TempDir::new("download").unwrap();
+        let dest = tempdir.path().join("build_cookbook.tgz");
+        let url = format!("http://{}/build_cookbook-1.0.0.tgz", SERVER_ADDRESS);
+        to_file(&url, &dest).unwrap();
+        assert_eq!("tarball", read_file(&dest).unwrap());
+    }
+
+    #[test]
+    fn download_not_found() {
+        let _m = mock("GET", "/missing.tgz").with_status(404).create();
+        let tempdir = TempDir::new("download").unwrap();
+        let url = format!("http://{}/missing.tgz", SERVER_ADDRESS);
+        let err = to_file(&url, tempdir.path().join("missing.tgz")).unwrap_err();
+        match err.kind {
+            Kind::DownloadFailed => {}
+            _ => panic!("expected a DownloadFailed error, got {:?}", err.kind),
+        }
+        assert!(err.detail.unwrap().contains("404"));
+    }
+
+    #[test]
+    fn download_over_https_is_not_supported() {
+        let tempdir = TempDir::new("download").unwrap();
+        let url = "https://artifacts.example.com/build_cookbook.tgz";
+        let err = to_file(url, tempdir.path().join("build_cookbook.tgz")).unwrap_err();
+        assert_enum!(err.kind, Kind::DownloadFailed);
+        assert!(err.detail.unwrap().contains("https urls are not supported"));
+    }
+}
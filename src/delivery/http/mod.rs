@@ -32,13 +32,15 @@ use std::env;
 use std::fmt;
 use std::io::prelude::*;
 use std::path::PathBuf;
-use token::TokenStore;
+use token::{credential, TokenStore};
 use types::DeliveryResult;
 use utils::say::sayln;
 
 pub mod change;
+pub mod download;
 mod headers;
 pub mod saml;
+pub mod status;
 pub mod token;
 pub mod user;
 
@@ -317,10 +319,15 @@ impl APIClient {
             }
             None => req,
         };
-        debug!(
-            "Request: {:?} Path: {:?} Payload: {:?}",
-            http_method, path, payload
-        );
+        // The payload of a token request has the password of the user
+        if path.ends_with("/get-token") {
+            debug!("Request: {:?} Path: {:?} Payload: <redacted>", http_method, path);
+        } else {
+            debug!(
+                "Request: {:?} Path: {:?} Payload: {:?}",
+                http_method, path, payload
+            );
+        }
         if payload.is_empty() {
             req.send()
         } else {
@@ -509,12 +516,18 @@ impl APIClient {
     }
 }
 
-#[derive(Debug)]
 pub struct APIAuth {
     user: String,
     token: String,
 }
 
+// Never display the token, it would end up in the debug logs
+impl fmt::Debug for APIAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "APIAuth {{ user: {:?}, token: \"<redacted>\" }}", self.user)
+    }
+}
+
 impl APIAuth {
     pub fn from_env() -> APIAuth {
         let token = env::var("TOKEN").ok().expect("env missing TOKEN");
@@ -531,6 +544,13 @@ impl APIAuth {
     /// Reads API tokens from `$HOME/.delivery/api-tokens`.
     /// Lookup for the stored token, if it does not exist request it.
     pub fn from_config(config: &Config) -> DeliveryResult<APIAuth> {
+        // The `token_command` takes precedence over the tokens on disk
+        if let Some(token) = try!(credential::token(&config)) {
+            return Ok(APIAuth {
+                user: try!(config.user()),
+                token: token,
+            });
+        }
         if !try!(http::token::verify(&config)) {
             sayln("red", "Token expired");
             return APIAuth::from_token_request(config);
@@ -581,7 +601,7 @@ impl APIAuth {
         let interactive = !config.non_interactive.unwrap_or(false);
         if interactive {
             let token = try!(TokenStore::request_token(&config));
-            debug!("APIAuth from_token_request: {:?}@{:?}", user, token);
+            debug!("APIAuth from_token_request: {:?}", user);
             Ok(APIAuth {
                 user: user.clone(),
                 token: token.clone(),
@@ -623,6 +643,17 @@ mod tests {
     use token::TokenStore;
     use utils::path_join_many::PathJoinMany;
 
+    #[test]
+    fn api_auth_debug_redacts_the_token() {
+        let auth = APIAuth {
+            user: String::from("bob"),
+            token: String::from("s3cr3t-token"),
+        };
+        let debug = format!("{:?}", auth);
+        assert!(debug.contains("bob"));
+        assert!(!debug.contains("s3cr3t-token"));
+    }
+
     #[test]
     fn api_auth() {
         fake_test_env();
@@ -0,0 +1,80 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use config::Config;
+use http::APIClient;
+use json::server_status::ServerStatus;
+use serde_json;
+use types::DeliveryResult;
+
+/// Fetch the status of the Automate server, this endpoint doesn't
+/// require authentication so it is useful to verify that the server
+/// is reachable and to detect if it runs in FIPS mode.
+pub fn get(config: &Config) -> DeliveryResult<ServerStatus> {
+    let client = try!(APIClient::from_config_with_basic_routing(config));
+    let mut result = try!(client.get("api/_status"));
+    let json_string = try!(APIClient::extract_pretty_json(&mut result));
+    parse_status(&json_string)
+}
+
+pub fn parse_status(response: &str) -> DeliveryResult<ServerStatus> {
+    Ok(serde_json::from_str::<ServerStatus>(&normalize_status(response))?)
+}
+
+/// Backwards compat: A few versions of the server shipped with a
+/// key with a space in it.
+pub fn normalize_status(response: &str) -> String {
+    response.replace("configuration mode", "configuration_mode")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_status_with_fips_mode() {
+        let response = r#"{
+            "configuration mode": "standalone",
+            "status": "pong",
+            "upstreams": [{
+                "lsyncd": {"status": "not_running"},
+                "postgres": {"status": "pong"},
+                "rabbitmq": {"status": "pong"}
+            }],
+            "fips_mode": true
+        }"#;
+        let status = parse_status(response).unwrap();
+        assert_eq!("standalone", status.configuration_mode);
+        assert_eq!("pong", status.status);
+        assert_eq!(Some(true), status.fips_mode);
+    }
+
+    #[test]
+    fn parse_status_without_fips_mode() {
+        let response = r#"{
+            "configuration_mode": "standalone",
+            "status": "pong",
+            "upstreams": [{
+                "lsyncd": {"status": "not_running"},
+                "postgres": {"status": "pong"},
+                "rabbitmq": {"status": "pong"}
+            }]
+        }"#;
+        let status = parse_status(response).unwrap();
+        assert_eq!(None, status.fips_mode);
+    }
+}
@@ -0,0 +1,265 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+//! Build Cookbook Cache
+//!
+//! Running `berks vendor` on every job is slow, so the vendored cookbooks
+//! are kept under the workspace path in a content-addressed cache. The key
+//! of an entry is the digest of the build_cookbook source (from the
+//! config.json), its revision and the content of its `Berksfile.lock`,
+//! or of its `Berksfile` if it has no lock; if any of them changes a new
+//! entry is created. Restoring an entry marks it as used, and the entries
+//! that weren't stored nor used in the last `MAX_AGE` are pruned, so the
+//! cache doesn't grow forever. Pruning only happens on the store path: a
+//! job that vendors its cookbooks (including with `--no-cache`) prunes
+//! the cache, a job that restores them from the cache doesn't.
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempdir::TempDir;
+use types::DeliveryResult;
+use utils;
+use utils::path_ext::is_dir;
+
+/// How long an entry is kept in the cache, 30 days
+pub const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+pub struct BuildCookbookCache {
+    pub root: PathBuf,
+}
+
+impl BuildCookbookCache {
+    /// The cache that lives in the workspace path, shared by every job
+    pub fn new(ws_path: &Path) -> BuildCookbookCache {
+        BuildCookbookCache {
+            root: ws_path.join("build_cookbook_cache"),
+        }
+    }
+
+    /// The key of an entry of the cache.
+    ///
+    /// The `revision` identifies the content of the build_cookbook (a
+    /// git SHA or a digest of its files) and `berksfile` is the content
+    /// of its `Berksfile.lock`, or of its `Berksfile` without a lock, if
+    /// the build_cookbook has one.
+    pub fn key(
+        source: &HashMap<String, String>,
+        revision: &str,
+        berksfile: Option<&str>,
+    ) -> String {
+        // Sort the source so the key doesn't depend on the HashMap order
+        let sorted: BTreeMap<&String, &String> = source.iter().collect();
+        let mut sha = Sha256::new();
+        for (k, v) in sorted {
+            sha.input_str(&format!("{}={}\n", k, v));
+        }
+        sha.input_str(&format!("revision={}\n", revision));
+        sha.input_str(berksfile.unwrap_or(""));
+        sha.result_str()
+    }
+
+    pub fn entry(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+
+    /// Copy the cookbooks of an entry into `dest`, returns `false` if
+    /// there is no entry for that key. The entry is marked as used so
+    /// that it isn't pruned while jobs keep restoring it.
+    pub fn restore(&self, key: &str, dest: &Path) -> DeliveryResult<bool> {
+        let entry = self.entry(key);
+        if !is_dir(&entry) {
+            return Ok(false);
+        }
+        utils::touch(&entry)?;
+        debug!("Restoring {} from the cache {}", dest.display(), entry.display());
+        utils::remove_recursive(dest)?;
+        utils::copy_recursive(&entry, dest)?;
+        Ok(true)
+    }
+
+    /// Save a copy of the cookbooks in `src` as the entry of the key,
+    /// replacing the entry if it already exists.
+    pub fn store(&self, key: &str, src: &Path) -> DeliveryResult<()> {
+        let entry = self.entry(key);
+        // Copy to a temporary directory of our own first so that an
+        // interrupted copy never leaves a partial entry behind, and the
+        // jobs that store the same key at the same time don't collide.
+        utils::mkdir_recursive(&self.root)?;
+        let tmp = TempDir::new_in(&self.root, "tmp")?;
+        let copy = tmp.path().join(key);
+        utils::copy_recursive(src, &copy)?;
+        // The previous entry goes away along with the temporary directory
+        let _ = fs::rename(&entry, tmp.path().join("previous"));
+        if let Err(e) = fs::rename(&copy, &entry) {
+            // Another job stored the key in the meantime, and the content
+            // of an entry only depends on its key.
+            if !is_dir(&entry) {
+                return Err(e.into());
+            }
+        }
+        debug!("Stored {} in the cache {}", src.display(), entry.display());
+        self.prune(MAX_AGE)
+    }
+
+    /// Remove the entries, and the leftovers of interrupted jobs, that
+    /// were stored or restored more than `max_age` ago.
+    pub fn prune(&self, max_age: Duration) -> DeliveryResult<()> {
+        for dir_entry in fs::read_dir(&self.root)? {
+            let path = dir_entry?.path();
+            let age = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.elapsed().ok());
+            if age.map_or(false, |a| a > max_age) {
+                debug!("Pruning {} from the cache", path.display());
+                utils::remove_recursive(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A digest of the files of a directory, their relative paths and their
+/// content, used as the revision of a build_cookbook that doesn't come
+/// from git. The `.git` directory is ignored.
+pub fn tree_digest(dir: &Path) -> DeliveryResult<String> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    files.sort();
+    let mut sha = Sha256::new();
+    for relative in files {
+        sha.input_str(&format!("{}\n", relative.display()));
+        sha.input_str(&utils::sha256_file(dir.join(&relative))?);
+    }
+    Ok(sha.result_str())
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> DeliveryResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.file_name().map(|n| n == ".git").unwrap_or(false) {
+            continue;
+        }
+        if is_dir(&path) {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::prelude::*;
+    use tempdir::TempDir;
+    use utils::path_ext::is_file;
+
+    fn source(git: &str) -> HashMap<String, String> {
+        let mut source = HashMap::new();
+        source.insert(String::from("name"), String::from("build_cookbook"));
+        source.insert(String::from("git"), String::from(git));
+        source
+    }
+
+    #[test]
+    fn key_changes_with_every_input() {
+        let key = BuildCookbookCache::key(&source("a"), "sha1", Some("lock"));
+        assert_eq!(key, BuildCookbookCache::key(&source("a"), "sha1", Some("lock")));
+        assert!(key != BuildCookbookCache::key(&source("b"), "sha1", Some("lock")));
+        assert!(key != BuildCookbookCache::key(&source("a"), "sha2", Some("lock")));
+        assert!(key != BuildCookbookCache::key(&source("a"), "sha1", Some("lock2")));
+        assert!(key != BuildCookbookCache::key(&source("a"), "sha1", None));
+    }
+
+    #[test]
+    fn store_and_restore() {
+        let tempdir = TempDir::new("build_cookbook_cache").unwrap();
+        let cache = BuildCookbookCache::new(tempdir.path());
+        let cookbooks = tempdir.path().join("cookbooks");
+        utils::mkdir_recursive(&cookbooks.join("build_cookbook")).unwrap();
+        File::create(cookbooks.join("build_cookbook/metadata.rb")).unwrap();
+
+        let dest = tempdir.path().join("restored");
+        assert_eq!(false, cache.restore("key", &dest).unwrap());
+        cache.store("key", &cookbooks).unwrap();
+        assert_eq!(true, cache.restore("key", &dest).unwrap());
+        assert!(is_file(&dest.join("build_cookbook/metadata.rb")));
+
+        // Storing the key again replaces the entry
+        File::create(cookbooks.join("build_cookbook/README.md")).unwrap();
+        cache.store("key", &cookbooks).unwrap();
+        assert_eq!(true, cache.restore("key", &dest).unwrap());
+        assert!(is_file(&dest.join("build_cookbook/README.md")));
+        assert_eq!(1, fs::read_dir(&cache.root).unwrap().count());
+    }
+
+    #[test]
+    fn prune_old_entries() {
+        let tempdir = TempDir::new("build_cookbook_cache").unwrap();
+        let cache = BuildCookbookCache::new(tempdir.path());
+        let cookbooks = tempdir.path().join("cookbooks");
+        utils::mkdir_recursive(&cookbooks.join("build_cookbook")).unwrap();
+
+        cache.store("key", &cookbooks).unwrap();
+        cache.prune(MAX_AGE).unwrap();
+        assert!(is_dir(&cache.entry("key")));
+        ::std::thread::sleep(Duration::from_millis(10));
+        cache.prune(Duration::from_millis(1)).unwrap();
+        assert!(!is_dir(&cache.entry("key")));
+    }
+
+    #[test]
+    fn restore_marks_the_entry_as_used() {
+        let tempdir = TempDir::new("build_cookbook_cache").unwrap();
+        let cache = BuildCookbookCache::new(tempdir.path());
+        let cookbooks = tempdir.path().join("cookbooks");
+        utils::mkdir_recursive(&cookbooks.join("build_cookbook")).unwrap();
+
+        cache.store("key", &cookbooks).unwrap();
+        ::std::thread::sleep(Duration::from_millis(1100));
+        cache.restore("key", &tempdir.path().join("restored")).unwrap();
+        cache.prune(Duration::from_secs(1)).unwrap();
+        assert!(is_dir(&cache.entry("key")));
+    }
+
+    #[test]
+    fn tree_digest_of_a_directory() {
+        let tempdir = TempDir::new("tree_digest").unwrap();
+        let dir = tempdir.path();
+        utils::mkdir_recursive(&dir.join("recipes")).unwrap();
+        utils::mkdir_recursive(&dir.join(".git")).unwrap();
+        File::create(dir.join("recipes/default.rb")).unwrap();
+        let digest = tree_digest(dir).unwrap();
+
+        // Changes inside .git don't matter
+        File::create(dir.join(".git/HEAD")).unwrap();
+        assert_eq!(digest, tree_digest(dir).unwrap());
+
+        File::create(dir.join("recipes/default.rb"))
+            .unwrap()
+            .write_all(b"log 'hi'")
+            .unwrap();
+        assert!(digest != tree_digest(dir).unwrap());
+    }
+}
@@ -15,6 +15,7 @@
 // limitations under the License.
 //
 
+use delivery_config::paths::PathsDecision;
 use job::change::{BuilderCompat, Change};
 use serde_json::Value as SerdeJson;
 
@@ -23,6 +24,10 @@ pub struct Top {
     pub workspace_path: String,
     pub workspace: WorkspaceCompat,
     pub change: Change,
+    pub build_cookbook: BuildCookbookCompat,
+    // The files changed by the change and the phases that they trigger,
+    // `None` when the config.json has no `paths`
+    pub paths: Option<PathsDecision>,
     // Use a generic Json format
     //
     // There are projects that have custom attributes inside the
@@ -48,6 +53,14 @@ pub struct DNA {
     pub delivery_builder: BuilderCompat,
 }
 
+// The build_cookbook that runs the job, the revision is the SHA that
+// was checked out when it comes from a git repository.
+#[derive(Serialize)]
+pub struct BuildCookbookCompat {
+    pub name: String,
+    pub revision: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct WorkspaceCompat {
     pub root: String,
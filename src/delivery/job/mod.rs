@@ -15,6 +15,7 @@
 // limitations under the License.
 //
 
+pub mod cache;
 pub mod change;
 pub mod dna;
 pub mod workspace;
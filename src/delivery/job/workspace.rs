@@ -16,22 +16,28 @@
 //
 
 use config::Config;
+use delivery_config::paths::PathsDecision;
 use delivery_config::{BuildCookbookLocation, DeliveryConfig};
 use errors::{DeliveryError, Kind};
 use git;
+use http;
+use job::cache::{tree_digest, BuildCookbookCache};
 use job::change::{BuilderCompat, Change};
-use job::dna::{Top, WorkspaceCompat, DNA};
+use job::dna::{BuildCookbookCompat, Top, WorkspaceCompat, DNA};
 use serde_json;
 use std::error;
+use std::fs;
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use types::DeliveryResult;
 use utils;
+use utils::archive::unpack_tar_gz;
 use utils::path_ext::{is_dir, is_file};
 use utils::path_join_many::PathJoinMany;
 use utils::path_to_string;
+use utils::say::sayln;
 
 pub struct Workspace {
     pub root: PathBuf,
@@ -111,18 +117,42 @@ impl Workspace {
         utils::copy_recursive(path, &self.chef.join("build_cookbook"))
     }
 
-    fn setup_build_cookbook_from_git(&self, config: &DeliveryConfig) -> DeliveryResult<()> {
+    // Clone the build_cookbook and checkout the `revision`, `tag` or
+    // `branch` (master by default) of the config, returns the SHA that
+    // was checked out after verifying it is the one that was requested.
+    fn setup_build_cookbook_from_git(&self, config: &DeliveryConfig) -> DeliveryResult<String> {
         let git_url = config.build_cookbook_get("git")?;
-        let branch = config
-            .build_cookbook_get("branch")
-            .unwrap_or("master".to_owned());
         let build_cookbook_path = &self.chef.join("build_cookbook");
         git::git_command(
             &["clone", &git_url, &path_to_string(build_cookbook_path)],
             &self.chef,
         )?;
-        git::git_command(&["checkout", &branch], build_cookbook_path)?;
-        Ok(())
+        if let Ok(revision) = config.build_cookbook_get("revision") {
+            git::git_command(&["checkout", "--detach", &revision], build_cookbook_path)?;
+            let sha = self.build_cookbook_revision()?;
+            verify_revision(&sha, &revision)?;
+            Ok(sha)
+        } else if let Ok(tag) = config.build_cookbook_get("tag") {
+            let tag_ref = format!("refs/tags/{}", tag);
+            git::git_command(&["checkout", "--detach", &tag_ref], build_cookbook_path)?;
+            let sha = self.build_cookbook_revision()?;
+            let tag_commit = format!("{}^{{commit}}", tag_ref);
+            let tag_sha = git::git_command(&["rev-parse", &tag_commit], build_cookbook_path)?;
+            verify_revision(&sha, tag_sha.stdout.trim())?;
+            Ok(sha)
+        } else {
+            let branch = config
+                .build_cookbook_get("branch")
+                .unwrap_or("master".to_owned());
+            git::git_command(&["checkout", &branch], build_cookbook_path)?;
+            self.build_cookbook_revision()
+        }
+    }
+
+    // The SHA of the HEAD of a build_cookbook fetched with git
+    fn build_cookbook_revision(&self) -> DeliveryResult<String> {
+        let head = git::git_command(&["rev-parse", "HEAD"], &self.chef.join("build_cookbook"))?;
+        Ok(head.stdout.trim().to_string())
     }
 
     // This will need a windows implementation, and probably won't work on non-gnu tar systems
@@ -185,7 +215,7 @@ impl Workspace {
         &self,
         config: &DeliveryConfig,
         toml_config: &Config,
-    ) -> DeliveryResult<()> {
+    ) -> DeliveryResult<String> {
         let name = config.build_cookbook_name()?;
         let ent = config.build_cookbook_get("enterprise")?;
         let org = config.build_cookbook_get("organization")?;
@@ -203,31 +233,69 @@ impl Workspace {
             ],
             &self.chef,
         )?;
+        self.build_cookbook_revision()
+    }
+
+    // Download the tarball of the build_cookbook, verify its checksum
+    // and unpack it. A tarball usually has a single top-level directory
+    // named after the cookbook, that directory becomes the build_cookbook.
+    fn setup_build_cookbook_from_url(&self, config: &DeliveryConfig) -> DeliveryResult<()> {
+        let url = config.build_cookbook_get("url")?;
+        let sha256 = config.build_cookbook_get("sha256")?;
+        let tarball = self.chef.join("build_cookbook.tgz");
+        let unpack_dir = self.chef.join("tmp_build_cookbook");
+        http::download::to_file(&url, &tarball)?;
+        verify_sha256(&tarball, &sha256)?;
+
+        utils::remove_recursive(&unpack_dir)?;
+        unpack_tar_gz(&tarball, &unpack_dir)?;
+        let mut entries = fs::read_dir(&unpack_dir)?.collect::<Result<Vec<_>, _>>()?;
+        let cookbook_dir = if entries.len() == 1 && is_dir(&entries[0].path()) {
+            entries.remove(0).path()
+        } else {
+            unpack_dir.clone()
+        };
+        fs::rename(&cookbook_dir, &self.chef.join("build_cookbook")).map_err(|e| {
+            DeliveryError {
+                kind: Kind::MoveFailed,
+                detail: Some(format!("{}: {}", cookbook_dir.display(), e)),
+            }
+        })?;
+        utils::remove_recursive(&unpack_dir)?;
+        utils::remove_recursive(&tarball)?;
         Ok(())
     }
 
+    // Fetch the build_cookbook, returns the revision (SHA) that was
+    // fetched when the build_cookbook comes from a git repository.
     fn setup_build_cookbook(
         &self,
         toml_config: &Config,
         config: &DeliveryConfig,
-    ) -> DeliveryResult<()> {
+    ) -> DeliveryResult<Option<String>> {
         match config.build_cookbook_location()? {
             BuildCookbookLocation::Local => {
                 let ab_path = self.repo.join(config.build_cookbook_get("path")?);
-                self.setup_build_cookbook_from_path(&ab_path)
+                self.setup_build_cookbook_from_path(&ab_path)?;
+            }
+            BuildCookbookLocation::Git => {
+                return self.setup_build_cookbook_from_git(config).map(Some);
             }
-            BuildCookbookLocation::Git => self.setup_build_cookbook_from_git(config),
             BuildCookbookLocation::Supermarket => {
-                self.setup_build_cookbook_from_supermarket(config)
+                self.setup_build_cookbook_from_supermarket(config)?;
             }
             BuildCookbookLocation::Workflow => {
-                self.setup_build_cookbook_from_workflow(config, toml_config)
+                return self
+                    .setup_build_cookbook_from_workflow(config, toml_config)
+                    .map(Some);
             }
             BuildCookbookLocation::ChefServer => {
                 let name = config.build_cookbook_name()?;
-                self.setup_build_cookbook_from_chef_server(&name)
+                self.setup_build_cookbook_from_chef_server(&name)?;
             }
+            BuildCookbookLocation::Url => self.setup_build_cookbook_from_url(config)?,
         }
+        Ok(None)
     }
 
     fn berks_vendor(&self, bc_name: &str) -> DeliveryResult<()> {
@@ -360,11 +428,53 @@ impl Workspace {
         Ok(())
     }
 
+    // Vendor the cookbooks of the build_cookbook, reusing the ones from the
+    // cache when the build_cookbook and its Berksfile.lock (or Berksfile,
+    // without a lock) didn't change. With `refresh` the cache is not read
+    // but it is still updated.
+    fn vendor_cookbooks(
+        &self,
+        config: &DeliveryConfig,
+        revision: &Option<String>,
+        ws_path: &Path,
+        refresh: bool,
+    ) -> DeliveryResult<()> {
+        let build_cb_name = config.build_cookbook_name()?;
+        let build_cookbook = self.chef.join("build_cookbook");
+        let berksfile = match ["Berksfile.lock", "Berksfile"]
+            .iter()
+            .map(|f| build_cookbook.join(f))
+            .find(|f| is_file(f))
+        {
+            Some(f) => Some(utils::read_file(&f)?),
+            None => None,
+        };
+        let revision = match *revision {
+            Some(ref sha) => sha.clone(),
+            None => tree_digest(&build_cookbook)?,
+        };
+        let key = BuildCookbookCache::key(
+            &config.build_cookbook,
+            &revision,
+            berksfile.as_ref().map(|b| b.as_str()),
+        );
+        let cache = BuildCookbookCache::new(ws_path);
+        let cookbooks = self.chef.join("cookbooks");
+        if !refresh && cache.restore(&key, &cookbooks)? {
+            sayln("white", &format!("Using the cached cookbooks ({})", &key[..12]));
+            return Ok(());
+        }
+        self.berks_vendor(&build_cb_name)?;
+        cache.store(&key, &cookbooks)
+    }
+
     pub fn setup_chef_for_job(
         &self,
         toml_config: &Config,
         change: Change,
         ws_path: &PathBuf,
+        no_cache: bool,
+        paths: Option<PathsDecision>,
     ) -> Result<(), DeliveryError> {
         let config_rb_path = &self.chef.join("config.rb");
         debug!("Writing content of chef/config.rb");
@@ -373,9 +483,12 @@ impl Workspace {
         config_rb.write_all(CONFIG_RB.as_bytes())?;
         let config = DeliveryConfig::load_config(&self.repo)?;
         debug!("Setting up the build_cookbook");
-        self.setup_build_cookbook(toml_config, &config)?;
+        let build_cb_revision = self.setup_build_cookbook(toml_config, &config)?;
+        if let Some(ref sha) = build_cb_revision {
+            debug!("The build_cookbook is at revision {}", sha);
+        }
         let build_cb_name = config.build_cookbook_name()?;
-        self.berks_vendor(&build_cb_name)?;
+        self.vendor_cookbooks(&config, &build_cb_revision, ws_path, no_cache)?;
         let workspace_data = WorkspaceCompat {
             root: path_to_string(&self.root),
             chef: path_to_string(&self.chef),
@@ -383,10 +496,16 @@ impl Workspace {
             repo: path_to_string(&self.repo),
             ssh_wrapper: path_to_string(&self.ssh_wrapper),
         };
+        let build_cookbook_data = BuildCookbookCompat {
+            name: build_cb_name,
+            revision: build_cb_revision,
+        };
         let top = Top {
             workspace_path: path_to_string(ws_path),
             workspace: workspace_data,
             change: change,
+            build_cookbook: build_cookbook_data,
+            paths: paths,
             // TODO: When we have a reserved field to pass attributes
             // from the config to the build_cookbook. Replace it for:
             //
@@ -413,13 +532,15 @@ impl Workspace {
         Ok(())
     }
 
+    // Checkout the change merged into the pipeline branch, returns the files
+    // that the change modifies.
     pub fn setup_repo_for_change(
         &self,
         git_url: &str,
         change_branch: &str,
         pipeline: &str,
         sha: &str,
-    ) -> DeliveryResult<()> {
+    ) -> DeliveryResult<Option<Vec<String>>> {
         if !is_dir(&self.repo.join(".git")) {
             try!(git::git_command(&["clone", git_url, "."], &self.repo));
         }
@@ -439,8 +560,64 @@ impl Workspace {
         } else {
             try!(self.reset_repo(sha))
         }
-        Ok(())
+        Ok(self.changed_files(pipeline))
+    }
+
+    // The files that the change modifies compared to the pipeline branch
+    //
+    // When the change is already merged (the stages after approval check
+    // out the merge commit) it is compared to its first parent instead.
+    // Returns `None` if git is unable to tell, like for a root commit.
+    fn changed_files(&self, pipeline: &str) -> Option<Vec<String>> {
+        let pipeline_ref = format!("remotes/origin/{}", pipeline);
+        let diff = git::git_command(&["merge-base", &pipeline_ref, "HEAD"], &self.repo)
+            .and_then(|merge_base| {
+                let head = git::git_command(&["rev-parse", "HEAD"], &self.repo)?;
+                let base = if merge_base.stdout.trim() == head.stdout.trim() {
+                    String::from("HEAD^1")
+                } else {
+                    merge_base.stdout.trim().to_string()
+                };
+                git::git_command(&["diff", "--name-only", &base, "HEAD"], &self.repo)
+            });
+        match diff {
+            Ok(d) => Some(d.stdout.lines().map(String::from).collect()),
+            Err(e) => {
+                debug!("Unable to find the changed files: {:?}", e);
+                None
+            }
+        }
+    }
+}
+
+// Fail if the checked out SHA of the build_cookbook is not the requested
+// revision, which can be abbreviated.
+fn verify_revision(sha: &str, expected: &str) -> DeliveryResult<()> {
+    let expected = expected.to_lowercase();
+    if expected.is_empty() || !sha.starts_with(&expected) {
+        return Err(DeliveryError {
+            kind: Kind::BuildCookbookRevisionMismatch,
+            detail: Some(format!("expected: {}\n  checked out: {}", expected, sha)),
+        });
     }
+    Ok(())
+}
+
+// Fail if the sha256 checksum of a file is not the expected one
+fn verify_sha256(path: &Path, expected: &str) -> DeliveryResult<()> {
+    let actual = utils::sha256_file(path)?;
+    if actual != expected.to_lowercase() {
+        return Err(DeliveryError {
+            kind: Kind::ChecksumMismatch,
+            detail: Some(format!(
+                "{}\n  expected: {}\n  actual:   {}",
+                path.display(),
+                expected,
+                actual
+            )),
+        });
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -491,4 +668,33 @@ mod test {
         // Remove temp cli workspace
         utils::remove_recursive(&root).unwrap();
     }
+
+    #[test]
+    fn test_verify_sha256() {
+        let root = PathBuf::from("/tmp/cli-workspace-sha256");
+        utils::mkdir_recursive(&root).unwrap();
+        let tarball = root.join("build_cookbook.tgz");
+        File::create(&tarball).unwrap().write_all(b"test").unwrap();
+        let sha = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08";
+        assert!(verify_sha256(&tarball, sha).is_ok());
+        let err = verify_sha256(&tarball, &"0".repeat(64)).unwrap_err();
+        match err.kind {
+            Kind::ChecksumMismatch => {}
+            _ => panic!("expected a ChecksumMismatch error, got {:?}", err.kind),
+        }
+        utils::remove_recursive(&root).unwrap();
+    }
+
+    #[test]
+    fn test_verify_revision() {
+        let sha = "5c0e8e4b2e2d3f4c1b6f0f4f6f2b8e2a9a3d1c7e";
+        assert!(verify_revision(sha, sha).is_ok());
+        assert!(verify_revision(sha, "5C0E8E4").is_ok());
+        assert!(verify_revision(sha, "").is_err());
+        let err = verify_revision(sha, "deadbeef").unwrap_err();
+        match err.kind {
+            Kind::BuildCookbookRevisionMismatch => {}
+            _ => panic!("expected a BuildCookbookRevisionMismatch, got {:?}", err.kind),
+        }
+    }
 }
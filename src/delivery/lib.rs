@@ -40,6 +40,8 @@ extern crate mime;
 extern crate mockito;
 
 extern crate dirs;
+extern crate flate2;
+extern crate tar;
 
 #[macro_export]
 macro_rules! validate {
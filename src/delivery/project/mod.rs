@@ -28,6 +28,8 @@ use types::DeliveryResult;
 use utils::path_ext::is_dir;
 use utils::{self, cmd_success_or_err, mkdir_recursive, walk_tree_for_path};
 
+pub mod watch;
+
 // README with a brief description of delivery and how to use it. This is added
 // to a new project by `delivery init` so we have something to submit as the
 // first change.
@@ -0,0 +1,283 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Watch the files of a project
+//!
+//! There is no portable way to get notified when a file changes, so the
+//! `Watcher` takes snapshots of the files of the project (their size and
+//! modification time) and compares them. The files that the `.gitignore`
+//! files of the project ignore are left out, and so is the `.git`
+//! directory.
+use delivery_config::paths::glob_to_regex;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use types::DeliveryResult;
+use utils;
+
+// A file is identified by its size and its modification time
+type Snapshot = BTreeMap<PathBuf, (u64, Option<SystemTime>)>;
+
+pub struct Watcher {
+    root: PathBuf,
+    snapshot: Snapshot,
+}
+
+impl Watcher {
+    pub fn new(root: &Path) -> DeliveryResult<Watcher> {
+        Ok(Watcher {
+            root: root.to_path_buf(),
+            snapshot: try!(snapshot(root)),
+        })
+    }
+
+    /// The files that were created, modified or removed since the last
+    /// time we looked, relative to the root of the project.
+    pub fn changes(&mut self) -> DeliveryResult<Vec<PathBuf>> {
+        let snapshot = try!(snapshot(&self.root));
+        let mut changed: Vec<PathBuf> = snapshot
+            .iter()
+            .filter(|&(path, state)| self.snapshot.get(path) != Some(state))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(
+            self.snapshot
+                .keys()
+                .filter(|path| !snapshot.contains_key(*path))
+                .cloned(),
+        );
+        changed.sort();
+        self.snapshot = snapshot;
+        Ok(changed)
+    }
+
+    /// Wait until some files change and then until they stop changing
+    /// for `debounce`, so that saving many files at once (i.e. switching
+    /// branches) is reported only once.
+    pub fn wait_for_changes(
+        &mut self,
+        interval: Duration,
+        debounce: Duration,
+    ) -> DeliveryResult<Vec<PathBuf>> {
+        let mut changed = Vec::new();
+        loop {
+            thread::sleep(interval);
+            changed.extend(try!(self.changes()));
+            if !changed.is_empty() {
+                break;
+            }
+        }
+        loop {
+            thread::sleep(debounce);
+            let more = try!(self.changes());
+            if more.is_empty() {
+                break;
+            }
+            changed.extend(more);
+        }
+        changed.sort();
+        changed.dedup();
+        Ok(changed)
+    }
+}
+
+// A pattern of a .gitignore
+struct IgnoreRule {
+    // The directory of the .gitignore, relative to the root
+    base: PathBuf,
+    regex: Regex,
+    // A pattern with a `/` is relative to the base, the others match the
+    // name of a file in any directory below it.
+    anchored: bool,
+    dir_only: bool,
+    negated: bool,
+}
+
+fn parse_gitignore(base: &Path, content: &str) -> Vec<IgnoreRule> {
+    content
+        .lines()
+        .map(|l| l.trim_end())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|line| {
+            let negated = line.starts_with('!');
+            let mut pattern = line.trim_start_matches('!');
+            let dir_only = pattern.ends_with('/');
+            pattern = pattern.trim_end_matches('/');
+            let anchored = pattern.contains('/');
+            pattern = pattern.trim_start_matches('/');
+            // An invalid pattern is ignored, like git does
+            glob_to_regex(pattern).ok().map(|regex| IgnoreRule {
+                base: base.to_path_buf(),
+                regex: regex,
+                anchored: anchored,
+                dir_only: dir_only,
+                negated: negated,
+            })
+        })
+        .collect()
+}
+
+// The last rule that matches a path decides if it is ignored
+fn is_ignored(rules: &[IgnoreRule], path: &Path, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for rule in rules {
+        if rule.dir_only && !is_dir {
+            continue;
+        }
+        let relative = match path.strip_prefix(&rule.base) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        let subject = if rule.anchored {
+            utils::path_to_string(relative).replace('\\', "/")
+        } else {
+            relative
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        if rule.regex.is_match(&subject) {
+            ignored = !rule.negated;
+        }
+    }
+    ignored
+}
+
+fn snapshot(root: &Path) -> DeliveryResult<Snapshot> {
+    let mut snapshot = Snapshot::new();
+    let mut rules = Vec::new();
+    try!(walk(root, Path::new(""), &mut rules, &mut snapshot));
+    Ok(snapshot)
+}
+
+fn walk(
+    root: &Path,
+    dir: &Path,
+    rules: &mut Vec<IgnoreRule>,
+    snapshot: &mut Snapshot,
+) -> DeliveryResult<()> {
+    let mut entries: Vec<fs::DirEntry> = match fs::read_dir(root.join(dir)) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).collect(),
+        // So can a directory, only the root of the project must be there
+        Err(_) if dir.parent().is_some() => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let rules_before = rules.len();
+    if let Ok(content) = utils::read_file(&root.join(dir).join(".gitignore")) {
+        rules.extend(parse_gitignore(dir, &content));
+    }
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = dir.join(entry.file_name());
+        // A file can go away while we look at it, it shows up as removed
+        let metadata = match fs::symlink_metadata(entry.path()) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        if entry.file_name() == ".git" || is_ignored(rules, &path, metadata.is_dir()) {
+            continue;
+        }
+        if metadata.is_dir() {
+            try!(walk(root, &path, rules, snapshot));
+        } else {
+            snapshot.insert(path, (metadata.len(), metadata.modified().ok()));
+        }
+    }
+    rules.truncate(rules_before);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        utils::mkdir_recursive(path.parent().unwrap()).unwrap();
+        File::create(path)
+            .unwrap()
+            .write_all(content.as_bytes())
+            .unwrap();
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn gitignore_rules() {
+        let rules = parse_gitignore(
+            Path::new(""),
+            "# comment\n*.log\n!keep.log\n/target\nbuild/\ndocs/*.html\n",
+        );
+        assert!(is_ignored(&rules, Path::new("a/b/debug.log"), false));
+        assert!(!is_ignored(&rules, Path::new("a/keep.log"), false));
+        assert!(is_ignored(&rules, Path::new("target"), true));
+        assert!(!is_ignored(&rules, Path::new("src/target"), true));
+        assert!(is_ignored(&rules, Path::new("src/build"), true));
+        assert!(!is_ignored(&rules, Path::new("build"), false));
+        assert!(is_ignored(&rules, Path::new("docs/index.html"), false));
+        assert!(!is_ignored(&rules, Path::new("web/docs/index.html"), false));
+
+        let nested = parse_gitignore(Path::new("web"), "*.js\n");
+        assert!(is_ignored(&nested, Path::new("web/app.js"), false));
+        assert!(!is_ignored(&nested, Path::new("app.js"), false));
+    }
+
+    #[test]
+    fn detect_the_changes_of_a_project() {
+        let tempdir = TempDir::new("watch").unwrap();
+        let root = tempdir.path();
+        write(root, ".gitignore", "*.log\n/tmp/\n");
+        write(root, "web/.gitignore", "dist\n");
+        write(root, "README.md", "# readme");
+        write(root, "src/main.rs", "fn main() {}");
+        let mut watcher = Watcher::new(root).unwrap();
+        assert!(watcher.changes().unwrap().is_empty());
+
+        write(root, "debug.log", "ignored");
+        write(root, "tmp/cache", "ignored");
+        write(root, "web/dist/app.js", "ignored");
+        write(root, ".git/index", "ignored");
+        assert!(watcher.changes().unwrap().is_empty());
+
+        write(root, "src/main.rs", "fn main() { println!(); }");
+        write(root, "web/app.js", "app");
+        fs::remove_file(root.join("README.md")).unwrap();
+        assert_eq!(
+            paths(&["README.md", "src/main.rs", "web/app.js"]),
+            watcher.changes().unwrap()
+        );
+        assert!(watcher.changes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_directory_can_vanish_during_a_scan() {
+        let tempdir = TempDir::new("watch").unwrap();
+        let root = tempdir.path();
+        let mut rules = Vec::new();
+        let mut files = Snapshot::new();
+        assert!(walk(root, Path::new("gone"), &mut rules, &mut files).is_ok());
+        assert!(files.is_empty());
+        assert!(snapshot(&root.join("missing")).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//
+// Copyright:: Copyright (c) 2015 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Credential Helpers
+//!
+//! Run the `password_command` or the `token_command` from the cli.toml
+//! and read the secret they print on stdout, so the end-user can keep
+//! their credentials in a vault instead of typing them every time.
+//!
+//! The command is run through the shell in a process group of its own,
+//! with stdin and stderr attached to the terminal, that way the helper can
+//! still prompt for a master password. The secret itself is never logged.
+//! A Ctrl-C or a SIGTERM stops the helper, and delivery gives the terminal
+//! back before it exits.
+//!
+//! Only the user-global `~/.delivery/cli.toml`, its profiles and the
+//! environment can set the helpers, a cli.toml committed to a project
+//! can't make us run a command.
+//!
+use config::Config;
+use errors::{DeliveryError, Kind};
+use std::io::Read;
+use std::process::Stdio;
+use std::thread;
+use std::time::{Duration, Instant};
+use types::DeliveryResult;
+use utils;
+use utils::ProcessGroup;
+
+/// Seconds to wait for a credential helper when `credential_timeout`
+/// is not set in the cli.toml.
+pub const DEFAULT_TIMEOUT: u64 = 30;
+
+/// Get the password from the `password_command`, if configured.
+pub fn password(config: &Config) -> DeliveryResult<Option<String>> {
+    match config.password_command {
+        Some(ref cmd) => Ok(Some(run("password_command", cmd, timeout(config)?)?)),
+        None => Ok(None),
+    }
+}
+
+/// Get the API token from the `token_command`, if configured.
+pub fn token(config: &Config) -> DeliveryResult<Option<String>> {
+    match config.token_command {
+        Some(ref cmd) => Ok(Some(run("token_command", cmd, timeout(config)?)?)),
+        None => Ok(None),
+    }
+}
+
+fn timeout(config: &Config) -> DeliveryResult<Duration> {
+    let secs = match config.credential_timeout {
+        Some(ref t) => t.parse::<u64>().map_err(|_| DeliveryError {
+            kind: Kind::ConfigParse,
+            detail: Some(format!(
+                "credential_timeout must be a number of seconds, found '{}'",
+                t
+            )),
+        })?,
+        None => DEFAULT_TIMEOUT,
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+// Run the helper and return the first line of its stdout
+fn run(option: &str, cmd: &str, timeout: Duration) -> DeliveryResult<String> {
+    debug!("Running the {}: {}", option, cmd);
+    let mut command = utils::shell_command(cmd);
+    command
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    let mut group = ProcessGroup::spawn(&mut command, true)
+        .map_err(|e| failure(option, &format!("unable to run '{}': {}", cmd, e)))?;
+
+    // Read stdout on its own thread so a chatty helper can't block on a
+    // full pipe while we wait for it to exit.
+    let mut stdout = group.child.stdout.take().unwrap();
+    let reader = thread::spawn(move || {
+        let mut out = String::new();
+        stdout.read_to_string(&mut out).map(|_| out)
+    });
+
+    let status = wait_with_timeout(&mut group, timeout).map_err(|e| match e {
+        Some(e) => failure(option, &format!("unable to wait for '{}': {}", cmd, e)),
+        None if utils::interrupted() => DeliveryError {
+            kind: Kind::Interrupted,
+            detail: Some(format!("{}: '{}' was stopped", option, cmd)),
+        },
+        None => failure(
+            option,
+            &format!("'{}' timed out after {} seconds", cmd, timeout.as_secs()),
+        ),
+    })?;
+    if !status.success() {
+        return Err(failure(
+            option,
+            &format!("'{}' exited with {}", cmd, status),
+        ));
+    }
+
+    let output = match reader.join() {
+        Ok(Ok(out)) => out,
+        _ => return Err(failure(option, &format!("unable to read the output of '{}'", cmd))),
+    };
+    let secret = output.lines().next().unwrap_or("").trim().to_string();
+    if secret.is_empty() {
+        return Err(failure(option, &format!("'{}' printed nothing on stdout", cmd)));
+    }
+    debug!("The {} returned a secret of {} characters", option, secret.len());
+    Ok(secret)
+}
+
+// Returns `Err(None)` when the timeout expires or delivery is interrupted,
+// the whole process group is killed so nothing the helper started keeps
+// running, or holds stdout. Dropping the group then gives the terminal back.
+fn wait_with_timeout(
+    group: &mut ProcessGroup,
+    timeout: Duration,
+) -> Result<::std::process::ExitStatus, Option<::std::io::Error>> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = group.try_wait().map_err(Some)? {
+            if utils::interrupted() {
+                return Err(None);
+            }
+            return Ok(status);
+        }
+        if start.elapsed() >= timeout || utils::interrupted() {
+            let _ = group.kill();
+            return Err(None);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn failure(option: &str, msg: &str) -> DeliveryError {
+    DeliveryError {
+        kind: Kind::CredentialCommandFailed,
+        detail: Some(format!("{}: {}", option, msg)),
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    fn config_with(password_command: &str, timeout: &str) -> Config {
+        let mut config = Config::default();
+        config.password_command = Some(password_command.to_string());
+        config.credential_timeout = Some(timeout.to_string());
+        config
+    }
+
+    #[test]
+    fn without_helpers() {
+        assert_eq!(None, password(&Config::default()).unwrap());
+        assert_eq!(None, token(&Config::default()).unwrap());
+    }
+
+    #[test]
+    fn password_from_the_first_line_of_stdout() {
+        let config = config_with("printf 's3cr3t \\nsecond line'", "5");
+        assert_eq!(Some("s3cr3t".to_string()), password(&config).unwrap());
+    }
+
+    #[test]
+    fn failing_and_empty_helpers() {
+        let err = password(&config_with("exit 2", "5")).unwrap_err();
+        assert_enum!(err.kind, Kind::CredentialCommandFailed);
+        assert!(err.detail.unwrap().contains("exited with"));
+
+        let err = password(&config_with("true", "5")).unwrap_err();
+        assert!(err.detail.unwrap().contains("printed nothing"));
+    }
+
+    #[test]
+    fn helper_timeout() {
+        let err = password(&config_with("sleep 5", "1")).unwrap_err();
+        assert_eq!(
+            Some("password_command: 'sleep 5' timed out after 1 seconds".to_string()),
+            err.detail
+        );
+    }
+}
@@ -40,6 +40,8 @@ use utils::path_join_many::PathJoinMany;
 use utils::say::{say, sayln};
 use utils::{env_variable, home_dir};
 
+pub mod credential;
+
 #[derive(Debug)]
 pub struct TokenStore {
     tokens: BTreeMap<String, String>,
@@ -115,6 +117,12 @@ impl TokenStore {
     }
 
     pub fn request_token(config: &Config) -> DeliveryResult<String> {
+        // A token from the `token_command` is never written to disk,
+        // the credential helper is the one in charge of storing it.
+        if let Some(token) = try!(credential::token(&config)) {
+            sayln("yellow", "Using the token from the token_command");
+            return Ok(token);
+        }
         sayln("yellow", "Requesting Token");
         let ent = try!(config.enterprise());
         let user = try!(config.user());
@@ -141,11 +149,14 @@ impl TokenStore {
         } else {
             // For automation purposes if the end-user provides the environment
             // variable `AUTOMATE_PASSWORD` we will consume it instead of prompting
-            // to write the password in the terminal, but if the variable is not set
-            // we will still ask for it
+            // to write the password in the terminal, then we try the
+            // `password_command` and if none of them is set we will still ask for it
             let pass: String = match env_variable("AUTOMATE_PASSWORD") {
                 Some(p) => p,
-                None => try!(rpassword::prompt_password_stdout("Automate password: ")),
+                None => match try!(credential::password(&config)) {
+                    Some(p) => p,
+                    None => try!(rpassword::prompt_password_stdout("Automate password: ")),
+                },
             };
             try!(http::token::request(&config, &pass))
         };
@@ -0,0 +1,85 @@
+//
+// Copyright:: Copyright (c) 2016 Chef Software, Inc.
+// License:: Apache License, Version 2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use errors::{DeliveryError, Kind};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::path::Path;
+use tar::Archive;
+use types::DeliveryResult;
+
+/// Unpack a gzipped tarball (`.tgz` or `.tar.gz`) into the `dest`
+/// directory without depending on a `tar` binary.
+pub fn unpack_tar_gz<A, B>(archive: A, dest: B) -> DeliveryResult<()>
+where
+    A: AsRef<Path>,
+    B: AsRef<Path>,
+{
+    let file = File::open(&archive)?;
+    let mut tar = Archive::new(GzDecoder::new(file));
+    tar.unpack(&dest).map_err(|e| DeliveryError {
+        kind: Kind::TarFailed,
+        detail: Some(format!("{}: {}", archive.as_ref().display(), e)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tar::{Builder, Header};
+    use tempdir::TempDir;
+    use utils::read_file;
+
+    #[test]
+    fn unpack_a_tar_gz() {
+        let tempdir = TempDir::new("unpack_tar_gz").unwrap();
+        let archive = tempdir.path().join("build_cookbook.tgz");
+        {
+            let gz = GzEncoder::new(File::create(&archive).unwrap(), Compression::default());
+            let mut builder = Builder::new(gz);
+            let content = b"name 'build_cookbook'\n";
+            let mut header = Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "build_cookbook/metadata.rb", &content[..])
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let dest = tempdir.path().join("out");
+        unpack_tar_gz(&archive, &dest).unwrap();
+        assert_eq!(
+            "name 'build_cookbook'\n",
+            read_file(&dest.join("build_cookbook/metadata.rb")).unwrap()
+        );
+    }
+
+    #[test]
+    fn unpack_an_invalid_archive() {
+        let tempdir = TempDir::new("unpack_tar_gz").unwrap();
+        let archive = tempdir.path().join("not_a_tarball.tgz");
+        File::create(&archive).unwrap();
+        let err = unpack_tar_gz(&archive, tempdir.path()).unwrap_err();
+        match err.kind {
+            Kind::TarFailed => {}
+            _ => panic!("expected a TarFailed error, got {:?}", err.kind),
+        }
+    }
+}
@@ -28,6 +28,7 @@ use std::process::Output as CmdOutput;
 use types::DeliveryResult;
 use utils::path_join_many::PathJoinMany;
 
+pub mod archive;
 pub mod open;
 pub mod path_ext;
 pub mod path_join_many;
@@ -37,6 +38,7 @@ pub mod test_paths;
 
 use crypto::digest::Digest;
 use crypto::md5::Md5;
+use crypto::sha2::Sha256;
 
 use dirs;
 
@@ -190,6 +192,24 @@ where
     Ok(true)
 }
 
+// Returns the hex encoded sha256 checksum of the content of a file
+pub fn sha256_file<P>(path: P) -> DeliveryResult<String>
+where
+    P: AsRef<Path>,
+{
+    let mut sha = Sha256::new();
+    let mut file = File::open(&path)?;
+    let mut buffer = [0; 8192];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        sha.input(&buffer[..read]);
+    }
+    Ok(sha.result_str())
+}
+
 // Verify if the `process::Output` of a `process::Command`
 // was executed successfully, otherwise return the provided
 // error and printing the STDOUT & STDERR
@@ -228,6 +248,7 @@ mod tests {
     use super::*;
     use std::ffi::OsStr;
     use std::path::PathBuf;
+    use tempdir::TempDir;
     use utils::test_paths::fixture_file;
 
     #[test]
@@ -253,6 +274,17 @@ mod tests {
         let no_zero_exitcode = make_command("ls").arg("-").output().unwrap();
         assert!(super::cmd_success_or_err(&no_zero_exitcode, Kind::FailedToExecute).is_err());
     }
+
+    #[test]
+    fn sha256_of_a_file() {
+        let tempdir = TempDir::new("sha256_file").unwrap();
+        let path = tempdir.path().join("test.txt");
+        File::create(&path).unwrap().write_all(b"test").unwrap();
+        assert_eq!(
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+            sha256_file(&path).unwrap()
+        );
+    }
 }
 
 #[cfg(test)]
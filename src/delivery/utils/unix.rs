@@ -20,8 +20,12 @@ use libc;
 use std::convert::AsRef;
 use std::env;
 use std::error;
+use std::io;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::thread;
 use utils::path_to_string;
 
 pub fn copy_recursive<A, B>(f: &A, t: &B) -> Result<(), DeliveryError>
@@ -68,6 +72,20 @@ where
     super::cmd_success_or_err(&result, Kind::ChmodFailed)
 }
 
+// Set the modification time of a file or a directory to now
+pub fn touch<P: ?Sized>(path: &P) -> Result<(), DeliveryError>
+where
+    P: AsRef<Path>,
+{
+    let result = try!(
+        Command::new("touch")
+            .arg("-c")
+            .arg(path.as_ref().to_str().unwrap())
+            .output()
+    );
+    super::cmd_success_or_err(&result, Kind::TouchFailed)
+}
+
 pub fn chown_all<P: AsRef<Path>>(who: &str, paths: &[P]) -> Result<(), DeliveryError> {
     let mut command = Command::new("chown");
     command.arg("-R").arg(who);
@@ -96,6 +114,12 @@ pub fn privileged_process() -> bool {
     }
 }
 
+// Returns true if the standard input is attached to a terminal, that
+// is, there is someone in front of the keyboard that can answer questions.
+pub fn stdin_is_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
+}
+
 // Abstraction for command creation. Needed because of how we're
 // wrapping commands in Windows. See this function in the
 // corresponding windows module.
@@ -103,6 +127,200 @@ pub fn make_command(cmd: &str) -> Command {
     Command::new(cmd)
 }
 
+// Run a command line through the shell of the user (`$SHELL` or `/bin/sh`)
+// so that quotes, pipes and `&&` work like they do in a terminal.
+pub fn shell_command(cmd: &str) -> Command {
+    let shell = env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"));
+    shell_command_with(&shell, cmd)
+}
+
+// Run a command line through the given shell, whatever the one of the user is
+pub fn shell_command_with(shell: &str, cmd: &str) -> Command {
+    let mut c = Command::new(shell);
+    c.arg("-c").arg(cmd);
+    c
+}
+
+// A free slot of `PROCESS_GROUPS`
+const FREE: AtomicI32 = AtomicI32::new(0);
+
+// The process groups that are running, a signal that stops delivery is
+// forwarded to them since they don't get the ones from the terminal.
+static PROCESS_GROUPS: [AtomicI32; 64] = [
+    FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE,
+    FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE,
+    FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE,
+    FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE, FREE,
+];
+
+// The signals that stop delivery while an `InterruptGuard` is alive, the
+// handlers they had before are restored once the last guard is dropped.
+const STOP_SIGNALS: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+const NO_HANDLER: AtomicUsize = AtomicUsize::new(0);
+static PREVIOUS_HANDLERS: [AtomicUsize; 3] = [NO_HANDLER, NO_HANDLER, NO_HANDLER];
+static GUARDS: AtomicUsize = AtomicUsize::new(0);
+static GUARDS_LOCK: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// Only record the signal, the code that waits for the process groups
+// kills them and returns so that everything is cleaned up on the way out.
+extern "C" fn catch_signal(signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    for group in PROCESS_GROUPS.iter() {
+        let pgid = group.load(Ordering::SeqCst);
+        if pgid > 0 {
+            unsafe { libc::kill(-pgid, signal) };
+        }
+    }
+}
+
+// The guards are counted, and the handlers swapped, one thread at a time
+fn with_guards_lock<F: FnOnce()>(f: F) {
+    while GUARDS_LOCK
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        thread::yield_now();
+    }
+    f();
+    GUARDS_LOCK.store(false, Ordering::SeqCst);
+}
+
+/// Returns true once delivery got SIGINT, SIGTERM or SIGHUP while an
+/// `InterruptGuard` was alive.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// While it is alive SIGINT, SIGTERM and SIGHUP don't kill delivery, they
+/// are forwarded to the process groups and `interrupted` returns true, so
+/// that the code waiting for them can stop them and clean up.
+pub struct InterruptGuard;
+
+impl InterruptGuard {
+    pub fn new() -> InterruptGuard {
+        with_guards_lock(|| {
+            if GUARDS.fetch_add(1, Ordering::SeqCst) == 0 {
+                let handler = catch_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+                for (signal, previous) in STOP_SIGNALS.iter().zip(PREVIOUS_HANDLERS.iter()) {
+                    previous.store(unsafe { libc::signal(*signal, handler) }, Ordering::SeqCst);
+                }
+            }
+        });
+        InterruptGuard
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        with_guards_lock(|| {
+            if GUARDS.fetch_sub(1, Ordering::SeqCst) == 1 {
+                for (signal, previous) in STOP_SIGNALS.iter().zip(PREVIOUS_HANDLERS.iter()) {
+                    unsafe { libc::signal(*signal, previous.load(Ordering::SeqCst)) };
+                }
+            }
+        });
+    }
+}
+
+// Returns true if delivery runs in the foreground of the terminal
+// attached to its standard input.
+fn owns_terminal() -> bool {
+    unsafe {
+        libc::isatty(libc::STDIN_FILENO) == 1
+            && libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp()
+    }
+}
+
+// Make `pgid` the foreground process group of the terminal. Only the
+// foreground group may do it without being stopped, so SIGTTOU is ignored
+// while the terminal changes hands.
+unsafe fn give_terminal_to(pgid: libc::pid_t) {
+    let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+    libc::tcsetpgrp(libc::STDIN_FILENO, pgid);
+    libc::signal(libc::SIGTTOU, previous);
+}
+
+/// A child process that runs in a process group of its own, so that it
+/// can be killed along with every process it started.
+///
+/// A process group in the background of the terminal is stopped as soon
+/// as it reads from it, so a `foreground` group is handed the terminal
+/// (when delivery owns it) and handed it back once it is dropped, while
+/// a background group gets its standard input from `/dev/null`.
+pub struct ProcessGroup {
+    pub child: Child,
+    slot: Option<usize>,
+    terminal: bool,
+    _guard: InterruptGuard,
+}
+
+impl ProcessGroup {
+    pub fn spawn(cmd: &mut Command, foreground: bool) -> io::Result<ProcessGroup> {
+        let guard = InterruptGuard::new();
+        let terminal = foreground && owns_terminal();
+        if !foreground {
+            cmd.stdin(Stdio::null());
+        }
+        // The child takes the terminal itself, before it runs the command,
+        // so there is no window where it could read from it in the
+        // background. `spawn` doesn't return until then.
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::setpgid(0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if terminal {
+                    give_terminal_to(libc::getpid());
+                }
+                Ok(())
+            });
+        }
+        let child = cmd.spawn()?;
+        let pgid = child.id() as i32;
+        let slot = PROCESS_GROUPS.iter().position(|g| {
+            g.compare_exchange(0, pgid, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        });
+        Ok(ProcessGroup {
+            child: child,
+            slot: slot,
+            terminal: terminal,
+            _guard: guard,
+        })
+    }
+
+    /// The exit status of the child if it exited. A group that has the
+    /// terminal gets the Ctrl-C instead of delivery, so its child dying
+    /// from SIGINT counts as an interrupt of delivery.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        let status = self.child.try_wait()?;
+        if self.terminal && status.and_then(|s| s.signal()) == Some(libc::SIGINT) {
+            INTERRUPTED.store(true, Ordering::SeqCst);
+        }
+        Ok(status)
+    }
+
+    /// Kill every process of the group and wait for the child
+    pub fn kill(&mut self) -> io::Result<()> {
+        if unsafe { libc::kill(-(self.child.id() as i32), libc::SIGKILL) } != 0 {
+            self.child.kill()?;
+        }
+        self.child.wait().map(|_| ())
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if self.terminal {
+            unsafe { give_terminal_to(libc::getpgrp()) };
+        }
+        if let Some(slot) = self.slot {
+            PROCESS_GROUPS[slot].store(0, Ordering::SeqCst);
+        }
+    }
+}
+
 /// Returns the absolute path for a given command, if it exists, by searching the `PATH`
 /// environment variable.
 ///
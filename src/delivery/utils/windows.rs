@@ -16,12 +16,13 @@
 //
 
 use errors::{DeliveryError, Kind};
+use libc;
 use std::convert::AsRef;
 use std::env;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus, Stdio};
 
 pub fn copy_recursive<A, B>(f: &A, t: &B) -> Result<(), DeliveryError>
 where
@@ -76,6 +77,12 @@ where
     }
 }
 
+// Returns true if the standard input is attached to a terminal, that
+// is, there is someone in front of the keyboard that can answer questions.
+pub fn stdin_is_terminal() -> bool {
+    unsafe { libc::isatty(0) != 0 }
+}
+
 pub fn make_command(cmd: &str) -> Command {
     // could do "cmd.exe /c cmd" instead and less overhead.
     let mut c = Command::new("powershell.exe");
@@ -83,6 +90,60 @@ pub fn make_command(cmd: &str) -> Command {
     c
 }
 
+// Run a command line through the shell, on Windows that is what
+// `make_command` does already.
+pub fn shell_command(cmd: &str) -> Command {
+    make_command(cmd)
+}
+
+// PowerShell is the only shell that runs command lines on Windows
+#[allow(unused_variables)]
+pub fn shell_command_with(shell: &str, cmd: &str) -> Command {
+    make_command(cmd)
+}
+
+/// Ctrl-C isn't caught on Windows, delivery stops along with its children
+pub fn interrupted() -> bool {
+    false
+}
+
+pub struct InterruptGuard;
+
+impl InterruptGuard {
+    pub fn new() -> InterruptGuard {
+        InterruptGuard
+    }
+}
+
+/// A child process and the processes it started, on Windows only the
+/// child itself can be killed. A background one gets its standard input
+/// from `NUL`, like on unix.
+pub struct ProcessGroup {
+    pub child: Child,
+}
+
+impl ProcessGroup {
+    pub fn spawn(cmd: &mut Command, foreground: bool) -> io::Result<ProcessGroup> {
+        if !foreground {
+            cmd.stdin(Stdio::null());
+        }
+        Ok(ProcessGroup {
+            child: cmd.spawn()?,
+        })
+    }
+
+    /// The exit status of the child if it exited
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+
+    /// Kill the child and wait for it
+    pub fn kill(&mut self) -> io::Result<()> {
+        self.child.kill()?;
+        self.child.wait().map(|_| ())
+    }
+}
+
 /// Returns the absolute path for a given command, if it exists, by searching the `PATH`
 /// environment variable.
 ///
@@ -163,6 +224,19 @@ where
     Ok(())
 }
 
+// Set the modification time of a file or a directory to now
+pub fn touch<P: ?Sized>(path: &P) -> Result<(), DeliveryError>
+where
+    P: AsRef<Path>,
+{
+    let result = try!(make_command(&format!(
+        "(Get-Item '{}').LastWriteTime = Get-Date",
+        path.as_ref().to_str().unwrap()
+    ))
+    .output());
+    super::cmd_success_or_err(&result, Kind::TouchFailed)
+}
+
 #[allow(unused_variables)]
 pub fn chown_all<P: AsRef<Path>>(who: &str, paths: &[P]) -> Result<(), DeliveryError> {
     Ok(())